@@ -0,0 +1,180 @@
+use crate::error::{parse_number, Error, Result};
+use crate::input::InputSource;
+use crate::solutions::{Answer, Entry};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The known answers for the puzzle inputs in the `input` directory, which the solutions are
+/// checked against. They're read from a file like
+///
+/// ```toml
+/// [day1]
+/// part1 = 913824
+/// part2 = 240889536
+///
+/// [day21]
+/// part2 = "kbdgs,sqvv,slkfgq"
+/// ```
+///
+/// Only this small part of TOML is understood: a `[dayN]` header per day, then a
+/// `partN = ` line for each known answer, which is either a number or a quoted string.
+/// Blank lines and lines starting with `#` are ignored.
+pub struct Answers {
+    known: HashMap<(u8, u8), String>,
+}
+
+impl Answers {
+    pub fn load(path: &Path) -> Result<Answers> {
+        let contents = fs::read_to_string(path).map_err(|error| Error::Io {
+            source: path.display().to_string(),
+            error,
+        })?;
+        Answers::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Answers> {
+        let mut known = HashMap::new();
+        let mut day = None;
+        for (i, line) in contents.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            match parse_line(trimmed).map_err(|e| e.offset_lines(i))? {
+                Line::Day(d) => day = Some(d),
+                Line::Answer(part, answer) => match day {
+                    Some(d) => {
+                        known.insert((d, part), answer);
+                    }
+                    None => {
+                        return Err(Error::parse(
+                            trimmed,
+                            trimmed,
+                            "answers must come after a [dayN] header",
+                        )
+                        .offset_lines(i))
+                    }
+                },
+            }
+        }
+        Ok(Answers { known })
+    }
+
+    pub fn get(&self, day: u8, part: u8) -> Option<&str> {
+        self.known.get(&(day, part)).map(|s| s.as_str())
+    }
+
+    /// Runs a puzzle part, and compares what it gives with the recorded answer.
+    pub fn check(&self, entry: &Entry, source: &InputSource) -> Check {
+        let answer = match entry.run(source) {
+            Ok(answer) => answer,
+            Err(error) => return Check::Error(error),
+        };
+        match self.get(entry.day, entry.part) {
+            Some(expected) if expected == answer.to_string() => Check::Pass,
+            Some(expected) => Check::Fail {
+                expected: expected.to_owned(),
+                actual: answer,
+            },
+            None => Check::Missing(answer),
+        }
+    }
+}
+
+/// How the answer a puzzle part gives compares with the recorded one.
+pub enum Check {
+    Pass,
+    Fail { expected: String, actual: Answer },
+    // there's no recorded answer to compare with
+    Missing(Answer),
+    // the part didn't give an answer at all
+    Error(Error),
+}
+
+pub fn default_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("answers.toml")
+}
+
+enum Line {
+    Day(u8),
+    Answer(u8, String),
+}
+
+fn parse_line(line: &str) -> Result<Line> {
+    if let Some(header) = line.strip_prefix('[') {
+        return match header.strip_suffix(']').and_then(|h| h.strip_prefix("day")) {
+            Some(day) => Ok(Line::Day(parse_number(line, day)?)),
+            None => Err(Error::parse(line, line, "expected a header like [day1]")),
+        };
+    }
+
+    let (key, value) = match line.split_once('=') {
+        Some((key, value)) => (key.trim(), value.trim()),
+        None => return Err(Error::parse(line, line, "expected 'partN = answer'")),
+    };
+    let part = match key.strip_prefix("part") {
+        Some(part) => parse_number(line, part)?,
+        None => return Err(Error::parse(line, key, "expected 'part1' or 'part2'")),
+    };
+    let answer = match value.strip_prefix('"') {
+        Some(rest) => match rest.strip_suffix('"') {
+            Some(text) => text.to_owned(),
+            None => return Err(Error::parse(line, value, "missing closing quote")),
+        },
+        // answers too big for an i64 are given as text, which is just their digits
+        None => match parse_number::<i64>(line, value) {
+            Ok(number) => number.to_string(),
+            Err(_) if is_integer(value) => value.to_owned(),
+            Err(e) => return Err(e),
+        },
+    };
+    Ok(Line::Answer(part, answer))
+}
+
+fn is_integer(value: &str) -> bool {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_numbers_and_strings() {
+        let answers = Answers::parse(
+            "# a comment\n[day1]\npart1 = 514579\n\n[day21]\npart2 = \"mxmxvkd,sqjhc\"\n",
+        )
+        .unwrap();
+        assert_eq!(answers.get(1, 1), Some("514579"));
+        assert_eq!(answers.get(1, 2), None);
+        assert_eq!(answers.get(21, 2), Some("mxmxvkd,sqjhc"));
+    }
+
+    #[test]
+    fn reads_numbers_too_big_for_an_i64() {
+        let answers = Answers::parse("[day13]\npart2 = 123456789012345678901234567890\n").unwrap();
+        assert_eq!(answers.get(13, 2), Some("123456789012345678901234567890"));
+    }
+
+    #[test]
+    fn rejects_answers_without_a_day() {
+        assert!(matches!(
+            Answers::parse("\npart1 = 3"),
+            Err(Error::Parse { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        assert!(matches!(
+            Answers::parse("[day3]\npart1 = abc"),
+            Err(Error::Parse {
+                line: 2,
+                column: 9,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,422 @@
+use crate::grid::{Grid, ALL_DIRECTIONS};
+use std::collections::{HashMap, HashSet};
+
+/// A position in N-dimensional space.
+pub type Point<const N: usize> = [isize; N];
+
+/// Which cells count as the neighbours of each cell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Neighbourhood {
+    // every cell touching this one, diagonally included: 8 of them in 2 dimensions, 26 in 3
+    Moore,
+    // the six cells around a hexagon, in 2 dimensions only. Hexagons are given axial
+    // co-ordinates, where the first axis runs east and the second south-east
+    Hex,
+    // the first cell seen looking out in each of the Moore directions, looking past any
+    // empty space (positions which hold no cell) on the way
+    LineOfSight,
+}
+
+impl Neighbourhood {
+    // the steps to take from a cell to find its neighbours, or to start looking for them
+    fn directions<const N: usize>(&self) -> Vec<Point<N>> {
+        match self {
+            Neighbourhood::Moore | Neighbourhood::LineOfSight => {
+                let mut directions = vec![[0; N]];
+                for axis in 0..N {
+                    directions = directions
+                        .into_iter()
+                        .flat_map(|d| {
+                            (-1..=1).map(move |step| {
+                                let mut d = d;
+                                d[axis] = step;
+                                d
+                            })
+                        })
+                        .collect();
+                }
+                directions.retain(|d| d.iter().any(|&step| step != 0));
+                directions
+            }
+            Neighbourhood::Hex => {
+                assert_eq!(N, 2, "hexagonal neighbourhoods are only for 2 dimensions");
+                [(-1, 0), (0, -1), (1, -1), (1, 0), (0, 1), (-1, 1)]
+                    .iter()
+                    .map(|&(x, y)| {
+                        let mut d = [0; N];
+                        d[0] = x;
+                        d[1] = y;
+                        d
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+fn add<const N: usize>(a: &Point<N>, b: &Point<N>) -> Point<N> {
+    let mut sum = *a;
+    for (s, b) in sum.iter_mut().zip(b) {
+        *s += b;
+    }
+    sum
+}
+
+/// How many live neighbours bring a dead cell to life, and how many keep a live one alive.
+/// Any other number leaves a cell dead.
+#[derive(Clone, Debug)]
+pub struct Rule {
+    birth: Vec<usize>,
+    survival: Vec<usize>,
+}
+
+impl Rule {
+    pub fn new(birth: &[usize], survival: &[usize]) -> Rule {
+        Rule {
+            birth: birth.to_vec(),
+            survival: survival.to_vec(),
+        }
+    }
+
+    /// Conway's Game of Life: born with 3 live neighbours, surviving with 2 or 3.
+    pub fn conway() -> Rule {
+        Rule::new(&[3], &[2, 3])
+    }
+
+    fn next(&self, alive: bool, live_neighbours: usize) -> bool {
+        if alive {
+            self.survival.contains(&live_neighbours)
+        } else {
+            self.birth.contains(&live_neighbours)
+        }
+    }
+}
+
+/// A grid of cells which are each alive or dead, all updated together a generation at a time.
+pub trait Automaton {
+    /// Moves on a generation, saying whether any cell changed.
+    fn step(&mut self) -> bool;
+
+    fn count_alive(&self) -> usize;
+
+    fn run(&mut self, generations: usize) {
+        for _ in 0..generations {
+            self.step();
+        }
+    }
+
+    /// Keeps going until nothing changes, giving the number of generations that took.
+    fn run_until_stable(&mut self) -> usize {
+        let mut generations = 0;
+        while self.step() {
+            generations += 1;
+        }
+        generations
+    }
+}
+
+/*
+For unbounded space which is mostly dead, only the live cells are kept. Each generation,
+every live cell adds one to the count of each of its neighbours, so the only positions
+looked at are those with a live neighbour - plus the live cells themselves, in case they
+survive with none.
+
+A Moore neighbourhood is a box 3 cells wide in every dimension, less the cell in the
+middle, and adding up a box can be done one axis at a time: first each cell's count is
+spread along the first axis, then those sums along the second, and so on. That's 3 steps
+per axis rather than one for each of the 3^N - 1 neighbours.
+
+When the cells are symmetric about 0 along an axis, and stay that way because the rule
+treats both directions alike, only the cells on the positive side of it (or on 0) need to
+be kept. A cell at 1 stands for one at -1 too, so it's also counted as a neighbour of the
+cells at 0. Each such axis halves the work, which is what makes 6 dimensions quick enough
+to run.
+
+Every position holds a cell, so there's no empty space to look past, and a line-of-sight
+neighbourhood is the same as a Moore one.
+*/
+pub struct Sparse<const N: usize> {
+    alive: HashSet<Point<N>>,
+    neighbourhood: Neighbourhood,
+    mirrored: [bool; N],
+    rule: Rule,
+}
+
+impl<const N: usize> Sparse<N> {
+    pub fn new(
+        alive: impl IntoIterator<Item = Point<N>>,
+        neighbourhood: Neighbourhood,
+        rule: Rule,
+    ) -> Sparse<N> {
+        assert!(
+            !rule.birth.contains(&0),
+            "cells born with no live neighbours would fill the whole of space"
+        );
+        Sparse {
+            alive: alive.into_iter().collect(),
+            neighbourhood,
+            mirrored: [false; N],
+            rule,
+        }
+    }
+
+    /// Keeps only the half of space with a co-ordinate of 0 or more along `axis`, for cells
+    /// which are the same on both sides of it. The live cells must all be on that half.
+    pub fn mirrored(mut self, axis: usize) -> Sparse<N> {
+        assert_ne!(
+            self.neighbourhood,
+            Neighbourhood::Hex,
+            "hexagonal neighbourhoods aren't symmetric along an axis"
+        );
+        assert!(
+            self.alive.iter().all(|cell| cell[axis] >= 0),
+            "the live cells must all be on the positive side of a mirrored axis"
+        );
+        self.mirrored[axis] = true;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn is_alive(&self, point: &Point<N>) -> bool {
+        self.alive.contains(point)
+    }
+
+    // the number of live neighbours of every position with any
+    fn live_neighbours(&self) -> HashMap<Point<N>, usize> {
+        match self.neighbourhood {
+            Neighbourhood::Moore | Neighbourhood::LineOfSight => {
+                let mut sums: HashMap<Point<N>, usize> =
+                    self.alive.iter().map(|&cell| (cell, 1)).collect();
+                for axis in 0..N {
+                    let mut spread = HashMap::with_capacity(sums.len() * 2);
+                    for (point, count) in sums {
+                        for step in -1..=1 {
+                            let mut to = point;
+                            to[axis] += step;
+                            if !(self.mirrored[axis] && to[axis] < 0) {
+                                *spread.entry(to).or_insert(0) += count;
+                            }
+                        }
+                        if self.mirrored[axis] && point[axis] == 1 {
+                            // the twin of this one at -1 is next to 0 as well
+                            let mut to = point;
+                            to[axis] = 0;
+                            *spread.entry(to).or_insert(0) += count;
+                        }
+                    }
+                    sums = spread;
+                }
+                // each live cell was counted in its own box
+                for cell in &self.alive {
+                    sums.entry(*cell).and_modify(|count| *count -= 1);
+                }
+                sums
+            }
+            Neighbourhood::Hex => {
+                let directions = self.neighbourhood.directions();
+                let mut counts = HashMap::new();
+                for cell in &self.alive {
+                    for direction in &directions {
+                        *counts.entry(add(cell, direction)).or_insert(0) += 1;
+                    }
+                }
+                counts
+            }
+        }
+    }
+}
+
+impl<const N: usize> Automaton for Sparse<N> {
+    fn step(&mut self) -> bool {
+        let counts = self.live_neighbours();
+        let lonely = self.alive.iter().filter(|&cell| !counts.contains_key(cell));
+        let next: HashSet<Point<N>> = lonely
+            .map(|&cell| (cell, 0))
+            .chain(counts.iter().map(|(&cell, &count)| (cell, count)))
+            .filter(|(cell, count)| self.rule.next(self.alive.contains(cell), *count))
+            .map(|(cell, _)| cell)
+            .collect();
+        let changed = next != self.alive;
+        self.alive = next;
+        changed
+    }
+
+    fn count_alive(&self) -> usize {
+        // a cell off a mirrored axis has a twin on its other side
+        self.alive
+            .iter()
+            .map(|cell| {
+                let twins = (0..N).filter(|&axis| self.mirrored[axis] && cell[axis] != 0);
+                1 << twins.count()
+            })
+            .sum()
+    }
+}
+
+/*
+For a fixed rectangle of space, every position is stored. A position can hold a live or
+dead cell, or nothing at all - which never changes, and which line-of-sight neighbourhoods
+look past. Because of that, the neighbours of each cell never change either, so they're
+found once at the start, from the grid's own neighbours and rays, rather than every
+generation.
+*/
+pub struct Dense {
+    // row by row, as in the grid the cells came from
+    cells: Vec<Option<bool>>,
+    neighbours: Vec<Vec<usize>>,
+    rule: Rule,
+}
+
+impl Dense {
+    /// Positions of the grid holding `None` have no cell.
+    pub fn new(grid: &Grid<Option<bool>>, neighbourhood: Neighbourhood, rule: Rule) -> Dense {
+        let width = grid.width();
+        let index = |(row, col): (usize, usize)| row * width + col;
+        let is_cell = |position: &(usize, usize)| grid[*position].is_some();
+        let hex: Vec<(isize, isize)> = Neighbourhood::Hex
+            .directions::<2>()
+            .iter()
+            .map(|&[row, col]| (row, col))
+            .collect();
+        let neighbours = grid
+            .positions()
+            .map(|position| {
+                if grid[position].is_none() {
+                    return vec![];
+                }
+                let found: Vec<(usize, usize)> = match neighbourhood {
+                    Neighbourhood::Moore => grid.neighbours_8(position).filter(is_cell).collect(),
+                    Neighbourhood::Hex => grid.neighbours(position, &hex).filter(is_cell).collect(),
+                    Neighbourhood::LineOfSight => ALL_DIRECTIONS
+                        .iter()
+                        .filter_map(|&direction| grid.ray(position, direction).find(is_cell))
+                        .collect(),
+                };
+                found.into_iter().map(index).collect()
+            })
+            .collect();
+        Dense {
+            cells: grid.iter().copied().collect(),
+            neighbours,
+            rule,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn get(&self, index: usize) -> Option<bool> {
+        self.cells[index]
+    }
+}
+
+impl Automaton for Dense {
+    fn step(&mut self) -> bool {
+        let next: Vec<Option<bool>> = self
+            .cells
+            .iter()
+            .zip(&self.neighbours)
+            .map(|(cell, neighbours)| {
+                cell.map(|alive| {
+                    let live_neighbours = neighbours
+                        .iter()
+                        .filter(|&&n| self.cells[n] == Some(true))
+                        .count();
+                    self.rule.next(alive, live_neighbours)
+                })
+            })
+            .collect();
+        let changed = next != self.cells;
+        self.cells = next;
+        changed
+    }
+
+    fn count_alive(&self) -> usize {
+        self.cells.iter().filter(|&&c| c == Some(true)).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neighbourhood_sizes() {
+        assert_eq!(Neighbourhood::Moore.directions::<1>().len(), 2);
+        assert_eq!(Neighbourhood::Moore.directions::<2>().len(), 8);
+        assert_eq!(Neighbourhood::Moore.directions::<4>().len(), 80);
+        assert_eq!(Neighbourhood::Hex.directions::<2>().len(), 6);
+    }
+
+    #[test]
+    fn sparse_blinker() {
+        let mut life = Sparse::new(
+            vec![[0, -1], [0, 0], [0, 1]],
+            Neighbourhood::Moore,
+            Rule::conway(),
+        );
+        life.step();
+        assert!(life.is_alive(&[-1, 0]) && life.is_alive(&[1, 0]));
+        assert!(!life.is_alive(&[0, 1]));
+        life.step();
+        assert!(life.is_alive(&[0, 1]));
+        assert_eq!(life.count_alive(), 3);
+    }
+
+    #[test]
+    fn mirroring_keeps_the_count() {
+        // an L of three cells flat in the first two dimensions, spreading out along the third
+        let cells = vec![[0, 0, 0], [0, 1, 0], [1, 0, 0]];
+        let mut whole = Sparse::new(cells.clone(), Neighbourhood::Moore, Rule::conway());
+        let mut half = Sparse::new(cells, Neighbourhood::Moore, Rule::conway()).mirrored(2);
+        for _ in 0..4 {
+            whole.step();
+            half.step();
+            assert_eq!(half.count_alive(), whole.count_alive());
+        }
+        assert!(half.alive.iter().all(|cell| cell[2] >= 0));
+    }
+
+    #[test]
+    fn dense_matches_sparse() {
+        let glider = [[0, 1], [1, 2], [2, 0], [2, 1], [2, 2]];
+        let mut sparse = Sparse::new(glider.to_vec(), Neighbourhood::Moore, Rule::conway());
+        let mut cells = Grid::from_rows(vec![vec![Some(false); 10]; 10]).unwrap();
+        for [row, col] in glider {
+            cells[(row as usize, col as usize)] = Some(true);
+        }
+        let mut dense = Dense::new(&cells, Neighbourhood::Moore, Rule::conway());
+        for _ in 0..8 {
+            sparse.step();
+            dense.step();
+        }
+        let alive: HashSet<Point<2>> = (0..10)
+            .flat_map(|row| (0..10).map(move |col| [row, col]))
+            .filter(|&[row, col]| dense.get(row as usize * 10 + col as usize) == Some(true))
+            .collect();
+        assert_eq!(alive, sparse.alive);
+    }
+
+    #[test]
+    fn line_of_sight_looks_past_empty_space() {
+        // a live cell, two positions with no cell, then a dead one which is born when it
+        // sees exactly one live neighbour
+        let cells = Grid::from_rows(vec![vec![Some(true), None, None, Some(false)]]).unwrap();
+        let rule = Rule::new(&[1], &[]);
+        let mut far_sighted = Dense::new(&cells, Neighbourhood::LineOfSight, rule.clone());
+        assert!(far_sighted.step());
+        assert_eq!(far_sighted.get(3), Some(true));
+        assert_eq!(far_sighted.get(1), None);
+
+        let mut near_sighted = Dense::new(&cells, Neighbourhood::Moore, rule);
+        near_sighted.step();
+        assert_eq!(near_sighted.get(3), Some(false));
+    }
+
+    #[test]
+    fn running_until_stable() {
+        // a 2x2 block never changes
+        let block = Grid::from_rows(vec![vec![Some(true); 2]; 2]).unwrap();
+        let mut dense = Dense::new(&block, Neighbourhood::Moore, Rule::conway());
+        assert_eq!(dense.run_until_stable(), 0);
+        assert_eq!(dense.count_alive(), 4);
+    }
+}
@@ -0,0 +1,202 @@
+use std::fmt;
+use std::time::Duration;
+
+/// How benchmark results are written out: a table to read, or JSON or CSV to keep and
+/// compare across commits. Times in JSON and CSV are whole nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "table" => Some(Format::Table),
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// The part of a day's solution that was timed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    Parse,
+    Part(u8),
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stage::Parse => write!(f, "parse"),
+            Stage::Part(part) => write!(f, "part{}", part),
+        }
+    }
+}
+
+/// Every time taken by one stage of one day, over all the runs of it.
+pub struct Measurement {
+    pub day: u8,
+    pub stage: Stage,
+    times: Vec<Duration>,
+}
+
+impl Measurement {
+    pub fn new(day: u8, stage: Stage) -> Measurement {
+        Measurement {
+            day,
+            stage,
+            times: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, time: Duration) {
+        self.times.push(time);
+    }
+
+    pub fn runs(&self) -> usize {
+        self.times.len()
+    }
+
+    // the times in order, from which the statistics below are read off
+    fn sorted(&self) -> Vec<Duration> {
+        let mut times = self.times.clone();
+        times.sort_unstable();
+        times
+    }
+
+    pub fn min(&self) -> Duration {
+        self.sorted().first().copied().unwrap_or_default()
+    }
+
+    pub fn max(&self) -> Duration {
+        self.sorted().last().copied().unwrap_or_default()
+    }
+
+    // with an even number of runs, this is halfway between the middle two
+    pub fn median(&self) -> Duration {
+        let times = self.sorted();
+        let middle = times.len() / 2;
+        match times.len() {
+            0 => Duration::default(),
+            n if n % 2 == 1 => times[middle],
+            _ => (times[middle - 1] + times[middle]) / 2,
+        }
+    }
+}
+
+pub fn render(measurements: &[Measurement], format: Format) -> String {
+    match format {
+        Format::Table => render_table(measurements),
+        Format::Json => render_json(measurements),
+        Format::Csv => render_csv(measurements),
+    }
+}
+
+fn render_table(measurements: &[Measurement]) -> String {
+    let mut out = format!(
+        "{:>3}  {:<6} {:>5} {:>11} {:>11} {:>11}\n",
+        "day", "stage", "runs", "min", "median", "max"
+    );
+    for m in measurements {
+        out.push_str(&format!(
+            "{:>3}  {:<6} {:>5} {:>11} {:>11} {:>11}\n",
+            m.day,
+            m.stage.to_string(),
+            m.runs(),
+            format!("{:.2?}", m.min()),
+            format!("{:.2?}", m.median()),
+            format!("{:.2?}", m.max()),
+        ));
+    }
+    out
+}
+
+fn render_json(measurements: &[Measurement]) -> String {
+    let rows: Vec<String> = measurements
+        .iter()
+        .map(|m| {
+            format!(
+                "  {{\"day\": {}, \"stage\": \"{}\", \"runs\": {}, \"min_ns\": {}, \"median_ns\": {}, \"max_ns\": {}}}",
+                m.day,
+                m.stage,
+                m.runs(),
+                m.min().as_nanos(),
+                m.median().as_nanos(),
+                m.max().as_nanos()
+            )
+        })
+        .collect();
+    if rows.is_empty() {
+        String::from("[]\n")
+    } else {
+        format!("[\n{}\n]\n", rows.join(",\n"))
+    }
+}
+
+fn render_csv(measurements: &[Measurement]) -> String {
+    let mut out = String::from("day,stage,runs,min_ns,median_ns,max_ns\n");
+    for m in measurements {
+        out.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            m.day,
+            m.stage,
+            m.runs(),
+            m.min().as_nanos(),
+            m.median().as_nanos(),
+            m.max().as_nanos()
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measurement(stage: Stage, micros: &[u64]) -> Measurement {
+        let mut m = Measurement::new(7, stage);
+        for &t in micros {
+            m.record(Duration::from_micros(t));
+        }
+        m
+    }
+
+    #[test]
+    fn statistics() {
+        let odd = measurement(Stage::Parse, &[30, 10, 20]);
+        assert_eq!(odd.min(), Duration::from_micros(10));
+        assert_eq!(odd.median(), Duration::from_micros(20));
+        assert_eq!(odd.max(), Duration::from_micros(30));
+
+        let even = measurement(Stage::Parse, &[40, 10, 20, 30]);
+        assert_eq!(even.median(), Duration::from_micros(25));
+    }
+
+    #[test]
+    fn csv() {
+        let measurements = [
+            measurement(Stage::Parse, &[1, 2, 3]),
+            measurement(Stage::Part(2), &[5]),
+        ];
+        assert_eq!(
+            render(&measurements, Format::Csv),
+            "day,stage,runs,min_ns,median_ns,max_ns\n\
+             7,parse,3,1000,2000,3000\n\
+             7,part2,1,5000,5000,5000\n"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let measurements = [measurement(Stage::Part(1), &[1, 3])];
+        assert_eq!(
+            render(&measurements, Format::Json),
+            "[\n  {\"day\": 7, \"stage\": \"part1\", \"runs\": 2, \"min_ns\": 1000, \"median_ns\": 2000, \"max_ns\": 3000}\n]\n"
+        );
+        assert_eq!(render(&[], Format::Json), "[]\n");
+    }
+}
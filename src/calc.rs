@@ -0,0 +1,321 @@
+use crate::error::Error;
+use crate::solutions::day18::{parse_expression, Associativity, BinaryOp, Notation, Precedence};
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "\
+<expression>    work out an expression, eg. 1 + 2 * (3 - 4), and show how it was grouped
+:mode           show the precedence mode in use, and the others there are
+:mode <NAME>    switch to another precedence mode
+:op             show how tightly each operator binds, and which way it groups
+:op <OP> <LEVEL> [left|right]
+                put an operator on a level, grouping to the left unless told otherwise,
+                making a custom precedence mode from the one in use
+:show <NAME>    write expressions out as infix (fully bracketed, the default), prefix
+                (an S-expression), postfix (Reverse Polish) or dot (a Graphviz graph)
+:help           show this list
+:quit           leave the calculator
+
+Numbers are whole, and the operators are + - * / and unary minus. Division rounds
+towards zero.";
+
+/// Which of the precedence tables the calculator reads expressions with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    Equal,
+    AdditionFirst,
+    Standard,
+}
+
+impl Mode {
+    const ALL: [Mode; 3] = [Mode::Equal, Mode::AdditionFirst, Mode::Standard];
+
+    pub fn from_name(name: &str) -> Option<Mode> {
+        Mode::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.to_string() == name)
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            Mode::Equal => "every operator the same, worked out left to right (part 1)",
+            Mode::AdditionFirst => "+ and - before * and / (part 2)",
+            Mode::Standard => "* and / before + and -, as usual",
+        }
+    }
+
+    fn precedence(&self) -> Precedence {
+        match self {
+            Mode::Equal => Precedence::equal(),
+            Mode::AdditionFirst => Precedence::addition_first(),
+            Mode::Standard => Precedence::standard(),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Mode::Equal => "equal",
+            Mode::AdditionFirst => "addition-first",
+            Mode::Standard => "standard",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// the mode in use, or None once an operator has been moved away from it
+fn mode_name(mode: Option<Mode>) -> String {
+    match mode {
+        Some(mode) => mode.to_string(),
+        None => String::from("custom"),
+    }
+}
+
+fn modes(current: Option<Mode>) -> String {
+    let mut lines: Vec<String> = Mode::ALL
+        .iter()
+        .map(|&mode| {
+            let marker = if Some(mode) == current { "=>" } else { "  " };
+            format!("{} {:<15} {}", marker, mode.to_string(), mode.describe())
+        })
+        .collect();
+    if current.is_none() {
+        lines.push(format!("=> {:<15} {}", "custom", "changed with :op"));
+    }
+    lines.join("\n")
+}
+
+fn operators(precedence: &Precedence) -> String {
+    BinaryOp::ALL
+        .iter()
+        .map(|&op| match precedence.get(op) {
+            Some((level, associativity)) => {
+                format!("{}  level {}, grouping to the {}", op, level, associativity)
+            }
+            None => format!("{}  can't be used", op),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// reads the operator, level and associativity given to :op
+fn parse_op<'a>(
+    mut words: impl Iterator<Item = &'a str>,
+) -> Result<(BinaryOp, u8, Associativity), String> {
+    let symbol = words.next().unwrap_or("");
+    let mut chars = symbol.chars();
+    let op = match (chars.next().and_then(BinaryOp::from_symbol), chars.next()) {
+        (Some(op), None) => op,
+        _ => return Err(format!("'{}' isn't an operator - use + - * or /", symbol)),
+    };
+    let level = match words.next() {
+        Some(level) => level.parse().map_err(|_| {
+            format!(
+                "'{}' isn't a level - it must be from 0 to {}",
+                level,
+                u8::MAX
+            )
+        })?,
+        None => return Err(format!(":op {} needs a level", op)),
+    };
+    let associativity = match words.next() {
+        None => Associativity::Left,
+        Some(name) => Associativity::from_name(name)
+            .ok_or_else(|| format!("'{}' isn't a direction - use left or right", name))?,
+    };
+    match words.next() {
+        Some(extra) => Err(format!("unexpected '{}'", extra)),
+        None => Ok((op, level, associativity)),
+    }
+}
+
+// the result of a line which isn't a command: the expression as it was grouped and its
+// value, or what went wrong, with a parse error pointed at under the line
+fn evaluate(line: &str, precedence: &Precedence, notation: Notation) -> String {
+    let expression = match parse_expression(line, precedence) {
+        Ok(expression) => expression,
+        Err(Error::Parse {
+            column, message, ..
+        }) => {
+            return format!(
+                "  {}\n  {}^ {}",
+                line,
+                " ".repeat(column.saturating_sub(1)),
+                message
+            )
+        }
+        Err(e) => return e.to_string(),
+    };
+    match expression.eval() {
+        Ok(value) => format!("{}\n= {}", expression.render(notation), value),
+        Err(e) => format!("{}\n{}", expression.render(notation), e),
+    }
+}
+
+/// Works out the expressions read from `input` one line at a time, showing each on `output`
+/// with its value, until told to quit or the input runs out.
+pub fn calc(
+    mode: Mode,
+    mut notation: Notation,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> io::Result<()> {
+    writeln!(
+        output,
+        "using {} precedence - type :help for the commands",
+        mode
+    )?;
+    let mut precedence = mode.precedence();
+    let mut mode = Some(mode);
+    let mut lines = input.lines();
+    loop {
+        write!(output, "({}) ", mode_name(mode))?;
+        output.flush()?;
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        let line = line.trim();
+        let mut words = line.split_whitespace();
+        let shown = match words.next() {
+            None => continue,
+            Some(":quit") | Some(":q") => break,
+            Some(":help") | Some(":h") => String::from(HELP),
+            Some(":mode") | Some(":m") => match (words.next(), words.next()) {
+                (None, _) => modes(mode),
+                (Some(name), None) => match Mode::from_name(name) {
+                    Some(chosen) => {
+                        mode = Some(chosen);
+                        precedence = chosen.precedence();
+                        format!("now using {} precedence", chosen)
+                    }
+                    None => format!("unknown mode '{}' - :mode lists them", name),
+                },
+                (Some(_), Some(extra)) => format!("unexpected '{}'", extra),
+            },
+            Some(":op") | Some(":o") => {
+                let mut words = words.peekable();
+                if words.peek().is_none() {
+                    operators(&precedence)
+                } else {
+                    match parse_op(words) {
+                        Ok((op, level, associativity)) => {
+                            mode = None;
+                            precedence.set(op, level, associativity);
+                            format!(
+                                "{} is now on level {}, grouping to the {}",
+                                op, level, associativity
+                            )
+                        }
+                        Err(message) => message,
+                    }
+                }
+            }
+            Some(":show") | Some(":s") => match (words.next(), words.next()) {
+                (None, _) => format!("showing expressions as {}", notation),
+                (Some(name), None) => match Notation::from_name(name) {
+                    Some(chosen) => {
+                        notation = chosen;
+                        format!("now showing expressions as {}", notation)
+                    }
+                    None => format!(
+                        "unknown notation '{}' - use infix, prefix, postfix or dot",
+                        name
+                    ),
+                },
+                (Some(_), Some(extra)) => format!("unexpected '{}'", extra),
+            },
+            Some(command) if command.starts_with(':') => {
+                format!("unknown command '{}' - try :help", command)
+            }
+            Some(_) => evaluate(line, &precedence, notation),
+        };
+        writeln!(output, "{}", shown)?;
+    }
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(mode: Mode, commands: &str) -> String {
+        let mut output = Vec::new();
+        calc(mode, Notation::Infix, commands.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn mode_names() {
+        for mode in Mode::ALL.iter() {
+            assert_eq!(Mode::from_name(&mode.to_string()), Some(*mode));
+        }
+        assert_eq!(Mode::from_name("advanced"), None);
+    }
+
+    #[test]
+    fn switching_modes() {
+        let output = session(
+            Mode::Equal,
+            "1 + 2 * 3 + 4\n:mode addition-first\n1 + 2 * 3 + 4\n:mode\n:quit\n5\n",
+        );
+        assert!(output.contains("(((1 + 2) * 3) + 4)\n= 13\n"));
+        assert!(output.contains("now using addition-first precedence"));
+        assert!(output.contains("((1 + 2) * (3 + 4))\n= 21\n"));
+        assert!(output.contains("=> addition-first  + and - before * and / (part 2)"));
+        // nothing after quitting is read
+        assert_eq!(output.matches("(equal) ").count(), 2);
+        assert_eq!(output.matches("(addition-first) ").count(), 3);
+    }
+
+    #[test]
+    fn errors() {
+        let output = session(
+            Mode::Standard,
+            "2 * (3 + 4\n8 / (2 - 2)\n:mode fancy\n:undo\n",
+        );
+        assert!(output.contains("  2 * (3 + 4\n            ^ expected ')'\n"));
+        assert!(output.contains("(8 / (2 - 2))\nno solution exists: 8 / 0 divides by zero"));
+        assert!(output.contains("unknown mode 'fancy'"));
+        assert!(output.contains("unknown command ':undo'"));
+    }
+
+    #[test]
+    fn custom_precedence() {
+        let output = session(
+            Mode::Standard,
+            ":op - 3 right\n10 - 4 - 3\n:op\n:mode\n:op % 1\n:op + x\n:op * 1 up\n",
+        );
+        assert!(output.contains("(standard) - is now on level 3, grouping to the right\n"));
+        assert!(output.contains("(custom) (10 - (4 - 3))\n= 9\n"));
+        assert!(output.contains(
+            "+  level 1, grouping to the left\n-  level 3, grouping to the right\n\
+             *  level 2, grouping to the left\n/  level 2, grouping to the left\n"
+        ));
+        assert!(output.contains(
+            "   standard        * and / before + and -, as usual\n\
+                                 => custom          changed with :op\n"
+        ));
+        assert!(output.contains("'%' isn't an operator"));
+        assert!(output.contains("'x' isn't a level"));
+        assert!(output.contains("'up' isn't a direction"));
+        // switching mode starts again from its table
+        let output = session(Mode::Equal, ":op + 2\n:mode equal\n1 * 2 + 3\n");
+        assert!(output.contains("(equal) ((1 * 2) + 3)\n= 5\n"));
+    }
+
+    #[test]
+    fn notations() {
+        let output = session(
+            Mode::Equal,
+            "1 + 2 * 3\n:show rpn\n1 + 2 * 3\n:show\n:show tree\n",
+        );
+        assert!(output.contains("((1 + 2) * 3)\n= 9\n"));
+        assert!(output.contains("now showing expressions as postfix"));
+        assert!(output.contains("1 2 + 3 *\n= 9\n"));
+        assert!(output.contains("(equal) showing expressions as postfix\n"));
+        assert!(output.contains("unknown notation 'tree'"));
+    }
+}
@@ -0,0 +1,1174 @@
+use crate::answers::{self, Answers, Check};
+use crate::bench::{self, Format, Measurement, Stage};
+use crate::calc::{self, Mode};
+use crate::debugger;
+use crate::deduction::{self, json_string};
+use crate::input::InputSource;
+use crate::solutions::day17;
+use crate::solutions::day18::Notation;
+use crate::solutions::day19::{self, Engine};
+use crate::solutions::day22::{self, LogFormat, Loops, Rules, SubGames};
+use crate::solutions::day25;
+use crate::solutions::{registry, Entry, Registry};
+use crate::vm;
+use std::io;
+use std::path::PathBuf;
+use std::slice;
+
+const USAGE: &str = "\
+usage: aoc2020 run (--day <N> | --days <RANGE> | --all) [--part <1|2>]
+                   [--input <PATH> | --stdin]
+       aoc2020 run --day <N> --explain [--format <text|json>] [--part <1|2>]
+                   [--input <PATH> | --stdin]
+       aoc2020 verify [--day <N> | --days <RANGE> | --all] [--answers <PATH>]
+       aoc2020 bench (--day <N> | --days <RANGE> | --all) [--part <1|2>]
+                     [--input <PATH> | --stdin] [--iterations <N>]
+                     [--format <table|json|csv>]
+       aoc2020 debug [--input <PATH>]
+       aoc2020 cubes [--input <PATH> | --stdin] [--dimensions <N>] [--cycles <N>]
+       aoc2020 rules [--input <PATH> | --stdin] [--part <1|2>]
+                     [--match <MESSAGE>]... [--regex]
+                     [--engine <auto|automaton|backtracking>]
+       aoc2020 combat [--input <PATH> | --stdin] [--part <1|2>]
+                      [--sub-games <never|enough-cards>] [--max-depth <N>]
+                      [--loops <ignore|first-player|most-cards>]
+                      [--max-rounds <N>] [--log <text|json>]
+       aoc2020 handshake [--input <PATH> | --stdin] [--subject <N>] [--modulus <N>]
+       aoc2020 calc [--mode <equal|addition-first|standard>]
+                    [--show <infix|prefix|postfix|dot>]
+
+  --day <N>         run a single day, from 1 to 25
+  --days <RANGE>    run several days, eg. 10-15 or 1,3,5-7
+  --all             run every solved day
+  --part <1|2>      only run the given part of each selected day
+  --input <PATH>    read the puzzle input from a file instead of the input directory
+  --stdin           read the puzzle input from standard input
+  --answers <PATH>  check against this file instead of answers.toml
+  --explain         show each step taken in working out the answer
+  --iterations <N>  how many times to run each part when benchmarking (default 10)
+  --format <FMT>    write benchmark results as a table (the default), JSON or CSV,
+                    or an explanation as text (the default) or JSON
+  --mode <MODE>     the operator precedence the calculator starts with (default equal)
+  --show <NOTATION> how the calculator writes out expressions (default infix)
+  --dimensions <N>  how many dimensions day 17's cubes are laid out in (default 4)
+  --cycles <N>      how many cycles day 17's cubes are run for (default 6)
+  --match <MESSAGE> show how a message matches day 19's rule 0, or where it stops
+  --regex           write day 19's rule 0 out as a regular expression
+  --engine <ENGINE> how day 19's messages are counted (default auto)
+  --sub-games <WHEN>
+                    when a combat round is settled by a sub-game instead of the
+                    highest card
+  --max-depth <N>   how many games deep combat sub-games can go
+  --loops <RULE>    who wins a combat game when the decks repeat, if anyone
+  --max-rounds <N>  give up on a combat game which goes on for this many rounds
+  --log <FMT>       write out every round of a combat game, as text or JSON
+  --subject <N>     the subject number of day 25's handshake (default 7)
+  --modulus <N>     the modulus of day 25's handshake (default 20201227)
+
+--input and --stdin can only be used when running a single day. --explain works
+for the days solved by deduction, 16 and 21, and lists how each field or allergen
+was pinned down before giving the answers.
+verify checks every day unless told otherwise, and always uses the input
+directory.
+bench times parsing and each part separately, and reports the fastest, median
+and slowest runs.
+debug steps through day 8's boot code, or any other boot code file given with
+--input - type help once it's started for what it can do.
+calc works out expressions with day 18's operator precedence rules.
+cubes runs day 17's cubes in anything from 2 to 6 dimensions, and says how many
+are active at the end.
+rules checks the messages in day 19's input against rule 0, using the looping
+rules of part 2 unless given --part 1, and shows where each one which doesn't
+match goes wrong. Messages given with --match are shown matched rule by rule.
+The count can be made with an automaton, which only works when the rules don't
+loop, by backtracking, or with whichever suits the rules (auto).
+combat plays day 22's card game, by the rules of part 2 unless given --part 1,
+with any number of players, and says who won and how much play it took. The
+other combat options change the part's rules.
+handshake works out day 25's encryption key, with another subject number or
+modulus if given one.";
+
+pub enum Command {
+    Run(Selection),
+    Explain(Selection, deduction::Format),
+    Verify(Verification),
+    Bench(Benchmark),
+    Debug(InputSource),
+    Calc(Mode, Notation),
+    Cubes(Cubes),
+    Rules(RuleCheck),
+    Combat(Combat),
+    Handshake(Handshake),
+    Help,
+}
+
+// which puzzle parts to run. `single` records whether exactly one day was asked for, in
+// which case asking for a part which doesn't exist is an error rather than being skipped
+pub struct Selection {
+    days: Vec<u8>,
+    part: Option<u8>,
+    single: bool,
+    input: InputSource,
+}
+
+impl Selection {
+    fn includes(&self, entry: &Entry) -> bool {
+        self.days.contains(&entry.day) && self.part.is_none_or(|part| part == entry.part)
+    }
+}
+
+// which days to check, and the file of answers to check them against
+pub struct Verification {
+    days: Vec<u8>,
+    answers: PathBuf,
+}
+
+// the parts to time, how many times to run each, and how to show the results
+pub struct Benchmark {
+    selection: Selection,
+    iterations: usize,
+    format: Format,
+}
+
+// the day 17 slice to start from, how many dimensions to lay it out in, and how many
+// cycles to run
+pub struct Cubes {
+    input: InputSource,
+    dimensions: usize,
+    cycles: usize,
+}
+
+// the day 19 rules and messages to check, which part's rules to check them with, and any
+// messages given to explain instead of the input's own, whether to write the rules out as a
+// regex instead, and how to count the messages which match
+pub struct RuleCheck {
+    input: InputSource,
+    part: u8,
+    messages: Vec<String>,
+    regex: bool,
+    engine: Engine,
+}
+
+// the day 22 game to play, the rules to play it by, and how to write out its log, if at all
+pub struct Combat {
+    input: InputSource,
+    rules: Rules,
+    log: Option<LogFormat>,
+}
+
+// the day 25 public keys to read, and the subject number and modulus of the handshake
+pub struct Handshake {
+    input: InputSource,
+    subject: u64,
+    modulus: u64,
+}
+
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let mut args = args.iter();
+    match args.next().map(|s| s.as_str()) {
+        None | Some("help") | Some("--help") | Some("-h") => Ok(Command::Help),
+        Some("run") => parse_explain_args(args),
+        Some("verify") => parse_verify_args(args).map(Command::Verify),
+        Some("bench") => parse_bench_args(args).map(Command::Bench),
+        Some("debug") => parse_debug_args(args).map(Command::Debug),
+        Some("calc") => parse_calc_args(args).map(|(mode, notation)| Command::Calc(mode, notation)),
+        Some("cubes") => parse_cubes_args(args).map(Command::Cubes),
+        Some("rules") => parse_rules_args(args).map(Command::Rules),
+        Some("combat") => parse_combat_args(args).map(Command::Combat),
+        Some("handshake") => parse_handshake_args(args).map(Command::Handshake),
+        Some(other) => Err(format!("unknown command '{}'", other)),
+    }
+}
+
+fn parse_run_args(mut args: slice::Iter<String>) -> Result<Selection, String> {
+    let mut days = None;
+    let mut part = None;
+    let mut single = false;
+    let mut input = InputSource::Default;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--day" => {
+                days = Some(vec![parse_day(value()?)?]);
+                single = true;
+            }
+            "--days" => {
+                days = Some(parse_days(value()?)?);
+                single = false;
+            }
+            "--all" => {
+                days = Some((1..=25).collect());
+                single = false;
+            }
+            "--part" => part = Some(parse_part(value()?)?),
+            "--input" => input = InputSource::File(PathBuf::from(value()?)),
+            "--stdin" => input = InputSource::Stdin,
+            other => return Err(format!("unknown option '{}'", other)),
+        }
+    }
+
+    if !single {
+        if let InputSource::File(_) | InputSource::Stdin = input {
+            return Err(String::from(
+                "--input and --stdin can only be used with a single --day",
+            ));
+        }
+    }
+
+    match days {
+        Some(days) => Ok(Selection {
+            days,
+            part,
+            single,
+            input,
+        }),
+        None => Err(String::from(
+            "no days selected - use --day, --days or --all",
+        )),
+    }
+}
+
+// takes out the options for explaining a deduction, and leaves the rest to choose the day
+// and input just as for running. Without --explain, this is just running
+fn parse_explain_args(mut args: slice::Iter<String>) -> Result<Command, String> {
+    let mut explain = false;
+    let mut format = None;
+    let mut rest = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--explain" => explain = true,
+            "--format" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for {}", arg))?;
+                format = Some(
+                    deduction::Format::from_name(name)
+                        .ok_or_else(|| format!("unknown format '{}' - use text or json", name))?,
+                );
+            }
+            _ => rest.push(arg.to_owned()),
+        }
+    }
+    let selection = parse_run_args(rest.iter())?;
+    match (explain, format) {
+        (false, None) => Ok(Command::Run(selection)),
+        (false, Some(_)) => Err(String::from("--format can only be used with --explain")),
+        (true, _) if !selection.single => Err(String::from(
+            "--explain can only be used with a single --day",
+        )),
+        (true, format) => Ok(Command::Explain(
+            selection,
+            format.unwrap_or(deduction::Format::Text),
+        )),
+    }
+}
+
+fn parse_verify_args(mut args: slice::Iter<String>) -> Result<Verification, String> {
+    let mut days = (1..=25).collect();
+    let mut answers = answers::default_path();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--day" => days = vec![parse_day(value()?)?],
+            "--days" => days = parse_days(value()?)?,
+            "--all" => days = (1..=25).collect(),
+            "--answers" => answers = PathBuf::from(value()?),
+            other => return Err(format!("unknown option '{}'", other)),
+        }
+    }
+    Ok(Verification { days, answers })
+}
+
+// takes out the options only used for benchmarking, and leaves the rest to choose the days
+// and input just as for running
+fn parse_bench_args(mut args: slice::Iter<String>) -> Result<Benchmark, String> {
+    let mut iterations = 10;
+    let mut format = Format::Table;
+    let mut rest = Vec::new();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--iterations" => iterations = parse_iterations(value()?)?,
+            "--format" => {
+                let name = value()?;
+                format = Format::from_name(name)
+                    .ok_or_else(|| format!("unknown format '{}' - use table, json or csv", name))?;
+            }
+            _ => rest.push(arg.to_owned()),
+        }
+    }
+    Ok(Benchmark {
+        selection: parse_run_args(rest.iter())?,
+        iterations,
+        format,
+    })
+}
+
+// the debugger's commands are read from standard input, so the program can't also come from
+// there, and --stdin isn't accepted
+fn parse_debug_args(mut args: slice::Iter<String>) -> Result<InputSource, String> {
+    let mut input = InputSource::Default;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => match args.next() {
+                Some(path) => input = InputSource::File(PathBuf::from(path)),
+                None => return Err(format!("missing value for {}", arg)),
+            },
+            other => return Err(format!("unknown option '{}'", other)),
+        }
+    }
+    Ok(input)
+}
+
+fn parse_calc_args(mut args: slice::Iter<String>) -> Result<(Mode, Notation), String> {
+    let mut mode = Mode::Equal;
+    let mut notation = Notation::Infix;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--mode" => {
+                let name = value()?;
+                mode = Mode::from_name(name).ok_or_else(|| {
+                    format!(
+                        "unknown mode '{}' - use equal, addition-first or standard",
+                        name
+                    )
+                })?;
+            }
+            "--show" => {
+                let name = value()?;
+                notation = Notation::from_name(name).ok_or_else(|| {
+                    format!(
+                        "unknown notation '{}' - use infix, prefix, postfix or dot",
+                        name
+                    )
+                })?;
+            }
+            other => return Err(format!("unknown option '{}'", other)),
+        }
+    }
+    Ok((mode, notation))
+}
+
+fn parse_cubes_args(mut args: slice::Iter<String>) -> Result<Cubes, String> {
+    let mut cubes = Cubes {
+        input: InputSource::Default,
+        dimensions: 4,
+        cycles: 6,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--input" => cubes.input = InputSource::File(PathBuf::from(value()?)),
+            "--stdin" => cubes.input = InputSource::Stdin,
+            "--dimensions" => cubes.dimensions = parse_dimensions(value()?)?,
+            "--cycles" => cubes.cycles = parse_limit(value()?)?,
+            other => return Err(format!("unknown option '{}'", other)),
+        }
+    }
+    Ok(cubes)
+}
+
+fn parse_rules_args(mut args: slice::Iter<String>) -> Result<RuleCheck, String> {
+    let mut check = RuleCheck {
+        input: InputSource::Default,
+        part: 2,
+        messages: Vec::new(),
+        regex: false,
+        engine: Engine::Auto,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--input" => check.input = InputSource::File(PathBuf::from(value()?)),
+            "--stdin" => check.input = InputSource::Stdin,
+            "--part" => check.part = parse_part(value()?)?,
+            "--match" => check.messages.push(value()?.to_owned()),
+            "--regex" => check.regex = true,
+            "--engine" => {
+                let name = value()?;
+                check.engine = Engine::from_name(name).ok_or_else(|| {
+                    format!(
+                        "unknown engine '{}' - use auto, automaton or backtracking",
+                        name
+                    )
+                })?;
+            }
+            other => return Err(format!("unknown option '{}'", other)),
+        }
+    }
+    Ok(check)
+}
+
+// the part chosen gives the rules to start from, which the other options then change,
+// whichever order they're given in
+fn parse_combat_args(mut args: slice::Iter<String>) -> Result<Combat, String> {
+    let mut input = InputSource::Default;
+    let mut part = 2;
+    let (mut sub_games, mut max_depth, mut loops, mut max_rounds) = (None, None, None, None);
+    let mut log = None;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--input" => input = InputSource::File(PathBuf::from(value()?)),
+            "--stdin" => input = InputSource::Stdin,
+            "--part" => part = parse_part(value()?)?,
+            "--sub-games" => {
+                let name = value()?;
+                sub_games = Some(SubGames::from_name(name).ok_or_else(|| {
+                    format!(
+                        "unknown sub-game rule '{}' - use never or enough-cards",
+                        name
+                    )
+                })?);
+            }
+            "--max-depth" => max_depth = Some(parse_limit(value()?)?),
+            "--loops" => {
+                let name = value()?;
+                loops = Some(Loops::from_name(name).ok_or_else(|| {
+                    format!(
+                        "unknown loop rule '{}' - use ignore, first-player or most-cards",
+                        name
+                    )
+                })?);
+            }
+            "--max-rounds" => max_rounds = Some(parse_limit(value()?)?),
+            "--log" => {
+                let name = value()?;
+                log = Some(
+                    LogFormat::from_name(name)
+                        .ok_or_else(|| format!("unknown format '{}' - use text or json", name))?,
+                );
+            }
+            other => return Err(format!("unknown option '{}'", other)),
+        }
+    }
+
+    let part_rules = match part {
+        1 => Rules::part_1(),
+        _ => Rules::part_2(),
+    };
+    let rules = Rules {
+        sub_games: sub_games.unwrap_or(part_rules.sub_games),
+        max_depth: max_depth.or(part_rules.max_depth),
+        loops: loops.unwrap_or(part_rules.loops),
+        max_rounds: max_rounds.or(part_rules.max_rounds),
+    };
+    Ok(Combat { input, rules, log })
+}
+
+fn parse_handshake_args(mut args: slice::Iter<String>) -> Result<Handshake, String> {
+    let mut handshake = Handshake {
+        input: InputSource::Default,
+        subject: day25::SUBJECT,
+        modulus: day25::MODULUS,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--input" => handshake.input = InputSource::File(PathBuf::from(value()?)),
+            "--stdin" => handshake.input = InputSource::Stdin,
+            "--subject" => {
+                let s = value()?;
+                handshake.subject = s
+                    .parse()
+                    .map_err(|_| format!("'{}' isn't a subject number", s))?;
+            }
+            "--modulus" => {
+                let s = value()?;
+                handshake.modulus = match s.parse() {
+                    Ok(n) if n >= 2 => n,
+                    _ => return Err(format!("'{}' isn't a modulus - it must be at least 2", s)),
+                };
+            }
+            other => return Err(format!("unknown option '{}'", other)),
+        }
+    }
+    Ok(handshake)
+}
+
+fn parse_limit(s: &str) -> Result<usize, String> {
+    s.parse()
+        .map_err(|_| format!("'{}' isn't a limit - it must be a whole number", s))
+}
+
+fn parse_dimensions(s: &str) -> Result<usize, String> {
+    match s.parse() {
+        Ok(n) if (2..=day17::MAX_DIMENSIONS).contains(&n) => Ok(n),
+        _ => Err(format!(
+            "'{}' isn't a number of dimensions - it must be from 2 to {}",
+            s,
+            day17::MAX_DIMENSIONS
+        )),
+    }
+}
+
+fn parse_iterations(s: &str) -> Result<usize, String> {
+    match s.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!(
+            "'{}' isn't a number of iterations - it must be at least 1",
+            s
+        )),
+    }
+}
+
+fn parse_day(s: &str) -> Result<u8, String> {
+    match s.parse() {
+        Ok(day) if (1..=25).contains(&day) => Ok(day),
+        _ => Err(format!("there is no day '{}' - days run from 1 to 25", s)),
+    }
+}
+
+fn parse_days(s: &str) -> Result<Vec<u8>, String> {
+    let mut days = Vec::new();
+    for range in s.split(',') {
+        let ends: Vec<&str> = range.split('-').collect();
+        match ends[..] {
+            [day] => days.push(parse_day(day)?),
+            [start, end] => {
+                let (start, end) = (parse_day(start)?, parse_day(end)?);
+                if start > end {
+                    return Err(format!("day range '{}' is backwards", range));
+                }
+                days.extend(start..=end);
+            }
+            _ => return Err(format!("couldn't read day range '{}'", range)),
+        }
+    }
+    Ok(days)
+}
+
+fn parse_part(s: &str) -> Result<u8, String> {
+    match s {
+        "1" => Ok(1),
+        "2" => Ok(2),
+        _ => Err(format!(
+            "there is no part '{}' - each day has parts 1 and 2",
+            s
+        )),
+    }
+}
+
+// the registered parts which were asked for, in order
+fn selected_entries<'a>(
+    selection: &Selection,
+    registry: &'a Registry,
+) -> Result<Vec<&'a Entry>, String> {
+    let entries: Vec<&Entry> = match (selection.single, selection.part) {
+        (true, Some(part)) => {
+            let day = selection.days[0];
+            match registry.get(day, part) {
+                Some(entry) => vec![entry],
+                None => return Err(format!("day {}, part {} has no solution", day, part)),
+            }
+        }
+        _ => registry
+            .entries()
+            .filter(|entry| selection.includes(entry))
+            .collect(),
+    };
+
+    if entries.is_empty() {
+        return Err(String::from("no solutions exist for the selected days"));
+    }
+    Ok(entries)
+}
+
+// any input given explicitly is read up front, as stdin can't be read twice when
+// running both parts
+fn read_input(selection: Selection) -> Result<InputSource, String> {
+    match selection.input {
+        InputSource::Default => Ok(InputSource::Default),
+        other => Ok(InputSource::Text(
+            other.read(selection.days[0]).map_err(|e| e.to_string())?,
+        )),
+    }
+}
+
+fn run(selection: Selection) -> Result<(), String> {
+    let registry = registry();
+    let entries = selected_entries(&selection, &registry)?;
+    let source = read_input(selection)?;
+
+    // a failure in one part is reported, but doesn't stop the rest from running
+    let mut failures = 0;
+    for entry in entries {
+        match entry.run(&source) {
+            Ok(answer) => println!(
+                "The answer to day {}, part {} is {}",
+                entry.day, entry.part, answer
+            ),
+            Err(e) => {
+                eprintln!("error: day {}, part {}: {}", entry.day, entry.part, e);
+                failures += 1;
+            }
+        }
+    }
+
+    match failures {
+        0 => Ok(()),
+        1 => Err(String::from("1 part failed")),
+        n => Err(format!("{} parts failed", n)),
+    }
+}
+
+// shows the steps a deduction-based day took to work out its answer, then the answers to
+// the selected parts, either as text or together in one JSON object
+fn explain(selection: Selection, format: deduction::Format) -> Result<(), String> {
+    let registry = registry();
+    let entries = selected_entries(&selection, &registry)?;
+    let day = selection.days[0];
+    let source = read_input(selection)?;
+
+    let explanation = match entries[0].explain(&source) {
+        Ok(Some(explanation)) => explanation,
+        Ok(None) => {
+            return Err(format!(
+                "day {} has no deductions to explain - only days 16 and 21 do",
+                day
+            ))
+        }
+        Err(e) => return Err(format!("day {}: {}", day, e)),
+    };
+    let answers: Vec<(u8, Result<String, String>)> = entries
+        .iter()
+        .map(|entry| {
+            let answer = entry.run(&source).map(|a| a.to_string());
+            (entry.part, answer.map_err(|e| e.to_string()))
+        })
+        .collect();
+
+    match format {
+        deduction::Format::Text => {
+            print!("{}", explanation.render_text());
+            println!();
+            for (part, answer) in answers.iter() {
+                match answer {
+                    Ok(answer) => {
+                        println!("The answer to day {}, part {} is {}", day, part, answer)
+                    }
+                    Err(e) => eprintln!("error: day {}, part {}: {}", day, part, e),
+                }
+            }
+        }
+        deduction::Format::Json => {
+            let answers: Vec<String> = answers
+                .iter()
+                .map(|(part, answer)| match answer {
+                    Ok(answer) => format!(
+                        "    {{\"part\": {}, \"answer\": {}}}",
+                        part,
+                        json_string(answer)
+                    ),
+                    Err(e) => format!("    {{\"part\": {}, \"error\": {}}}", part, json_string(e)),
+                })
+                .collect();
+            println!("{{");
+            println!("  \"day\": {},", day);
+            // the steps are indented a level further in, as they're inside this object
+            println!(
+                "  \"steps\": {},",
+                explanation.render_json().replace('\n', "\n  ")
+            );
+            println!("  \"answers\": [\n{}\n  ]", answers.join(",\n"));
+            println!("}}");
+        }
+    }
+
+    match answers.iter().filter(|(_, answer)| answer.is_err()).count() {
+        0 => Ok(()),
+        1 => Err(String::from("1 part failed")),
+        n => Err(format!("{} parts failed", n)),
+    }
+}
+
+// runs each selected part the given number of times, timing the parse and the part itself
+// separately. A part which fails is reported and left out of the results
+fn bench(benchmark: Benchmark) -> Result<(), String> {
+    let registry = registry();
+    let entries = selected_entries(&benchmark.selection, &registry)?;
+    let source = read_input(benchmark.selection)?;
+
+    let mut measurements: Vec<Measurement> = Vec::new();
+    let mut failures = 0;
+    for entry in entries {
+        // reading the input isn't part of what's timed
+        let input = match source.read(entry.day) {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("error: day {}, part {}: {}", entry.day, entry.part, e);
+                failures += 1;
+                continue;
+            }
+        };
+        // both parts parse the same input, so the parse is only timed along with the first
+        // part of each day, once per iteration
+        let is_parse = |m: &Measurement| m.day == entry.day && m.stage == Stage::Parse;
+        let mut parse = if measurements.iter().any(is_parse) {
+            None
+        } else {
+            Some(Measurement::new(entry.day, Stage::Parse))
+        };
+        let mut part = Measurement::new(entry.day, Stage::Part(entry.part));
+        for _ in 0..benchmark.iterations {
+            match entry.solution.run_timed(entry.part, &input) {
+                Ok(timed) => {
+                    if let Some(parse) = parse.as_mut() {
+                        parse.record(timed.parse);
+                    }
+                    part.record(timed.solve);
+                }
+                Err(e) => {
+                    eprintln!("error: day {}, part {}: {}", entry.day, entry.part, e);
+                    failures += 1;
+                    break;
+                }
+            }
+        }
+        // if the first part failed straight away, the parse is timed with the other instead
+        measurements.extend(parse.filter(|p| p.runs() > 0));
+        measurements.push(part);
+    }
+    measurements.retain(|m| m.runs() > 0);
+
+    print!("{}", bench::render(&measurements, benchmark.format));
+
+    match failures {
+        0 => Ok(()),
+        1 => Err(String::from("1 part failed")),
+        n => Err(format!("{} parts failed", n)),
+    }
+}
+
+// runs each selected part against the input directory, and prints a table of how each
+// did against the recorded answers, followed by the details of any that didn't pass
+fn verify(verification: Verification) -> Result<(), String> {
+    let answers = Answers::load(&verification.answers).map_err(|e| e.to_string())?;
+    let registry = registry();
+    let mut problems = Vec::new();
+    let (mut passed, mut failed, mut missing) = (0, 0, 0);
+
+    println!("day  part 1   part 2");
+    for &day in verification.days.iter() {
+        let mut row = format!("{:>3}", day);
+        for part in 1..=2 {
+            let entry = match registry.get(day, part) {
+                Some(entry) => entry,
+                None => {
+                    row.push_str("  -      ");
+                    continue;
+                }
+            };
+            let (status, problem) = match answers.check(entry, &InputSource::Default) {
+                Check::Pass => {
+                    passed += 1;
+                    ("pass", None)
+                }
+                Check::Fail { expected, actual } => {
+                    failed += 1;
+                    let problem = format!("expected {}, got {}", expected, actual);
+                    ("FAIL", Some(problem))
+                }
+                Check::Missing(actual) => {
+                    missing += 1;
+                    let problem = format!("no recorded answer, got {}", actual);
+                    ("missing", Some(problem))
+                }
+                Check::Error(e) => {
+                    failed += 1;
+                    ("error", Some(e.to_string()))
+                }
+            };
+            if let Some(problem) = problem {
+                problems.push(format!("day {}, part {}: {}", day, part, problem));
+            }
+            row.push_str(&format!("  {:<7}", status));
+        }
+        println!("{}", row.trim_end());
+    }
+
+    if !problems.is_empty() {
+        println!();
+        for problem in problems {
+            println!("{}", problem);
+        }
+    }
+    println!();
+    println!("{} passed, {} failed, {} missing", passed, failed, missing);
+
+    match failed {
+        0 => Ok(()),
+        1 => Err(String::from("1 part failed verification")),
+        n => Err(format!("{} parts failed verification", n)),
+    }
+}
+
+// the boot code to debug is read as day 8's input, unless another file was given
+fn debug(input: InputSource) -> Result<(), String> {
+    let source = input.read(8).map_err(|e| e.to_string())?;
+    let program = vm::assemble(&source).map_err(|e| e.to_string())?;
+    let stdin = io::stdin();
+    debugger::debug(program, stdin.lock(), io::stdout()).map_err(|e| e.to_string())
+}
+
+fn calc(mode: Mode, notation: Notation) -> Result<(), String> {
+    let stdin = io::stdin();
+    calc::calc(mode, notation, stdin.lock(), io::stdout()).map_err(|e| e.to_string())
+}
+
+// runs day 17's cubes in however many dimensions were asked for
+fn cubes(cubes: Cubes) -> Result<(), String> {
+    let source = cubes.input.read(17).map_err(|e| e.to_string())?;
+    let slice = day17::parse_cubes(&source).map_err(|e| e.to_string())?;
+    let active =
+        day17::active_in(&slice, cubes.dimensions, cubes.cycles).map_err(|e| e.to_string())?;
+    println!(
+        "{} cubes are active after {} cycles in {} dimensions",
+        active, cubes.cycles, cubes.dimensions
+    );
+    Ok(())
+}
+
+// a message which doesn't match, pointed at under where it went wrong
+fn mismatch(message: &str, mismatch: &day19::Mismatch) -> String {
+    format!(
+        "  {}\n  {}^ {}",
+        message,
+        " ".repeat(mismatch.position),
+        mismatch
+    )
+}
+
+// shows how each message given matches rule 0, rule by rule, or where it stops matching.
+// Without any, the input's own messages are checked, and the ones which don't match shown
+// before counting them with the engine chosen
+fn rules(check: RuleCheck) -> Result<(), String> {
+    let source = check.input.read(19).map_err(|e| e.to_string())?;
+    let mut info = day19::parse_file(&source).map_err(|e| e.to_string())?;
+    if check.part == 2 {
+        info = info.with_looping_rules().map_err(|e| e.to_string())?;
+    }
+
+    if check.regex {
+        let regex = info.regex().ok_or_else(|| {
+            String::from("rule 0 loops, so can't be written as a regular expression")
+        })?;
+        println!("{}", regex);
+        return Ok(());
+    }
+
+    if !check.messages.is_empty() {
+        for message in check.messages.iter() {
+            match info.explain(message) {
+                Ok(tree) => print!("{}", tree),
+                Err(e) => println!("{}", mismatch(message, &e)),
+            }
+        }
+        return Ok(());
+    }
+    // counted first, so an engine which can't handle the rules fails before anything is shown
+    let matched = info
+        .count_matches(check.engine)
+        .map_err(|e| e.to_string())?;
+    for message in info.messages() {
+        if let Err(e) = info.explain(message) {
+            println!("{}", mismatch(message, &e));
+        }
+    }
+    println!(
+        "{} of {} messages match rule 0",
+        matched,
+        info.messages().len()
+    );
+    Ok(())
+}
+
+// plays day 22's game, writing out the log of every round first if it was asked for, and
+// then the winner and how much play it took
+fn combat(combat: Combat) -> Result<(), String> {
+    let source = combat.input.read(22).map_err(|e| e.to_string())?;
+    let game = day22::parse_file(&source).map_err(|e| e.to_string())?;
+    let outcome =
+        day22::simulate(&game, combat.rules, combat.log.is_some()).map_err(|e| e.to_string())?;
+    let log = outcome.log.unwrap_or_default();
+    match combat.log {
+        None => {}
+        Some(LogFormat::Text) => println!("{}", log.render_text()),
+        Some(LogFormat::Json) => {
+            println!("{{");
+            println!("  \"winner\": {},", outcome.winner + 1);
+            println!("  \"score\": {},", outcome.score);
+            println!("  \"statistics\": {},", outcome.statistics.render_json());
+            // the events are indented a level further in, as they're inside this object
+            println!("  \"events\": {}", log.render_json().replace('\n', "\n  "));
+            println!("}}");
+            return Ok(());
+        }
+    }
+    println!(
+        "Player {} wins, with a score of {}",
+        outcome.winner + 1,
+        outcome.score
+    );
+    println!();
+    println!("{}", outcome.statistics);
+    Ok(())
+}
+
+// works out the encryption key from the two public keys
+fn handshake(handshake: Handshake) -> Result<(), String> {
+    let source = handshake.input.read(25).map_err(|e| e.to_string())?;
+    let keys = day25::parse_keys(&source, handshake.modulus).map_err(|e| e.to_string())?;
+    let key = day25::encryption_key(keys, handshake.subject, handshake.modulus)
+        .map_err(|e| e.to_string())?;
+    println!("The encryption key is {}", key);
+    Ok(())
+}
+
+pub fn execute(command: Command) -> Result<(), String> {
+    match command {
+        Command::Help => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Command::Run(selection) => run(selection),
+        Command::Explain(selection, format) => explain(selection, format),
+        Command::Verify(verification) => verify(verification),
+        Command::Bench(benchmark) => bench(benchmark),
+        Command::Debug(input) => debug(input),
+        Command::Calc(mode, notation) => calc(mode, notation),
+        Command::Cubes(options) => cubes(options),
+        Command::Rules(check) => rules(check),
+        Command::Combat(options) => combat(options),
+        Command::Handshake(options) => handshake(options),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn day_ranges() {
+        assert_eq!(parse_days("1,3,5-7").unwrap(), vec![1, 3, 5, 6, 7]);
+        assert!(parse_days("7-5").is_err());
+        assert!(parse_days("0-3").is_err());
+        assert!(parse_days("1-2-3").is_err());
+    }
+
+    #[test]
+    fn verify_checks_every_day_by_default() {
+        match parse_args(&args("verify")) {
+            Ok(Command::Verify(verification)) => {
+                assert_eq!(verification.days, (1..=25).collect::<Vec<u8>>());
+                assert_eq!(verification.answers, answers::default_path());
+            }
+            _ => panic!("expected a verify command"),
+        }
+    }
+
+    #[test]
+    fn verify_options() {
+        match parse_args(&args("verify --days 3-4 --answers other.toml")) {
+            Ok(Command::Verify(verification)) => {
+                assert_eq!(verification.days, vec![3, 4]);
+                assert_eq!(verification.answers, PathBuf::from("other.toml"));
+            }
+            _ => panic!("expected a verify command"),
+        }
+        assert!(parse_args(&args("verify --part 1")).is_err());
+    }
+
+    #[test]
+    fn bench_options() {
+        match parse_args(&args("bench --iterations 3 --day 5 --format csv --part 2")) {
+            Ok(Command::Bench(benchmark)) => {
+                assert_eq!(benchmark.iterations, 3);
+                assert_eq!(benchmark.format, Format::Csv);
+                assert_eq!(benchmark.selection.days, vec![5]);
+                assert_eq!(benchmark.selection.part, Some(2));
+            }
+            _ => panic!("expected a bench command"),
+        }
+        assert!(parse_args(&args("bench --all --format xml")).is_err());
+        assert!(parse_args(&args("bench --all --iterations 0")).is_err());
+    }
+
+    #[test]
+    fn debug_options() {
+        assert!(matches!(
+            parse_args(&args("debug")),
+            Ok(Command::Debug(InputSource::Default))
+        ));
+        assert!(matches!(
+            parse_args(&args("debug --input boot.txt")),
+            Ok(Command::Debug(InputSource::File(_)))
+        ));
+        assert!(parse_args(&args("debug --stdin")).is_err());
+    }
+
+    #[test]
+    fn calc_options() {
+        assert!(matches!(
+            parse_args(&args("calc")),
+            Ok(Command::Calc(Mode::Equal, Notation::Infix))
+        ));
+        assert!(matches!(
+            parse_args(&args("calc --show dot --mode standard")),
+            Ok(Command::Calc(Mode::Standard, Notation::Dot))
+        ));
+        assert!(parse_args(&args("calc --show tree")).is_err());
+        assert!(parse_args(&args("calc --mode")).is_err());
+        assert!(parse_args(&args("calc --mode advanced")).is_err());
+    }
+
+    #[test]
+    fn explain_options() {
+        match parse_args(&args("run --day 16 --explain --format json")) {
+            Ok(Command::Explain(selection, format)) => {
+                assert_eq!(selection.days, vec![16]);
+                assert_eq!(format, deduction::Format::Json);
+            }
+            _ => panic!("expected an explain command"),
+        }
+        assert!(matches!(
+            parse_args(&args("run --explain --day 21 --part 2")),
+            Ok(Command::Explain(_, deduction::Format::Text))
+        ));
+        assert!(parse_args(&args("run --days 16,21 --explain")).is_err());
+        assert!(parse_args(&args("run --day 16 --format json")).is_err());
+        assert!(parse_args(&args("run --day 16 --explain --format csv")).is_err());
+    }
+
+    #[test]
+    fn cubes_options() {
+        match parse_args(&args("cubes")) {
+            Ok(Command::Cubes(cubes)) => assert_eq!((cubes.dimensions, cubes.cycles), (4, 6)),
+            _ => panic!("expected a cubes command"),
+        }
+        match parse_args(&args("cubes --dimensions 5 --cycles 3")) {
+            Ok(Command::Cubes(cubes)) => assert_eq!((cubes.dimensions, cubes.cycles), (5, 3)),
+            _ => panic!("expected a cubes command"),
+        }
+        assert!(parse_args(&args("cubes --dimensions 1")).is_err());
+        assert!(parse_args(&args("cubes --dimensions 7")).is_err());
+        assert!(parse_args(&args("cubes --cycles")).is_err());
+    }
+
+    #[test]
+    fn rules_options() {
+        match parse_args(&args("rules --part 1 --match abab --match b")) {
+            Ok(Command::Rules(check)) => {
+                assert_eq!(check.part, 1);
+                assert_eq!(check.messages, ["abab", "b"]);
+                assert_eq!((check.regex, check.engine), (false, Engine::Auto));
+            }
+            _ => panic!("expected a rules command"),
+        }
+        match parse_args(&args("rules --regex --engine backtracking")) {
+            Ok(Command::Rules(check)) => {
+                assert_eq!(check.part, 2);
+                assert_eq!((check.regex, check.engine), (true, Engine::Backtracking));
+            }
+            _ => panic!("expected a rules command"),
+        }
+        assert!(parse_args(&args("rules --match")).is_err());
+        assert!(parse_args(&args("rules --engine nfa")).is_err());
+        assert!(parse_args(&args("rules --all")).is_err());
+    }
+
+    #[test]
+    fn combat_options() {
+        match parse_args(&args("combat")) {
+            Ok(Command::Combat(combat)) => {
+                assert!(matches!(combat.input, InputSource::Default));
+                assert_eq!((combat.rules, combat.log), (Rules::part_2(), None));
+            }
+            _ => panic!("expected a combat command"),
+        }
+        match parse_args(&args("combat --stdin --log json --part 1")) {
+            Ok(Command::Combat(combat)) => {
+                assert!(matches!(combat.input, InputSource::Stdin));
+                assert_eq!(
+                    (combat.rules, combat.log),
+                    (Rules::part_1(), Some(LogFormat::Json))
+                );
+            }
+            _ => panic!("expected a combat command"),
+        }
+        // the part's rules are changed however late it's given
+        match parse_args(&args("combat --loops most-cards --max-depth 2 --part 1")) {
+            Ok(Command::Combat(combat)) => assert_eq!(
+                combat.rules,
+                Rules {
+                    loops: Loops::MostCardsWins,
+                    max_depth: Some(2),
+                    ..Rules::part_1()
+                }
+            ),
+            _ => panic!("expected a combat command"),
+        }
+        assert!(parse_args(&args("combat --log csv")).is_err());
+        assert!(parse_args(&args("combat --part 3")).is_err());
+        assert!(parse_args(&args("combat --sub-games sometimes")).is_err());
+        assert!(parse_args(&args("combat --max-rounds -1")).is_err());
+    }
+
+    #[test]
+    fn handshake_options() {
+        match parse_args(&args("handshake --subject 3 --modulus 11")) {
+            Ok(Command::Handshake(handshake)) => {
+                assert_eq!((handshake.subject, handshake.modulus), (3, 11))
+            }
+            _ => panic!("expected a handshake command"),
+        }
+        match parse_args(&args("handshake")) {
+            Ok(Command::Handshake(handshake)) => assert_eq!(
+                (handshake.subject, handshake.modulus),
+                (day25::SUBJECT, day25::MODULUS)
+            ),
+            _ => panic!("expected a handshake command"),
+        }
+        assert!(parse_args(&args("handshake --modulus 1")).is_err());
+        assert!(parse_args(&args("handshake --subject seven")).is_err());
+    }
+
+    #[test]
+    fn run_needs_days() {
+        assert!(parse_args(&args("run --part 1")).is_err());
+        assert!(parse_args(&args("run --days 1-3 --stdin")).is_err());
+        assert!(matches!(
+            parse_args(&args("run --day 3 --stdin")),
+            Ok(Command::Run(_))
+        ));
+    }
+}
@@ -0,0 +1,346 @@
+use crate::vm::{self, Instruction, Machine, Status};
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "\
+step [N]     (s)  run the next N instructions, 1 if not given
+continue     (c)  run until a breakpoint, or until the program stops
+break <N>    (b)  set a breakpoint before instruction N, or clear one that's there
+list [N]     (l)  show the instructions around N, or around the next one to run
+trace [N]    (t)  show the last N instructions run, 10 if not given
+dump         (d)  write the whole program out as boot code, to save and load again
+info         (i)  show where the program is, and the breakpoints
+fix          (f)  find the jmp or nop which, swapped, lets the program terminate
+reset        (r)  start the program again, keeping the breakpoints
+help         (h)  show this list
+quit         (q)  leave the debugger
+
+Instructions are numbered from 0, in the order they appear in the file.";
+
+// how many instructions either side of the one asked about are listed
+const LIST_CONTEXT: usize = 4;
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Step(usize),
+    Continue,
+    Break(usize),
+    List(Option<usize>),
+    Trace(usize),
+    Dump,
+    Info,
+    Fix,
+    Reset,
+    Help,
+    Quit,
+}
+
+fn parse_command(line: &str) -> Result<Command, String> {
+    let mut words = line.split_whitespace();
+    let name = words.next().unwrap_or("");
+    let number = words
+        .next()
+        .map(|n| {
+            n.parse::<usize>()
+                .map_err(|_| format!("'{}' isn't a number", n))
+        })
+        .transpose()?;
+    if let Some(extra) = words.next() {
+        return Err(format!("unexpected '{}'", extra));
+    }
+    let command = match name {
+        "step" | "s" => Command::Step(number.unwrap_or(1)),
+        "continue" | "c" => Command::Continue,
+        "break" | "b" => match number {
+            Some(n) => Command::Break(n),
+            None => return Err(String::from("break needs an instruction number")),
+        },
+        "list" | "l" => Command::List(number),
+        "trace" | "t" => Command::Trace(number.unwrap_or(10)),
+        "dump" | "d" => Command::Dump,
+        "info" | "i" => Command::Info,
+        "fix" | "f" => Command::Fix,
+        "reset" | "r" => Command::Reset,
+        "help" | "h" => Command::Help,
+        "quit" | "q" => Command::Quit,
+        _ => return Err(format!("unknown command '{}' - try help", name)),
+    };
+    let takes_number = matches!(
+        command,
+        Command::Step(_) | Command::Break(_) | Command::List(_) | Command::Trace(_)
+    );
+    if number.is_some() && !takes_number {
+        return Err(format!("{} doesn't take a number", name));
+    }
+    Ok(command)
+}
+
+// what the program has come to, after it's been run
+fn describe(machine: &Machine, status: &Status) -> String {
+    match status {
+        Status::Ready => format!(
+            "next is {}: {}  (acc = {})",
+            machine.position(),
+            machine.program()[machine.position()],
+            machine.acc()
+        ),
+        Status::Breakpoint(position) => format!(
+            "stopped at breakpoint {}: {}  (acc = {})",
+            position,
+            machine.program()[*position],
+            machine.acc()
+        ),
+        Status::Terminated => format!("the program terminated, with acc = {}", machine.acc()),
+        Status::JumpedOut => format!(
+            "the program jumped outside its instructions, with acc = {}",
+            machine.acc()
+        ),
+        Status::Looped(cycle) => {
+            let positions: Vec<String> = cycle.positions.iter().map(|p| p.to_string()).collect();
+            format!(
+                "the program loops: instruction {} would run again, having first run at step {}, \
+                 with acc = {}\nthe loop goes through {}",
+                machine.position(),
+                cycle.entered_at,
+                machine.acc(),
+                positions.join(" -> ")
+            )
+        }
+    }
+}
+
+fn list(machine: &Machine, around: usize) -> String {
+    let program = machine.program();
+    let breakpoints: Vec<usize> = machine.breakpoints().collect();
+    let start = around.saturating_sub(LIST_CONTEXT);
+    let end = (around + LIST_CONTEXT + 1).min(program.len());
+    (start..end)
+        .map(|position| {
+            let current = if position == machine.position() {
+                "=>"
+            } else {
+                "  "
+            };
+            let breakpoint = if breakpoints.contains(&position) {
+                "*"
+            } else {
+                " "
+            };
+            format!(
+                "{}{} {:>5}  {}\n",
+                current, breakpoint, position, program[position]
+            )
+        })
+        .collect()
+}
+
+fn info(machine: &Machine) -> String {
+    let breakpoints: Vec<String> = machine.breakpoints().map(|b| b.to_string()).collect();
+    format!(
+        "{}\n{} instructions run\nbreakpoints: {}",
+        describe(machine, &machine.status()),
+        machine.trace().len(),
+        if breakpoints.is_empty() {
+            String::from("none")
+        } else {
+            breakpoints.join(", ")
+        }
+    )
+}
+
+// the fix is only reported - the program being debugged is left as it is
+fn fix(machine: &Machine) -> String {
+    match vm::repair(machine.program()) {
+        Ok(Some(repair)) => {
+            let path: Vec<String> = repair.path.iter().map(|p| p.to_string()).collect();
+            format!(
+                "swapping instruction {} (line {}) from {} to {} lets the program terminate, \
+                 with acc = {}\nthe path it takes is {}",
+                repair.position,
+                repair.position + 1,
+                repair.original,
+                repair.replacement,
+                repair.acc,
+                path.join(" -> ")
+            )
+        }
+        Ok(None) => String::from("no single jmp/nop swap makes the program terminate"),
+        Err(e) => e.to_string(),
+    }
+}
+
+// carries out a command, giving what to show for it, or None to quit
+fn execute(machine: &mut Machine, command: Command) -> Option<String> {
+    let shown = match command {
+        Command::Step(n) => {
+            let mut status = Ok(machine.status());
+            for _ in 0..n {
+                status = machine.step();
+                if !matches!(status, Ok(Status::Ready)) {
+                    break;
+                }
+            }
+            match status {
+                Ok(status) => describe(machine, &status),
+                Err(e) => e.to_string(),
+            }
+        }
+        Command::Continue => match machine.run() {
+            Ok(status) => describe(machine, &status),
+            Err(e) => e.to_string(),
+        },
+        Command::Break(position) if position >= machine.program().len() => format!(
+            "there's no instruction {} - the program has {}",
+            position,
+            machine.program().len()
+        ),
+        Command::Break(position) => {
+            if machine.toggle_breakpoint(position) {
+                format!("breakpoint set at {}", position)
+            } else {
+                format!("breakpoint at {} cleared", position)
+            }
+        }
+        Command::List(around) => {
+            let around = around.unwrap_or_else(|| machine.position());
+            if around >= machine.program().len() {
+                format!("there's no instruction {}", around)
+            } else {
+                list(machine, around).trim_end().to_owned()
+            }
+        }
+        Command::Trace(n) => {
+            let trace = machine.trace();
+            let entries: Vec<String> = trace[trace.len().saturating_sub(n)..]
+                .iter()
+                .map(|entry| entry.to_string())
+                .collect();
+            if entries.is_empty() {
+                String::from("nothing has been run yet")
+            } else {
+                format!("  step    pos  instr     acc\n{}", entries.join("\n"))
+            }
+        }
+        Command::Dump => vm::disassemble(machine.program()).trim_end().to_owned(),
+        Command::Info => info(machine),
+        Command::Fix => fix(machine),
+        Command::Reset => {
+            machine.reset();
+            describe(machine, &machine.status())
+        }
+        Command::Help => String::from(HELP),
+        Command::Quit => return None,
+    };
+    Some(shown)
+}
+
+/// Steps through a program, reading commands from `input` and showing the results on
+/// `output`, until told to quit or the input runs out.
+pub fn debug(
+    program: Vec<Instruction>,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> io::Result<()> {
+    let mut machine = Machine::new(program);
+    writeln!(
+        output,
+        "loaded {} instructions - type help for the commands",
+        machine.program().len()
+    )?;
+    if !machine.program().is_empty() {
+        writeln!(output, "{}", describe(&machine, &machine.status()))?;
+    }
+    let mut lines = input.lines();
+    loop {
+        write!(output, "(debug) ")?;
+        output.flush()?;
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_command(&line) {
+            Ok(command) => match execute(&mut machine, command) {
+                Some(shown) => writeln!(output, "{}", shown)?,
+                None => break,
+            },
+            Err(message) => writeln!(output, "{}", message)?,
+        }
+    }
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6
+";
+
+    fn session(commands: &str) -> String {
+        let mut output = Vec::new();
+        debug(
+            vm::assemble(EXAMPLE).unwrap(),
+            commands.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(parse_command("s"), Ok(Command::Step(1)));
+        assert_eq!(parse_command("step 5"), Ok(Command::Step(5)));
+        assert_eq!(parse_command("  b 3 "), Ok(Command::Break(3)));
+        assert_eq!(parse_command("list"), Ok(Command::List(None)));
+        assert!(parse_command("break").is_err());
+        assert!(parse_command("step five").is_err());
+        assert!(parse_command("quit 2").is_err());
+        assert!(parse_command("jump 3").is_err());
+    }
+
+    #[test]
+    fn stepping_through() {
+        let output = session("step 2\nbreak 7\ncontinue\ncontinue\ntrace 2\nquit\nstep\n");
+        assert!(output.contains("next is 2: jmp +4  (acc = 1)"));
+        assert!(output.contains("breakpoint set at 7"));
+        assert!(output.contains("stopped at breakpoint 7: jmp -4  (acc = 2)"));
+        assert!(output.contains("the loop goes through 1 -> 2 -> 6 -> 7 -> 3 -> 4"));
+        assert!(output.contains("     5      3  acc +3    acc = 5"));
+        // nothing after quitting is run
+        assert_eq!(output.matches("(debug) ").count(), 6);
+    }
+
+    #[test]
+    fn fixing() {
+        let output = session("fix\n");
+        assert!(output.contains(
+            "swapping instruction 7 (line 8) from jmp -4 to nop -4 lets the program terminate, \
+             with acc = 8\nthe path it takes is 0 -> 1 -> 2 -> 6 -> 7 -> 8\n"
+        ));
+    }
+
+    #[test]
+    fn listing() {
+        let output = session("b 1\nlist\n");
+        assert!(output.contains("=>      0  nop +0\n  *     1  acc +1\n"));
+        assert!(!output.contains("acc +6"));
+    }
+
+    #[test]
+    fn dumping() {
+        let output = session("dump\n");
+        assert!(output.contains(&format!("(debug) {}", EXAMPLE)));
+    }
+}
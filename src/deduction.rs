@@ -0,0 +1,560 @@
+/*
+Puzzles where each of a number of items has to be given its own value, out of a set of
+candidates for each, such that no two items share a value. Items and values are both
+numbered from 0, and it's up to the caller to say what they stand for.
+
+The answer is deduced the way it would be by hand, so that it can be explained. An item
+with only one candidate left must have it, so no other item can - and if there's only one
+answer, there's always such an item, so this alone finds it. Getting stuck means there's
+more than one answer, in which case guesses are made (and undone when they leave an item
+with no candidates at all) to find two of them, and show where they differ.
+
+Before any of that, the candidates are checked as a bipartite matching between the items
+and the values, to find whether there's any answer at all without having to try every
+guess. When there isn't, the matching finds a group of items with fewer candidates between
+them than there are items, to say why.
+*/
+/// One step in working out the answer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Step {
+    /// The item had only the one value left, which was then ruled out for `others`.
+    OnlyCandidate {
+        item: usize,
+        value: usize,
+        others: Vec<usize>,
+    },
+    /// Nothing more could be deduced, so the item was tried with the value, out of
+    /// `choices` it could still have.
+    Guess {
+        item: usize,
+        value: usize,
+        choices: usize,
+    },
+    /// The guess led to a contradiction, and was undone.
+    Undo { item: usize, value: usize },
+}
+
+/// The value given to each item, and how they were worked out.
+#[derive(Debug)]
+pub struct Deduction {
+    pub assignment: Vec<usize>,
+    pub trace: Vec<Step>,
+}
+
+/// How an explanation is written out: a sentence for each step, or JSON to be read by
+/// another program.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// The steps of a deduction, along with what its items and values stand for.
+#[derive(Debug)]
+pub struct Explanation {
+    pub items: Vec<String>,
+    pub values: Vec<String>,
+    pub trace: Vec<Step>,
+}
+
+impl Explanation {
+    fn describe(&self, step: &Step) -> String {
+        step.describe(|i| self.items[i].clone(), |v| self.values[v].clone())
+    }
+
+    /// Each step in turn, numbered from 1.
+    pub fn render_text(&self) -> String {
+        self.trace
+            .iter()
+            .enumerate()
+            .map(|(n, step)| format!("{:>4}. {}\n", n + 1, self.describe(step)))
+            .collect()
+    }
+
+    /// A JSON array with an object for each step, giving its kind, the names of the items
+    /// and values it's about, and the sentence `render_text` would give for it.
+    pub fn render_json(&self) -> String {
+        let steps: Vec<String> = self
+            .trace
+            .iter()
+            .map(|step| {
+                let (kind, item, value, extra) = match step {
+                    Step::OnlyCandidate {
+                        item,
+                        value,
+                        others,
+                    } => {
+                        let others: Vec<String> = others
+                            .iter()
+                            .map(|&i| json_string(&self.items[i]))
+                            .collect();
+                        (
+                            "only_candidate",
+                            item,
+                            value,
+                            format!("\"ruled_out_for\": [{}], ", others.join(", ")),
+                        )
+                    }
+                    Step::Guess {
+                        item,
+                        value,
+                        choices,
+                    } => ("guess", item, value, format!("\"choices\": {}, ", choices)),
+                    Step::Undo { item, value } => ("undo", item, value, String::new()),
+                };
+                format!(
+                    "  {{\"kind\": \"{}\", \"item\": {}, \"value\": {}, {}\"text\": {}}}",
+                    kind,
+                    json_string(&self.items[*item]),
+                    json_string(&self.values[*value]),
+                    extra,
+                    json_string(&self.describe(step))
+                )
+            })
+            .collect();
+        if steps.is_empty() {
+            String::from("[]")
+        } else {
+            format!("[\n{}\n]", steps.join(",\n"))
+        }
+    }
+}
+
+/// `s` as a JSON string. Names come from the puzzle input, so could hold anything.
+pub fn json_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Why no answer could be deduced.
+#[derive(Debug, PartialEq)]
+pub enum Failure {
+    /// These items have only these values between them, which isn't enough to go round.
+    Shortage {
+        items: Vec<usize>,
+        values: Vec<usize>,
+    },
+    /// There's more than one answer: the item can have either of these values, with the
+    /// other items still each getting one.
+    Ambiguous { item: usize, values: [usize; 2] },
+}
+
+impl Failure {
+    /// Says what went wrong, using the given names for items and values.
+    pub fn describe(
+        &self,
+        item_name: impl Fn(usize) -> String,
+        value_name: impl Fn(usize) -> String,
+    ) -> String {
+        match self {
+            Failure::Shortage { items, values } if values.is_empty() => {
+                format!("{} can't be anything", item_name(items[0]))
+            }
+            Failure::Shortage { items, values } => {
+                let items: Vec<String> = items.iter().map(|&i| item_name(i)).collect();
+                let values: Vec<String> = values.iter().map(|&v| value_name(v)).collect();
+                format!(
+                    "{} can only be {} between them",
+                    items.join(", "),
+                    values.join(", ")
+                )
+            }
+            Failure::Ambiguous {
+                item,
+                values: [a, b],
+            } => format!(
+                "{} could be {} or {}",
+                item_name(*item),
+                value_name(*a),
+                value_name(*b)
+            ),
+        }
+    }
+}
+
+impl Step {
+    /// Says what the step deduced, using the given names for items and values.
+    pub fn describe(
+        &self,
+        item_name: impl Fn(usize) -> String,
+        value_name: impl Fn(usize) -> String,
+    ) -> String {
+        match self {
+            Step::OnlyCandidate {
+                item,
+                value,
+                others,
+            } if others.is_empty() => format!(
+                "{} must be {}, as it's the only candidate left",
+                item_name(*item),
+                value_name(*value)
+            ),
+            Step::OnlyCandidate {
+                item,
+                value,
+                others,
+            } => {
+                let others: Vec<String> = others.iter().map(|&i| item_name(i)).collect();
+                format!(
+                    "{} must be {}, as it's the only candidate left - so {} can't be",
+                    item_name(*item),
+                    value_name(*value),
+                    others.join(", ")
+                )
+            }
+            Step::Guess {
+                item,
+                value,
+                choices,
+            } => format!(
+                "stuck, so guessing that {} is {}, out of {} choices",
+                item_name(*item),
+                value_name(*value),
+                choices
+            ),
+            Step::Undo { item, value } => format!(
+                "{} can't be {} after all, as that leads to a contradiction",
+                item_name(*item),
+                value_name(*value)
+            ),
+        }
+    }
+}
+
+// a matching is built up an item at a time: each tries to take a value nobody has yet, or
+// one whose current item can be moved to another of its values, and so on along an
+// augmenting path
+struct Matching<'a> {
+    candidates: &'a [Vec<usize>],
+    // the item each value has been given to, if any
+    item_of: Vec<Option<usize>>,
+}
+
+impl Matching<'_> {
+    // tries to give the item a value, moving others around as needed. Values already
+    // looked at on this attempt are in `seen`
+    fn augment(&mut self, item: usize, seen: &mut Vec<bool>) -> bool {
+        for &value in &self.candidates[item] {
+            if seen[value] {
+                continue;
+            }
+            seen[value] = true;
+            let free = match self.item_of[value] {
+                None => true,
+                Some(other) => self.augment(other, seen),
+            };
+            if free {
+                self.item_of[value] = Some(item);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+// checks there's at least one answer, by giving every item a value
+fn check_matching(candidates: &[Vec<usize>], values: usize) -> Result<(), Failure> {
+    let mut matching = Matching {
+        candidates,
+        item_of: vec![None; values],
+    };
+    for item in 0..candidates.len() {
+        let mut seen = vec![false; values];
+        if !matching.augment(item, &mut seen) {
+            // every value looked at is taken, by items which could only find their way to
+            // those values - so there are more items than values between them
+            let values: Vec<usize> = (0..seen.len()).filter(|&v| seen[v]).collect();
+            let mut items: Vec<usize> =
+                values.iter().filter_map(|&v| matching.item_of[v]).collect();
+            items.push(item);
+            items.sort_unstable();
+            return Err(Failure::Shortage { items, values });
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone)]
+struct State {
+    candidates: Vec<Vec<usize>>,
+    assigned: Vec<Option<usize>>,
+}
+
+impl State {
+    fn assign(&mut self, item: usize, value: usize) -> Vec<usize> {
+        self.assigned[item] = Some(value);
+        self.candidates[item] = vec![value];
+        let mut others = vec![];
+        for (other, candidates) in self.candidates.iter_mut().enumerate() {
+            if other != item && candidates.contains(&value) {
+                candidates.retain(|&v| v != value);
+                others.push(other);
+            }
+        }
+        others
+    }
+}
+
+struct Solver {
+    trace: Vec<Step>,
+}
+
+impl Solver {
+    // makes every deduction it can without guessing, saying whether that led to a
+    // contradiction
+    fn propagate(&mut self, state: &mut State) -> bool {
+        loop {
+            let unassigned: Vec<usize> = (0..state.assigned.len())
+                .filter(|&i| state.assigned[i].is_none())
+                .collect();
+            if unassigned.iter().any(|&i| state.candidates[i].is_empty()) {
+                return false;
+            }
+            let item = match unassigned
+                .into_iter()
+                .find(|&i| state.candidates[i].len() == 1)
+            {
+                Some(item) => item,
+                None => return true,
+            };
+            let value = state.candidates[item][0];
+            let others = state.assign(item, value);
+            self.trace.push(Step::OnlyCandidate {
+                item,
+                value,
+                others,
+            });
+        }
+    }
+
+    // finds answers, up to two of them, adding them to `found`
+    fn search(&mut self, mut state: State, found: &mut Vec<Vec<usize>>) {
+        if !self.propagate(&mut state) {
+            return;
+        }
+        let item = (0..state.assigned.len())
+            .filter(|&i| state.assigned[i].is_none())
+            .min_by_key(|&i| state.candidates[i].len());
+        let item = match item {
+            Some(item) => item,
+            None => {
+                found.extend(state.assigned.into_iter().collect::<Option<Vec<usize>>>());
+                return;
+            }
+        };
+        let choices = state.candidates[item].clone();
+        for &value in &choices {
+            self.trace.push(Step::Guess {
+                item,
+                value,
+                choices: choices.len(),
+            });
+            let mut guess = state.clone();
+            guess.assign(item, value);
+            let before = found.len();
+            self.search(guess, found);
+            if found.len() == 2 {
+                return;
+            }
+            if found.len() == before {
+                self.trace.push(Step::Undo { item, value });
+            }
+        }
+    }
+}
+
+/// Gives each item one of its candidates, out of values numbered from 0 up to `values`,
+/// with no two items given the same one - as long as there's exactly one way to do that.
+pub fn solve(candidates: &[Vec<usize>], values: usize) -> Result<Deduction, Failure> {
+    check_matching(candidates, values)?;
+    let mut solver = Solver { trace: vec![] };
+    let state = State {
+        candidates: candidates.to_vec(),
+        assigned: vec![None; candidates.len()],
+    };
+    let mut found = vec![];
+    solver.search(state, &mut found);
+    match found.as_slice() {
+        [assignment] => Ok(Deduction {
+            assignment: assignment.clone(),
+            trace: solver.trace,
+        }),
+        [first, second] => {
+            let item = (0..first.len())
+                .find(|&i| first[i] != second[i])
+                .expect("two different answers differ somewhere");
+            Err(Failure::Ambiguous {
+                item,
+                values: [first[item], second[item]],
+            })
+        }
+        _ => unreachable!("there's an answer, as a matching was found"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(candidates: &[Vec<usize>]) -> (Vec<Vec<usize>>, Vec<Step>) {
+        let mut solver = Solver { trace: vec![] };
+        let mut found = vec![];
+        let state = State {
+            candidates: candidates.to_vec(),
+            assigned: vec![None; candidates.len()],
+        };
+        solver.search(state, &mut found);
+        (found, solver.trace)
+    }
+
+    #[test]
+    fn only_candidates() {
+        let deduction = solve(&[vec![0, 1], vec![1], vec![0, 1, 2]], 3).unwrap();
+        assert_eq!(deduction.assignment, [0, 1, 2]);
+        assert_eq!(
+            deduction.trace,
+            [
+                Step::OnlyCandidate {
+                    item: 1,
+                    value: 1,
+                    others: vec![0, 2]
+                },
+                Step::OnlyCandidate {
+                    item: 0,
+                    value: 0,
+                    others: vec![2]
+                },
+                Step::OnlyCandidate {
+                    item: 2,
+                    value: 2,
+                    others: vec![]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn spare_values() {
+        let deduction = solve(&[vec![3, 1], vec![1], vec![0, 3]], 5).unwrap();
+        assert_eq!(deduction.assignment, [3, 1, 0]);
+    }
+
+    #[test]
+    fn ambiguous_answers() {
+        assert_eq!(
+            solve(&[vec![0, 1], vec![0, 1], vec![0, 1, 2]], 3).unwrap_err(),
+            Failure::Ambiguous {
+                item: 0,
+                values: [0, 1]
+            }
+        );
+        assert!(matches!(
+            solve(&[vec![0, 1], vec![1, 2]], 3),
+            Err(Failure::Ambiguous { item: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn undoing_guesses() {
+        let (found, trace) = search(&[vec![0, 1], vec![0, 1], vec![0, 1]]);
+        assert!(found.is_empty());
+        assert_eq!(
+            trace,
+            [
+                Step::Guess {
+                    item: 0,
+                    value: 0,
+                    choices: 2
+                },
+                Step::OnlyCandidate {
+                    item: 1,
+                    value: 1,
+                    others: vec![2]
+                },
+                Step::Undo { item: 0, value: 0 },
+                Step::Guess {
+                    item: 0,
+                    value: 1,
+                    choices: 2
+                },
+                Step::OnlyCandidate {
+                    item: 1,
+                    value: 0,
+                    others: vec![2]
+                },
+                Step::Undo { item: 0, value: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn shortages() {
+        assert_eq!(
+            solve(&[vec![0], vec![0], vec![0, 1, 2]], 3).unwrap_err(),
+            Failure::Shortage {
+                items: vec![0, 1],
+                values: vec![0]
+            }
+        );
+        let failure = solve(&[vec![0], vec![]], 2).unwrap_err();
+        assert_eq!(
+            failure.describe(|i| format!("item {}", i), |v| v.to_string()),
+            "item 1 can't be anything"
+        );
+    }
+
+    #[test]
+    fn explaining() {
+        let explanation = Explanation {
+            items: vec![String::from("a"), String::from("b \"2\"")],
+            values: vec![String::from("x"), String::from("y")],
+            trace: solve(&[vec![0, 1], vec![1]], 2).unwrap().trace,
+        };
+        assert_eq!(
+            explanation.render_text(),
+            "   1. b \"2\" must be y, as it's the only candidate left - so a can't be\n   \
+             2. a must be x, as it's the only candidate left\n"
+        );
+        assert_eq!(
+            explanation.render_json(),
+            "[\n  {\"kind\": \"only_candidate\", \"item\": \"b \\\"2\\\"\", \"value\": \"y\", \
+             \"ruled_out_for\": [\"a\"], \"text\": \"b \\\"2\\\" must be y, as it's the only \
+             candidate left - so a can't be\"},\n  {\"kind\": \"only_candidate\", \"item\": \"a\", \
+             \"value\": \"x\", \"ruled_out_for\": [], \"text\": \"a must be x, as it's the only \
+             candidate left\"}\n]"
+        );
+    }
+
+    #[test]
+    fn describing_steps() {
+        let step = Step::OnlyCandidate {
+            item: 1,
+            value: 3,
+            others: vec![0, 2],
+        };
+        assert_eq!(
+            step.describe(|i| format!("position {}", i + 1), |v| format!("'{}'", v)),
+            "position 2 must be '3', as it's the only candidate left - so position 1, position 3 \
+             can't be"
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// Everything that can go wrong when reading, parsing or solving a puzzle.
+#[derive(Debug)]
+pub enum Error {
+    // the input couldn't be read at all
+    Io {
+        source: String,
+        error: io::Error,
+    },
+    // the input was read, but isn't in the expected format. Lines and columns count from 1
+    Parse {
+        line: usize,
+        column: usize,
+        text: String,
+        message: String,
+    },
+    // the input is well-formed, but the puzzle has no answer for it
+    NoSolution(String),
+    // the input is well-formed, but relies on something the solution doesn't handle
+    Unsupported(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// A parse error about `text`, which should be a slice of `line` - the column is worked
+    /// out from where it sits. The line number is 1 until adjusted by `offset_lines`, so
+    /// that parsers of a single line don't need to know where that line is.
+    pub fn parse(line: &str, text: &str, message: &str) -> Error {
+        let offset = (text.as_ptr() as usize).wrapping_sub(line.as_ptr() as usize);
+        let column = if offset <= line.len() { offset + 1 } else { 1 };
+        Error::Parse {
+            line: 1,
+            column,
+            text: text.to_owned(),
+            message: message.to_owned(),
+        }
+    }
+
+    /// Moves a parse error down by the given number of lines, for when the text it came from
+    /// started part-way through the input.
+    pub fn offset_lines(self, offset: usize) -> Error {
+        match self {
+            Error::Parse {
+                line,
+                column,
+                text,
+                message,
+            } => Error::Parse {
+                line: line + offset,
+                column,
+                text,
+                message,
+            },
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io { source, error } => write!(f, "couldn't read {}: {}", source, error),
+            Error::Parse {
+                line,
+                column,
+                text,
+                message,
+            } => write!(
+                f,
+                "parse error at line {}, column {}: {} (found '{}')",
+                line, column, message, text
+            ),
+            Error::NoSolution(message) => write!(f, "no solution exists: {}", message),
+            Error::Unsupported(message) => write!(f, "unsupported input: {}", message),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// Parses a number which makes up `text`, a slice of `line`.
+pub fn parse_number<T: FromStr>(line: &str, text: &str) -> Result<T> {
+    text.parse()
+        .map_err(|_| Error::parse(line, text, "expected a number"))
+}
+
+/// Parses each line of the input in turn, with any error pointing at the line it came from.
+pub fn parse_lines<T>(input: &str, parse_line: impl Fn(&str) -> Result<T>) -> Result<Vec<T>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| parse_line(line).map_err(|e| e.offset_lines(i)))
+        .collect()
+}
+
+/// Parses each group of lines in the input, where groups are separated by blank lines. The
+/// parser sees the lines of one group at a time, and any error it gives is moved to point
+/// at the right line of the whole input.
+pub fn parse_groups<T>(input: &str, parse_group: impl Fn(&str) -> Result<T>) -> Result<Vec<T>> {
+    let mut groups = Vec::new();
+    let mut start = 0;
+    let mut current: Vec<&str> = Vec::new();
+    for (i, line) in input.lines().chain(std::iter::once("")).enumerate() {
+        if !line.is_empty() {
+            if current.is_empty() {
+                start = i;
+            }
+            current.push(line);
+        } else if !current.is_empty() {
+            let group = parse_group(&current.join("\n")).map_err(|e| e.offset_lines(start))?;
+            groups.push(group);
+            current.clear();
+        }
+    }
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_come_from_the_slice() {
+        let line = "mem[8] = x";
+        match Error::parse(line, &line[9..], "expected a number") {
+            Error::Parse { line, column, .. } => assert_eq!((line, column), (1, 10)),
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn lines_are_offset() {
+        let result = parse_lines("1\n2\nx\n", |n| parse_number::<u8>(n, n));
+        assert!(matches!(
+            result,
+            Err(Error::Parse {
+                line: 3,
+                column: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn groups_are_offset() {
+        let result = parse_groups("a\nb\n\n\nc\nd\n", |g| {
+            match g.lines().position(|l| l == "d") {
+                Some(i) => Err(Error::parse(g, g, "found d").offset_lines(i)),
+                None => Ok(()),
+            }
+        });
+        assert!(matches!(result, Err(Error::Parse { line: 6, .. })));
+    }
+}
@@ -0,0 +1,332 @@
+use crate::error::{parse_lines, Error, Result};
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+/// The four directions along the rows and columns, as (row, column) steps.
+pub const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+/// All eight directions, diagonals included, as (row, column) steps.
+pub const ALL_DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// A rectangle of cells, such as a map read in from the puzzle input. Positions are given
+/// as (row, column), counting from the top left.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    // row by row
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Builds a grid from its rows, which must all be the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Option<Grid<T>> {
+        let height = rows.len();
+        let width = rows.first().map_or(0, |row| row.len());
+        if rows.iter().any(|row| row.len() != width) {
+            return None;
+        }
+        Some(Grid {
+            width,
+            height,
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    /// Reads a map with a character for each cell, such as
+    ///
+    /// ```text
+    /// ..#
+    /// #.#
+    /// ```
+    ///
+    /// using `cell` to say what each character stands for. A character it doesn't know is
+    /// reported as a parse error, along with `expected` to say what should have been there,
+    /// and so is a map with no rows at all.
+    pub fn parse(input: &str, cell: impl Fn(char) -> Option<T>, expected: &str) -> Result<Grid<T>> {
+        let width = input.lines().next().map_or(0, |l| l.chars().count());
+        let rows: Vec<Vec<T>> = parse_lines(input, |l| {
+            if l.is_empty() || l.chars().count() != width {
+                return Err(Error::parse(
+                    l,
+                    l,
+                    "rows must all have the same, non-zero, width",
+                ));
+            }
+            l.char_indices()
+                .map(|(i, c)| {
+                    cell(c).ok_or_else(|| Error::parse(l, &l[i..i + c.len_utf8()], expected))
+                })
+                .collect()
+        })?;
+        if rows.is_empty() {
+            return Err(Error::parse(input, input, "expected at least one row"));
+        }
+        Ok(Grid {
+            width,
+            height: rows.len(),
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        if row < self.height && col < self.width {
+            Some(&self.cells[row * self.width + col])
+        } else {
+            None
+        }
+    }
+
+    /// The cell at a position, where the grid repeats forever in every direction - so going
+    /// off one side comes back in on the other.
+    pub fn get_wrapping(&self, row: usize, col: usize) -> &T {
+        &self[(row % self.height, col % self.width)]
+    }
+
+    /// Every position in the grid, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.height).flat_map(move |row| (0..width).map(move |col| (row, col)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+
+    /// The position one step away in the given direction, if that's still in the grid.
+    pub fn step(
+        &self,
+        (row, col): (usize, usize),
+        (down, right): (isize, isize),
+    ) -> Option<(usize, usize)> {
+        let row = row.checked_add_signed(down)?;
+        let col = col.checked_add_signed(right)?;
+        if row < self.height && col < self.width {
+            Some((row, col))
+        } else {
+            None
+        }
+    }
+
+    /// The positions next to the given one, in any of `directions`, which are in the grid.
+    pub fn neighbours<'a>(
+        &'a self,
+        position: (usize, usize),
+        directions: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        directions
+            .iter()
+            .filter_map(move |&direction| self.step(position, direction))
+    }
+
+    // nothing uses the 4-neighbourhood yet, but it's here beside the 8 for when a puzzle does
+    #[allow(dead_code)]
+    pub fn neighbours_4(
+        &self,
+        position: (usize, usize),
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbours(position, &ORTHOGONAL)
+    }
+
+    pub fn neighbours_8(
+        &self,
+        position: (usize, usize),
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbours(position, &ALL_DIRECTIONS)
+    }
+
+    /// Every position passed on the way from the given one (which isn't included) to the
+    /// edge of the grid, going in a straight line in one direction.
+    pub fn ray(
+        &self,
+        position: (usize, usize),
+        direction: (isize, isize),
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        std::iter::successors(self.step(position, direction), move |&next| {
+            self.step(next, direction)
+        })
+    }
+
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Draws the grid with a character for each cell, and a line for each row.
+    pub fn render(&self, cell: impl Fn(&T) -> char) -> String {
+        (0..self.height)
+            .map(|row| {
+                let mut line: String = self.row(row).iter().map(&cell).collect();
+                line.push('\n');
+                line
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Grid<char> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(|&c| c))
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    /// The grid turned a quarter turn clockwise.
+    pub fn rotate_right(&self) -> Grid<T> {
+        Grid {
+            width: self.height,
+            height: self.width,
+            cells: (0..self.width)
+                .flat_map(|col| (0..self.height).rev().map(move |row| (row, col)))
+                .map(|position| self[position].clone())
+                .collect(),
+        }
+    }
+
+    /// The grid turned upside down, so the top row becomes the bottom one.
+    pub fn flip(&self) -> Grid<T> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: (0..self.height)
+                .rev()
+                .flat_map(|row| self.row(row).iter().cloned())
+                .collect(),
+        }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        assert!(
+            row < self.height && col < self.width,
+            "({}, {}) is outside a {}x{} grid",
+            row,
+            col,
+            self.width,
+            self.height
+        );
+        &self.cells[row * self.width + col]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        assert!(
+            row < self.height && col < self.width,
+            "({}, {}) is outside a {}x{} grid",
+            row,
+            col,
+            self.width,
+            self.height
+        );
+        &mut self.cells[row * self.width + col]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn letters() -> Grid<char> {
+        Grid::parse("abc\ndef\n", Some, "any character").unwrap()
+    }
+
+    #[test]
+    fn parsing() {
+        let grid = Grid::parse("..#\n#..\n", |c| Some(c == '#'), "'.' or '#'").unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert!(grid[(0, 2)]);
+        assert!(!grid[(1, 1)]);
+        assert!(matches!(
+            Grid::parse(
+                "..#\n#x.\n",
+                |c| Some(c == '#').filter(|_| c != 'x'),
+                "'.' or '#'"
+            ),
+            Err(Error::Parse {
+                line: 2,
+                column: 2,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Grid::parse("..#\n#.\n", Some, "anything"),
+            Err(Error::Parse { line: 2, .. })
+        ));
+        assert!(matches!(
+            Grid::parse("", Some, "anything"),
+            Err(Error::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn access() {
+        let grid = letters();
+        assert_eq!(grid.get(1, 2), Some(&'f'));
+        assert_eq!(grid.get(2, 0), None);
+        assert_eq!(grid.get_wrapping(3, 4), &'e');
+        assert_eq!(grid.row(1), &['d', 'e', 'f']);
+    }
+
+    #[test]
+    fn neighbours() {
+        let grid = letters();
+        let at = |positions: Vec<(usize, usize)>| -> String {
+            positions.into_iter().map(|p| grid[p]).collect()
+        };
+        assert_eq!(at(grid.neighbours_4((0, 0)).collect()), "bd");
+        assert_eq!(at(grid.neighbours_4((1, 1)).collect()), "bdf");
+        assert_eq!(at(grid.neighbours((1, 2), &ORTHOGONAL).collect()), "ce");
+        assert_eq!(at(grid.neighbours_8((0, 1)).collect()), "acdef");
+        assert_eq!(at(grid.ray((1, 0), (0, 1)).collect()), "ef");
+        assert_eq!(at(grid.ray((0, 0), (1, 1)).collect()), "e");
+        assert_eq!(grid.ray((0, 0), (-1, 0)).count(), 0);
+    }
+
+    #[test]
+    fn rendering() {
+        let grid = letters();
+        assert_eq!(grid.render(|&c| c.to_ascii_uppercase()), "ABC\nDEF\n");
+        assert_eq!(grid.to_string(), "abc\ndef\n");
+    }
+
+    #[test]
+    fn rotating_and_flipping() {
+        let grid = letters();
+        assert_eq!(grid.rotate_right().render(|&c| c), "da\neb\nfc\n");
+        assert_eq!(grid.flip().render(|&c| c), "def\nabc\n");
+        let turned_round = grid
+            .rotate_right()
+            .rotate_right()
+            .rotate_right()
+            .rotate_right();
+        assert_eq!(turned_round, grid);
+    }
+}
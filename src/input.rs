@@ -0,0 +1,51 @@
+use crate::error::{Error, Result};
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// Where a puzzle's input comes from. Unless told otherwise, each day reads its own file
+/// from the `input` directory of this repository, wherever the program is run from.
+pub enum InputSource {
+    Default,
+    File(PathBuf),
+    Stdin,
+    Text(String),
+}
+
+impl InputSource {
+    pub fn read(&self, day: u8) -> Result<String> {
+        match self {
+            InputSource::Default => read_file(&default_path(day)),
+            InputSource::File(path) => read_file(path),
+            InputSource::Stdin => {
+                let mut contents = String::new();
+                match io::stdin().read_to_string(&mut contents) {
+                    Ok(_) => Ok(contents),
+                    Err(error) => Err(Error::Io {
+                        source: String::from("standard input"),
+                        error,
+                    }),
+                }
+            }
+            InputSource::Text(contents) => Ok(contents.to_owned()),
+        }
+    }
+}
+
+pub fn default_path(day: u8) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("input")
+        .join(format!("input{}.txt", day))
+}
+
+fn read_file(path: &Path) -> Result<String> {
+    let mut contents = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut contents))
+        .map_err(|error| Error::Io {
+            source: path.display().to_string(),
+            error,
+        })?;
+    Ok(contents)
+}
@@ -1,54 +1,24 @@
+mod answers;
+mod automaton;
+mod bench;
+mod calc;
+mod cli;
+mod debugger;
+mod deduction;
+mod error;
+mod grid;
+mod input;
+mod modular;
 mod solutions;
+mod vm;
 
-use crate::solutions::*;
+use std::env;
+use std::process;
 
 fn main() {
-    println!("The answer to day 1, part 1 is {}", day1::part_1());
-    println!("The answer to day 1, part 2 is {}", day1::part_2());
-    println!("The answer to day 2, part 1 is {}", day2::part_1());
-    println!("The answer to day 2, part 2 is {}", day2::part_2());
-    println!("The answer to day 3, part 1 is {}", day3::part_1());
-    println!("The answer to day 3, part 2 is {}", day3::part_2());
-    println!("The answer to day 4, part 1 is {}", day4::part_1());
-    println!("The answer to day 4, part 2 is {}", day4::part_2());
-    println!("The answer to day 5, part 1 is {}", day5::part_1());
-    println!("The answer to day 5, part 2 is {}", day5::part_2());
-    println!("The answer to day 6, part 1 is {}", day6::part_1());
-    println!("The answer to day 6, part 2 is {}", day6::part_2());
-    println!("The answer to day 7, part 1 is {}", day7::part_1());
-    println!("The answer to day 7, part 2 is {}", day7::part_2());
-    println!("The answer to day 8, part 1 is {}", day8::part_1());
-    println!("The answer to day 8, part 2 is {}", day8::part_2());
-    println!("The answer to day 9, part 1 is {}", day9::part_1());
-    println!("The answer to day 9, part 2 is {}", day9::part_2());
-    println!("The answer to day 10, part 1 is {}", day10::part_1());
-    println!("The answer to day 10, part 2 is {}", day10::part_2());
-    println!("The answer to day 11, part 1 is {}", day11::part_1());
-    println!("The answer to day 11, part 2 is {}", day11::part_2());
-    println!("The answer to day 12, part 1 is {}", day12::part_1());
-    println!("The answer to day 12, part 2 is {}", day12::part_2());
-    println!("The answer to day 13, part 1 is {}", day13::part_1());
-    println!("The answer to day 13, part 2 is {}", day13::part_2());
-    println!("The answer to day 14, part 1 is {}", day14::part_1());
-    println!("The answer to day 14, part 2 is {}", day14::part_2());
-    println!("The answer to day 15, part 1 is {}", day15::part_1());
-    println!("The answer to day 15, part 2 is {}", day15::part_2());
-    println!("The answer to day 16, part 1 is {}", day16::part_1());
-    println!("The answer to day 16, part 2 is {}", day16::part_2());
-    println!("The answer to day 17, part 1 is {}", day17::part_1());
-    println!("The answer to day 17, part 2 is {}", day17::part_2());
-    println!("The answer to day 18, part 1 is {}", day18::part_1());
-    println!("The answer to day 18, part 2 is {}", day18::part_2());
-    println!("The answer to day 19, part 1 is {}", day19::part_1());
-    //println!("The answer to day 19, part 2 is {}", day19::part_2());
-    println!("The answer to day 20, part 1 is {}", day20::part_1());
-    println!("The answer to day 21, part 1 is {}", day21::part_1());
-    println!("The answer to day 21, part 2 is {}", day21::part_2());
-    println!("The answer to day 22, part 1 is {}", day22::part_1());
-    println!("The answer to day 22, part 2 is {}", day22::part_2());
-    println!("The answer to day 23, part 1 is {}", day23::part_1());
-    //println!("The answer to day 23, part 2 is {}", day23::part_2());
-    println!("The answer to day 24, part 1 is {}", day24::part_1());
-    println!("The answer to day 24, part 2 is {}", day24::part_2());
-    println!("The answer to day 25, part 1 is {}", day25::part_1());
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(message) = cli::parse_args(&args).and_then(cli::execute) {
+        eprintln!("error: {}", message);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,171 @@
+// Arithmetic modulo some number, for puzzles which work with remainders. Everything gives
+// values reduced to lie between 0 and the modulus, and products are worked out in 128 bits
+// so that any 64-bit modulus is fine.
+
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+
+pub fn mul(a: u64, b: u64, modulus: u64) -> u64 {
+    ((a as u128 * b as u128) % modulus as u128) as u64
+}
+
+/// `base` to the power `exp`, by repeated squaring.
+pub fn pow(base: u64, exp: u64, modulus: u64) -> u64 {
+    let mut result = 1 % modulus;
+    let mut square = base % modulus;
+    let mut exp = exp;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul(result, square, modulus);
+        }
+        square = mul(square, square, modulus);
+        exp >>= 1;
+    }
+    result
+}
+
+/// The number which gives 1 when multiplied by `a`, if there is one - which is when `a` and
+/// the modulus have no factor in common.
+pub fn inverse(a: u64, modulus: u64) -> Option<u64> {
+    // the extended Euclidean algorithm, keeping track of how each remainder is made up
+    // from `a`. Signed values are needed on the way, but the result is brought back in
+    // range at the end
+    let (mut old_r, mut r) = (i128::from(a % modulus), i128::from(modulus));
+    let (mut old_s, mut s) = (1i128, 0i128);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_s, s) = (s, old_s - quotient * s);
+    }
+    if old_r == 1 {
+        Some(old_s.rem_euclid(i128::from(modulus)) as u64)
+    } else if modulus == 1 {
+        Some(0)
+    } else {
+        None
+    }
+}
+
+// the square root of the modulus, rounded up. That's at most 2^32, so nothing worked out
+// from it overflows
+fn giant_step_size(modulus: u64) -> u64 {
+    let root = modulus.isqrt();
+    if root * root == modulus {
+        root
+    } else {
+        root + 1
+    }
+}
+
+/// The most baby steps `discrete_log` will store, which keeps the table to a couple of
+/// gigabytes at most. It's enough for any modulus up to 2^52.
+pub const MAX_BABY_STEPS: u64 = 1 << 26;
+
+/*
+The discrete logarithm: the smallest power of `base` which gives `target`, found by the
+baby-step giant-step method. With m the square root of the modulus (rounded up), any power
+can be written as i * m + j with both i and j below m. The "baby steps" base^j are all
+stored, then the "giant steps" target * base^(-m * i) are tried in turn until one is among
+them. That takes around the square root of the modulus in time and space, rather than
+the modulus itself for trying every power.
+
+This needs `base` to have an inverse, so fails if it shares a factor with the modulus (or
+the modulus is 0, leaving no remainders to work with). It also fails for a modulus so big
+that the baby steps wouldn't fit in memory. It gives None when `target` isn't a
+power of `base` at all.
+*/
+pub fn discrete_log(base: u64, target: u64, modulus: u64) -> Result<Option<u64>> {
+    if modulus == 0 {
+        return Err(Error::Unsupported(String::from(
+            "there are no remainders modulo 0",
+        )));
+    }
+    let target = target % modulus;
+    let base_inverse = inverse(base, modulus).ok_or_else(|| {
+        Error::Unsupported(format!(
+            "{} shares a factor with the modulus {}",
+            base, modulus
+        ))
+    })?;
+    let m = giant_step_size(modulus);
+    if m > MAX_BABY_STEPS {
+        return Err(Error::Unsupported(format!(
+            "the modulus {} would need {} baby steps stored, but no more than {} fit in memory",
+            modulus, m, MAX_BABY_STEPS
+        )));
+    }
+
+    // only the first power to give each value is kept, so the answer is the smallest
+    let mut baby_steps = HashMap::new();
+    let mut power = 1 % modulus;
+    for j in 0..m {
+        baby_steps.entry(power).or_insert(j);
+        power = mul(power, base, modulus);
+    }
+
+    let giant_step = pow(base_inverse, m, modulus);
+    let mut current = target;
+    for i in 0..m {
+        if let Some(&j) = baby_steps.get(&current) {
+            return Ok(Some(i * m + j));
+        }
+        current = mul(current, giant_step, modulus);
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn powers() {
+        assert_eq!(pow(7, 8, 20201227), 5764801);
+        assert_eq!(pow(2, 0, 13), 1);
+        assert_eq!(pow(5, 3, 1), 0);
+        // big enough that the products would overflow 64 bits
+        assert_eq!(pow(u64::MAX - 1, 2, u64::MAX), 1);
+    }
+
+    #[test]
+    fn inverses() {
+        assert_eq!(inverse(3, 7), Some(5));
+        assert_eq!(inverse(7, 20201227).map(|i| mul(i, 7, 20201227)), Some(1));
+        assert_eq!(inverse(4, 10), None);
+    }
+
+    #[test]
+    fn discrete_logs() {
+        assert_eq!(discrete_log(7, 5764801, 20201227).unwrap(), Some(8));
+        assert_eq!(discrete_log(7, 17807724, 20201227).unwrap(), Some(11));
+        assert_eq!(discrete_log(3, 1, 7).unwrap(), Some(0));
+        assert_eq!(discrete_log(5, 0, 1).unwrap(), Some(0));
+        // 2 only reaches 1, 2 and 4 modulo 7
+        assert_eq!(discrete_log(2, 3, 7).unwrap(), None);
+        assert!(matches!(discrete_log(2, 4, 6), Err(Error::Unsupported(_))));
+        assert!(matches!(discrete_log(2, 4, 0), Err(Error::Unsupported(_))));
+        // 2^61 - 1 is prime, but too big for the baby steps to be stored
+        assert!(matches!(
+            discrete_log(3, 5, (1 << 61) - 1),
+            Err(Error::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn giant_steps() {
+        assert_eq!(giant_step_size(1), 1);
+        assert_eq!(giant_step_size(16), 4);
+        assert_eq!(giant_step_size(17), 5);
+        assert_eq!(giant_step_size(20201227), 4495);
+        // without squaring anything past 64 bits
+        assert_eq!(giant_step_size(u64::MAX), 1 << 32);
+    }
+
+    #[test]
+    fn discrete_logs_are_the_smallest() {
+        for target in 1..101 {
+            let brute_force = (0..100).find(|&e| pow(3, e, 101) == target);
+            assert_eq!(discrete_log(3, target, 101).unwrap(), brute_force);
+        }
+    }
+}
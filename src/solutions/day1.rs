@@ -1,51 +1,120 @@
-use std::fs::File;
-use std::io::prelude::*;
-
-fn read_file() -> Vec<i32> {
-    let mut file = File::open("./input/input1.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    contents.lines().map(|n| n.parse().unwrap()).collect()
+use super::{Answer, Solution};
+use crate::error::{parse_lines, parse_number, Error, Result};
+
+pub struct Day1;
+
+fn parse_file(contents: &str) -> Result<Vec<i32>> {
+    parse_lines(contents, |n| parse_number(n, n))
+}
+
+// the product of entries which sum to 2020, worked out in 64 bits. Three large entries
+// (with a negative one to bring the sum back down) can still be too much for that
+fn product(entries: &[i32]) -> Result<i64> {
+    entries.iter().try_fold(1i64, |product, &entry| {
+        product.checked_mul(i64::from(entry)).ok_or_else(|| {
+            Error::Unsupported(format!(
+                "the product of {:?} is too big to work out",
+                entries
+            ))
+        })
+    })
 }
 
-fn solve_part_1(v: Vec<i32>) -> i32 {
-    let mut ans = 0;
-    for n in v.iter() {
-        let res = 2020 - n;
+fn solve_part_1(v: &[i32]) -> Result<i64> {
+    for &n in v.iter() {
+        // in 64 bits, so that this can't overflow for any entry
+        let res = 2020 - i64::from(n);
         // the below would generate a false positive if 1010 were in the input,
         // but I checked it isn't there - so we can ignore this!
-        if let Some(found) = v.iter().find(|m| *m == &res) {
-            ans = n * found;
-            break;
+        if let Some(&found) = v.iter().find(|&&m| i64::from(m) == res) {
+            return product(&[n, found]);
         }
     }
-    ans
+    Err(Error::NoSolution(String::from(
+        "no two entries sum to 2020",
+    )))
 }
 
-fn solve_part_2(v: Vec<i32>) -> i32 {
-    let mut ans = 0;
-    for n in v.iter() {
-        for m in v.iter() {
-            let res = 2020 - m - n;
+fn solve_part_2(v: &[i32]) -> Result<i64> {
+    for &n in v.iter() {
+        for &m in v.iter() {
+            let res = 2020 - i64::from(m) - i64::from(n);
             // to avoid false positives, we assume there are no duplicates
             if res < 0 || m == n {
                 continue;
             }
-            if let Some(found) = v.iter().find(|k| *k == &res) {
-                ans = n * m * found;
-                break;
+            if let Some(&found) = v.iter().find(|&&k| i64::from(k) == res) {
+                return product(&[n, m, found]);
             }
         }
     }
-    ans
+    Err(Error::NoSolution(String::from(
+        "no three entries sum to 2020",
+    )))
 }
 
-pub fn part_1() -> i32 {
-    let nums = read_file();
-    solve_part_1(nums)
+impl Solution for Day1 {
+    type Input = Vec<i32>;
+
+    fn parse(&self, input: &str) -> Result<Vec<i32>> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, nums: &Vec<i32>) -> Result<Answer> {
+        solve_part_1(nums).map(Answer::from)
+    }
+
+    fn part_2(&self, nums: &Vec<i32>) -> Result<Answer> {
+        solve_part_2(nums).map(Answer::from)
+    }
 }
 
-pub fn part_2() -> i32 {
-    let nums = read_file();
-    solve_part_2(nums)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    const EXAMPLE: &str = "1721\n979\n366\n299\n675\n1456\n";
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day1.run(1, EXAMPLE).unwrap().to_string(), "514579");
+    }
+
+    #[test]
+    fn part_2_example() {
+        assert_eq!(Day1.run(2, EXAMPLE).unwrap().to_string(), "241861950");
+    }
+
+    #[test]
+    fn rejects_non_numbers() {
+        assert!(matches!(
+            Day1.parse("1721\n97x\n"),
+            Err(Error::Parse { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn large_entries() {
+        // too big a product for an i32, but not an i64
+        assert_eq!(
+            Day1.run(1, "2000000000\n-1999997980\n")
+                .unwrap()
+                .to_string(),
+            "-3999995960000000000"
+        );
+        // too big even for an i64
+        assert!(matches!(
+            Day1.run(2, "1073741824\n1073741823\n-2147481627\n"),
+            Err(Error::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn no_pair_is_an_error() {
+        assert!(matches!(
+            Day1.run(1, "1\n2\n3\n"),
+            Err(Error::NoSolution(_))
+        ));
+    }
 }
@@ -1,11 +1,10 @@
-use std::fs::File;
-use std::io::prelude::*;
+use super::{Answer, Solution};
+use crate::error::{parse_lines, parse_number, Error, Result};
 
-fn read_file() -> Vec<usize> {
-    let mut file = File::open("./input/input10.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    contents.lines().map(|n| n.parse().unwrap()).collect()
+pub struct Day10;
+
+fn parse_file(contents: &str) -> Result<Vec<usize>> {
+    parse_lines(contents, |n| parse_number(n, n))
 }
 
 fn solve_part_1(nums: &mut Vec<usize>) -> usize {
@@ -26,20 +25,29 @@ fn solve_part_1(nums: &mut Vec<usize>) -> usize {
     diff_one_count * diff_three_count
 }
 
-pub fn part_1() -> usize {
-    let mut nums = read_file();
-    solve_part_1(&mut nums)
-}
-
 // takes a vector of numbers, assumed to be already sorted, and
 // splits into slices, where the end of each slice is exactly 3 from
 // the start of the next one
-fn partition(v: Vec<usize>) -> Vec<Vec<usize>> {
+// Adapters which are 0 or 2 apart, or 4 or more, break the assumptions below and are
+// reported as an error
+fn partition(v: Vec<usize>) -> Result<Vec<Vec<usize>>> {
     let mut res = vec![];
     let mut current = vec![0];
     let mut last_num = 0;
     for num in v {
         let diff = num - last_num;
+        if diff > 3 {
+            return Err(Error::NoSolution(format!(
+                "nothing fits the gap between adapters {} and {}",
+                last_num, num
+            )));
+        }
+        if diff != 1 && diff != 3 {
+            return Err(Error::Unsupported(format!(
+                "adapters {} and {} differ by {}, not 1 or 3",
+                last_num, num, diff
+            )));
+        }
         if diff == 3 {
             res.push(current.clone());
             current = vec![];
@@ -47,15 +55,15 @@ fn partition(v: Vec<usize>) -> Vec<Vec<usize>> {
         current.push(num);
         last_num = num;
     }
-    if current.len() > 0 {
+    if !current.is_empty() {
         res.push(current.clone());
     }
-    res
+    Ok(res)
 }
 
-fn solve_part_2(nums: &mut Vec<usize>) -> usize {
+fn solve_part_2(nums: &mut [usize]) -> Result<usize> {
     nums.sort_unstable();
-    let partitions = partition(nums.to_vec());
+    let partitions = partition(nums.to_vec())?;
     // we "cheat" here by not solving the puzzle in full generality, but
     // relying on two simplifying assumptions, which happen to be true of
     // the data given.
@@ -63,17 +71,59 @@ fn solve_part_2(nums: &mut Vec<usize>) -> usize {
     // of consecutive integers - with no gaps of 2 as are theoretically possible.
     // The second is that each such subsequence has length at most 5.
     let num_sequences = |length| match length {
-        1 => 1,
-        2 => 1,
-        3 => 2,
-        4 => 4,
-        5 => 7,
-        _ => panic!("I've seen this can't happen ;)"),
+        1 => Ok(1),
+        2 => Ok(1),
+        3 => Ok(2),
+        4 => Ok(4),
+        5 => Ok(7),
+        _ => Err(Error::Unsupported(format!(
+            "a run of {} consecutive adapters is too long",
+            length
+        ))),
     };
     partitions.iter().map(|v| num_sequences(v.len())).product()
 }
 
-pub fn part_2() -> usize {
-    let mut nums = read_file();
-    solve_part_2(&mut nums)
+impl Solution for Day10 {
+    type Input = Vec<usize>;
+
+    fn parse(&self, input: &str) -> Result<Vec<usize>> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, nums: &Vec<usize>) -> Result<Answer> {
+        Ok(solve_part_1(&mut nums.to_vec()).into())
+    }
+
+    fn part_2(&self, nums: &Vec<usize>) -> Result<Answer> {
+        solve_part_2(&mut nums.to_vec()).map(Answer::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    const SMALL_EXAMPLE: &str = "16\n10\n15\n5\n1\n11\n7\n19\n6\n12\n4\n";
+
+    const LARGER_EXAMPLE: &str = "\
+28\n33\n18\n42\n31\n14\n46\n20\n48\n47\n24\n23\n49\n45\n19\n38\n39\n11\n1\n32\n25\n35\n8\n17\n7\n9\n4\n2\n34\n10\n3\n";
+
+    #[test]
+    fn part_1_examples() {
+        assert_eq!(Day10.run(1, SMALL_EXAMPLE).unwrap().to_string(), "35");
+        assert_eq!(Day10.run(1, LARGER_EXAMPLE).unwrap().to_string(), "220");
+    }
+
+    #[test]
+    fn part_2_examples() {
+        assert_eq!(Day10.run(2, SMALL_EXAMPLE).unwrap().to_string(), "8");
+        assert_eq!(Day10.run(2, LARGER_EXAMPLE).unwrap().to_string(), "19208");
+    }
+
+    #[test]
+    fn gaps_too_big_to_bridge() {
+        assert!(matches!(Day10.run(2, "1\n5\n"), Err(Error::NoSolution(_))));
+    }
 }
@@ -1,7 +1,10 @@
-use std::fs::File;
-use std::io::prelude::*;
+use super::{Answer, Solution};
+use crate::error::{parse_lines, parse_number, Error, Result};
 
-enum Move {
+pub struct Day12;
+
+#[derive(Clone, Copy)]
+pub enum Move {
     North(usize),
     South(usize),
     East(usize),
@@ -77,7 +80,7 @@ impl ShipState {
         }
     }
 
-    fn movement(&mut self, m: Move) -> () {
+    fn movement(&mut self, m: Move) {
         match m {
             Move::North(amount) => self.y_pos += amount as isize,
             Move::South(amount) => self.y_pos -= amount as isize,
@@ -89,8 +92,8 @@ impl ShipState {
         }
     }
 
-    fn bulk_move(&mut self, v: Vec<Move>) -> () {
-        for m in v {
+    fn bulk_move(&mut self, v: &[Move]) {
+        for &m in v {
             self.movement(m);
         }
     }
@@ -99,7 +102,7 @@ impl ShipState {
         self.x_pos.abs() + self.y_pos.abs()
     }
 
-    fn waypoint_rotate_left(&mut self, turns: usize) -> () {
+    fn waypoint_rotate_left(&mut self, turns: usize) {
         for _ in 0..turns {
             let old_xpos = self.waypoint_offset_x;
             self.waypoint_offset_x = -self.waypoint_offset_y;
@@ -115,7 +118,7 @@ impl ShipState {
         }
     }
 
-    fn movement_with_waypoint(&mut self, m: Move) -> () {
+    fn movement_with_waypoint(&mut self, m: Move) {
         match m {
             Move::North(amount) => self.waypoint_offset_y += amount as isize,
             Move::South(amount) => self.waypoint_offset_y -= amount as isize,
@@ -130,58 +133,108 @@ impl ShipState {
         }
     }
 
-    fn bulk_move_with_waypoint(&mut self, v: Vec<Move>) -> () {
-        for m in v {
+    fn bulk_move_with_waypoint(&mut self, v: &[Move]) {
+        for &m in v {
             self.movement_with_waypoint(m);
         }
     }
 }
 
-fn read_file() -> Vec<Move> {
-    let mut file = File::open("./input/input12.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    contents.lines().map(parse_line).collect()
+fn parse_file(contents: &str) -> Result<Vec<Move>> {
+    parse_lines(contents, parse_line)
 }
 
-fn parse_line(l: &str) -> Move {
-    let dir = l.as_bytes()[0] as char;
-    let amount: usize = l.as_bytes()[1..]
-        .iter()
-        .map(|&n| n as char)
-        .collect::<String>()
-        .parse()
-        .unwrap();
+fn parse_line(l: &str) -> Result<Move> {
+    if l.len() < 2 || !l.is_char_boundary(1) {
+        return Err(Error::parse(l, l, "expected a move like 'F10'"));
+    }
+    let (dir, amount_str) = l.split_at(1);
+    let amount: usize = parse_number(l, amount_str)?;
+    if (dir == "L" || dir == "R") && amount % 90 != 0 {
+        return Err(Error::parse(
+            l,
+            amount_str,
+            "turns must be a multiple of 90 degrees",
+        ));
+    }
     match dir {
-        'N' => Move::North(amount),
-        'S' => Move::South(amount),
-        'E' => Move::East(amount),
-        'W' => Move::West(amount),
-        'L' => Move::Left(amount),
-        'R' => Move::Right(amount),
-        'F' => Move::Forward(amount),
-        c => panic!("unexpected move character: {}", c),
+        "N" => Ok(Move::North(amount)),
+        "S" => Ok(Move::South(amount)),
+        "E" => Ok(Move::East(amount)),
+        "W" => Ok(Move::West(amount)),
+        "L" => Ok(Move::Left(amount)),
+        "R" => Ok(Move::Right(amount)),
+        "F" => Ok(Move::Forward(amount)),
+        _ => Err(Error::parse(l, dir, "unexpected move character")),
     }
 }
 
-fn solve_part_1(moves: Vec<Move>) -> isize {
+fn solve_part_1(moves: &[Move]) -> isize {
     let mut ship = ShipState::new();
     ship.bulk_move(moves);
     ship.manhattan()
 }
 
-pub fn part_1() -> isize {
-    let moves = read_file();
-    solve_part_1(moves)
-}
-
-fn solve_part_2(moves: Vec<Move>) -> isize {
+fn solve_part_2(moves: &[Move]) -> isize {
     let mut ship = ShipState::new();
     ship.bulk_move_with_waypoint(moves);
     ship.manhattan()
 }
 
-pub fn part_2() -> isize {
-    let moves = read_file();
-    solve_part_2(moves)
+impl Solution for Day12 {
+    type Input = Vec<Move>;
+
+    fn parse(&self, input: &str) -> Result<Vec<Move>> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, moves: &Vec<Move>) -> Result<Answer> {
+        Ok(solve_part_1(moves).into())
+    }
+
+    fn part_2(&self, moves: &Vec<Move>) -> Result<Answer> {
+        Ok(solve_part_2(moves).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    const EXAMPLE: &str = "F10\nN3\nF7\nR90\nF11\n";
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day12.run(1, EXAMPLE).unwrap().to_string(), "25");
+    }
+
+    #[test]
+    fn part_2_example() {
+        assert_eq!(Day12.run(2, EXAMPLE).unwrap().to_string(), "286");
+    }
+
+    #[test]
+    fn rejects_unknown_moves() {
+        assert!(matches!(
+            Day12.parse("F10\nX7\n"),
+            Err(Error::Parse {
+                line: 2,
+                column: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_turns_that_are_not_right_angles() {
+        assert!(matches!(
+            Day12.parse("R45\n"),
+            Err(Error::Parse {
+                line: 1,
+                column: 2,
+                ..
+            })
+        ));
+    }
 }
@@ -1,31 +1,45 @@
+use super::{Answer, Solution};
+use crate::error::{parse_number, Error, Result};
 use ring_algorithm::chinese_remainder_theorem;
-use std::fs::File;
-use std::io::prelude::*;
 
-struct PuzzleInfo {
+pub struct Day13;
+
+pub struct PuzzleInfo {
     current_time: usize,
     buses: Vec<Option<usize>>,
 }
 
-fn read_file() -> PuzzleInfo {
-    let mut file = File::open("./input/input13.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
+fn parse_file(contents: &str) -> Result<PuzzleInfo> {
     let mut lines = contents.lines();
-    let current_time = lines.nth(0).unwrap().parse().unwrap();
-    let buses = lines
-        .nth(0)
-        .unwrap()
-        .split(",")
-        .map(|s| s.parse().ok())
-        .collect();
-    PuzzleInfo {
+    let (time_line, bus_line) = match (lines.next(), lines.next()) {
+        (Some(time_line), Some(bus_line)) => (time_line, bus_line),
+        _ => {
+            return Err(Error::parse(
+                contents,
+                contents,
+                "expected a time and a list of buses",
+            ))
+        }
+    };
+    let current_time = parse_number(time_line, time_line)?;
+    let buses = bus_line
+        .split(',')
+        .map(|s| match s {
+            "x" => Ok(None),
+            _ => match parse_number(bus_line, s)? {
+                0 => Err(Error::parse(bus_line, s, "bus IDs can't be 0")),
+                id => Ok(Some(id)),
+            },
+        })
+        .collect::<Result<Vec<_>>>()
+        .map_err(|e| e.offset_lines(1))?;
+    Ok(PuzzleInfo {
         current_time,
         buses,
-    }
+    })
 }
 
-fn find_bus(buses: &Vec<Option<usize>>, time_to_test: usize) -> Option<usize> {
+fn find_bus(buses: &[Option<usize>], time_to_test: usize) -> Option<usize> {
     let found_option = buses.iter().find(|b| match b {
         None => false,
         Some(id) => time_to_test % id == 0,
@@ -36,39 +50,88 @@ fn find_bus(buses: &Vec<Option<usize>>, time_to_test: usize) -> Option<usize> {
     }
 }
 
-fn solve_part_1(info: PuzzleInfo) -> usize {
+fn solve_part_1(info: &PuzzleInfo) -> Result<usize> {
     let PuzzleInfo {
         current_time,
         buses,
     } = info;
+    if buses.iter().all(Option::is_none) {
+        return Err(Error::NoSolution(String::from(
+            "there are no buses in service",
+        )));
+    }
     let mut counter = 0;
     loop {
-        if let Some(id) = find_bus(&buses, current_time + counter) {
-            return id * counter;
+        if let Some(id) = find_bus(buses, current_time + counter) {
+            return Ok(id * counter);
         }
         counter += 1;
     }
 }
 
-pub fn part_1() -> usize {
-    let info = read_file();
-    solve_part_1(info)
-}
-
-fn solve_part_2(buses: Vec<Option<usize>>) -> isize {
+fn solve_part_2(buses: &[Option<usize>]) -> Result<isize> {
     let (remainders, moduli): (Vec<isize>, Vec<isize>) = buses
         .iter()
         .enumerate()
-        .filter(|(_, b)| match b {
-            None => false,
-            _ => true,
-        })
+        .filter(|(_, b)| b.is_some())
         .map(|(i, m)| (-(i as isize), m.unwrap() as isize))
         .unzip();
-    chinese_remainder_theorem(&remainders[0..], &moduli[0..]).unwrap()
+    let time = chinese_remainder_theorem(&remainders[0..], &moduli[0..]).ok_or_else(|| {
+        Error::NoSolution(String::from(
+            "the bus IDs aren't pairwise coprime, so no time lines them all up",
+        ))
+    })?;
+    // the remainder found can be negative, but we want the earliest time from 0 onwards
+    Ok(time.rem_euclid(moduli.iter().product()))
 }
 
-pub fn part_2() -> isize {
-    let info = read_file();
-    solve_part_2(info.buses)
+impl Solution for Day13 {
+    type Input = PuzzleInfo;
+
+    fn parse(&self, input: &str) -> Result<PuzzleInfo> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, info: &PuzzleInfo) -> Result<Answer> {
+        solve_part_1(info).map(Answer::from)
+    }
+
+    fn part_2(&self, info: &PuzzleInfo) -> Result<Answer> {
+        solve_part_2(&info.buses).map(Answer::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    const EXAMPLE: &str = "939\n7,13,x,x,59,x,31,19\n";
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day13.run(1, EXAMPLE).unwrap().to_string(), "295");
+    }
+
+    #[test]
+    fn part_2_examples() {
+        assert_eq!(Day13.run(2, EXAMPLE).unwrap().to_string(), "1068781");
+        assert_eq!(Day13.run(2, "0\n17,x,13,19\n").unwrap().to_string(), "3417");
+        assert_eq!(
+            Day13.run(2, "0\n1789,37,47,1889\n").unwrap().to_string(),
+            "1202161486"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_buses() {
+        assert!(matches!(
+            Day13.parse("939\n7,13,y\n"),
+            Err(Error::Parse {
+                line: 2,
+                column: 6,
+                ..
+            })
+        ));
+    }
 }
@@ -1,29 +1,60 @@
+use super::{Answer, Solution};
+use crate::error::{parse_lines, parse_number, Error, Result};
 use std::collections::HashMap;
 use std::convert::TryInto;
-use std::fs::File;
-use std::io::prelude::*;
+
+pub struct Day14;
 
 #[derive(Debug, Copy, Clone)]
-enum BitSetting {
+pub enum BitSetting {
     Zero,
     One,
     Leave,
 }
 
 impl BitSetting {
-    fn from_string(s: &str) -> [BitSetting; 36] {
-        let as_vec: Vec<BitSetting> = s
-            .chars()
+    fn from_string(line: &str, s: &str) -> Result<[BitSetting; 36]> {
+        let as_vec = s
+            .char_indices()
             .rev()
-            .map(|c| match c {
-                '0' => BitSetting::Zero,
-                '1' => BitSetting::One,
-                'X' => BitSetting::Leave,
-                c => panic!("unexpected bit character: {}", c),
+            .map(|(i, c)| match c {
+                '0' => Ok(BitSetting::Zero),
+                '1' => Ok(BitSetting::One),
+                'X' => Ok(BitSetting::Leave),
+                _ => Err(Error::parse(
+                    line,
+                    &s[i..i + c.len_utf8()],
+                    "expected 0, 1 or X",
+                )),
             })
-            .collect();
-        as_vec.try_into().unwrap()
+            .collect::<Result<Vec<BitSetting>>>()?;
+        as_vec
+            .try_into()
+            .map_err(|_| Error::parse(line, s, "masks must be 36 bits long"))
+    }
+}
+
+// more floating bits than this would mean writing to over a million addresses at once
+const MAX_FLOATING_BITS: usize = 20;
+
+#[derive(Debug, Copy, Clone)]
+pub enum Instruction {
+    Mask([BitSetting; 36]),
+    Write { address: usize, value: usize },
+}
+
+fn parse_line(l: &str) -> Result<Instruction> {
+    if let Some(mask) = l.strip_prefix("mask = ") {
+        return Ok(Instruction::Mask(BitSetting::from_string(l, mask)?));
     }
+    let (address, value) = l
+        .strip_prefix("mem[")
+        .and_then(|rest| rest.split_once("] = "))
+        .ok_or_else(|| Error::parse(l, l, "expected 'mask = ...' or 'mem[...] = ...'"))?;
+    Ok(Instruction::Write {
+        address: parse_number(l, address)?,
+        value: parse_number(l, value)?,
+    })
 }
 
 fn apply_mask(num: usize, mask: [BitSetting; 36]) -> usize {
@@ -32,8 +63,8 @@ fn apply_mask(num: usize, mask: [BitSetting; 36]) -> usize {
     for setting in mask.iter() {
         str_index -= 1;
         match setting {
-            BitSetting::Zero => as_binary[str_index] = '0' as u8,
-            BitSetting::One => as_binary[str_index] = '1' as u8,
+            BitSetting::Zero => as_binary[str_index] = b'0',
+            BitSetting::One => as_binary[str_index] = b'1',
             BitSetting::Leave => (),
         }
     }
@@ -53,7 +84,7 @@ fn apply_mask_new(num: usize, mask: [BitSetting; 36]) -> Vec<usize> {
         match setting {
             BitSetting::Zero => (),
             BitSetting::One => {
-                as_binary[str_index] = '1' as u8;
+                as_binary[str_index] = b'1';
             }
             BitSetting::Leave => {
                 floating_indices.push(str_index);
@@ -67,8 +98,8 @@ fn apply_mask_new(num: usize, mask: [BitSetting; 36]) -> Vec<usize> {
             .flat_map(|bin_str| {
                 let mut with_zero = bin_str.clone();
                 let mut with_one = bin_str.clone();
-                with_zero[i] = '0' as u8;
-                with_one[i] = '1' as u8;
+                with_zero[i] = b'0';
+                with_one[i] = b'1';
                 vec![with_zero, with_one]
             })
             .collect();
@@ -99,70 +130,131 @@ impl Memory {
         }
     }
 
-    fn process_string(&mut self, s: String) -> () {
-        if &s[0..7] == "mask = " {
-            self.bitmask = BitSetting::from_string(&s[7..]);
-        } else {
-            // can assume line has the form mem[XXX] = YYY
-            let parts: Vec<&str> = s.split("] = ").collect();
-            let key = parts[0][4..].parse().unwrap();
-            let value = apply_mask(parts[1].parse().unwrap(), self.bitmask);
-            self.content.insert(key, value);
+    fn process(&mut self, instruction: &Instruction) {
+        match *instruction {
+            Instruction::Mask(mask) => self.bitmask = mask,
+            Instruction::Write { address, value } => {
+                self.content
+                    .insert(address, apply_mask(value, self.bitmask));
+            }
         }
     }
 
-    fn process_all(&mut self, v: Vec<String>) -> () {
-        for s in v {
-            self.process_string(s);
+    fn process_all(&mut self, v: &[Instruction]) {
+        for instruction in v {
+            self.process(instruction);
         }
     }
 
-    fn process_string_new(&mut self, s: String) -> () {
-        if &s[0..7] == "mask = " {
-            self.bitmask = BitSetting::from_string(&s[7..]);
-        } else {
-            // can assume line has the form mem[XXX] = YYY
-            let parts: Vec<&str> = s.split("] = ").collect();
-            let keys = apply_mask_new(parts[0][4..].parse().unwrap(), self.bitmask);
-            let value = parts[1].parse().unwrap();
-            for key in keys {
-                self.content.insert(key, value);
+    fn process_new(&mut self, instruction: &Instruction) -> Result<()> {
+        match *instruction {
+            Instruction::Mask(mask) => {
+                let floating = mask
+                    .iter()
+                    .filter(|setting| matches!(setting, BitSetting::Leave))
+                    .count();
+                if floating > MAX_FLOATING_BITS {
+                    return Err(Error::Unsupported(format!(
+                        "a mask has {} floating bits, but at most {} are handled",
+                        floating, MAX_FLOATING_BITS
+                    )));
+                }
+                self.bitmask = mask;
+            }
+            Instruction::Write { address, value } => {
+                for key in apply_mask_new(address, self.bitmask) {
+                    self.content.insert(key, value);
+                }
             }
         }
+        Ok(())
     }
 
-    fn process_all_new(&mut self, v: Vec<String>) -> () {
-        for s in v {
-            self.process_string_new(s);
-        }
+    fn process_all_new(&mut self, v: &[Instruction]) -> Result<()> {
+        v.iter()
+            .try_for_each(|instruction| self.process_new(instruction))
     }
 }
 
-fn read_file() -> Vec<String> {
-    let mut file = File::open("./input/input14.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    contents.lines().map(|l| l.to_owned()).collect()
+fn parse_file(contents: &str) -> Result<Vec<Instruction>> {
+    parse_lines(contents, parse_line)
 }
 
-fn solve_part_1(info: Vec<String>) -> usize {
+fn solve_part_1(info: &[Instruction]) -> usize {
     let mut memory = Memory::new();
     memory.process_all(info);
     memory.content.values().sum()
 }
 
-pub fn part_1() -> usize {
-    let info = read_file();
-    solve_part_1(info)
+fn solve_part_2(info: &[Instruction]) -> Result<usize> {
+    let mut memory = Memory::new();
+    memory.process_all_new(info)?;
+    Ok(memory.content.values().sum())
 }
 
-fn solve_part_2(info: Vec<String>) -> usize {
-    let mut memory = Memory::new();
-    memory.process_all_new(info);
-    memory.content.values().sum()
+impl Solution for Day14 {
+    type Input = Vec<Instruction>;
+
+    fn parse(&self, input: &str) -> Result<Vec<Instruction>> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, info: &Vec<Instruction>) -> Result<Answer> {
+        Ok(solve_part_1(info).into())
+    }
+
+    fn part_2(&self, info: &Vec<Instruction>) -> Result<Answer> {
+        solve_part_2(info).map(Answer::from)
+    }
 }
 
-pub fn part_2() -> usize {
-    let info = read_file();
-    solve_part_2(info)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    const EXAMPLE_1: &str = "\
+mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X
+mem[8] = 11
+mem[7] = 101
+mem[8] = 0
+";
+
+    const EXAMPLE_2: &str = "\
+mask = 000000000000000000000000000000X1001X
+mem[42] = 100
+mask = 00000000000000000000000000000000X0XX
+mem[26] = 1
+";
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day14.run(1, EXAMPLE_1).unwrap().to_string(), "165");
+    }
+
+    #[test]
+    fn part_2_example() {
+        assert_eq!(Day14.run(2, EXAMPLE_2).unwrap().to_string(), "208");
+    }
+
+    #[test]
+    fn too_many_floating_bits() {
+        // the first example, run as part 2, would write to 2^34 addresses
+        assert!(matches!(
+            Day14.run(2, EXAMPLE_1),
+            Err(Error::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_short_masks() {
+        assert!(matches!(
+            Day14.parse("mask = X01\n"),
+            Err(Error::Parse {
+                line: 1,
+                column: 8,
+                ..
+            })
+        ));
+    }
 }
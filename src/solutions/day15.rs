@@ -1,5 +1,9 @@
+use super::{Answer, Solution};
+use crate::error::{parse_number, Error, Result};
 use std::collections::HashMap;
 
+pub struct Day15;
+
 /*
 The key observation, for efficiently solving the second part of the puzzle (part 1 is
 easily done with any sensible algorithm), is that a vector/array of all numbers - which
@@ -24,11 +28,7 @@ impl IndexPair {
     }
 
     fn has_both(&self) -> bool {
-        if let IndexPair(Some(_), Some(_)) = self {
-            true
-        } else {
-            false
-        }
+        matches!(self, IndexPair(Some(_), Some(_)))
     }
 
     fn new_index(&self, index: usize) -> IndexPair {
@@ -58,7 +58,7 @@ impl Numbers {
         }
     }
 
-    fn insert(&mut self, num: usize) -> () {
+    fn insert(&mut self, num: usize) {
         self.last = num;
         let old_pair = self.used.get(&num);
         let new = &IndexPair::new();
@@ -79,7 +79,7 @@ impl Numbers {
         new
     }
 
-    fn next(&mut self) -> () {
+    fn next(&mut self) {
         let new = &IndexPair::new();
         let last_index_pair = self.used.get(&self.last).unwrap_or(new);
         let new_val = if last_index_pair.has_both() {
@@ -105,16 +105,70 @@ fn solve_part_1(nums: &mut Numbers) -> usize {
     nums.get_nth(2020)
 }
 
-pub fn part_1() -> usize {
-    let mut nums = Numbers::from_vec(vec![12, 1, 16, 3, 11, 0]);
-    solve_part_1(&mut nums)
-}
-
 fn solve_part_2(nums: &mut Numbers) -> usize {
     nums.get_nth(30000000)
 }
 
-pub fn part_2() -> usize {
-    let mut nums = Numbers::from_vec(vec![12, 1, 16, 3, 11, 0]);
-    solve_part_2(&mut nums)
+fn parse_file(contents: &str) -> Result<Vec<usize>> {
+    let line = contents.trim();
+    if line.is_empty() {
+        return Err(Error::parse(
+            contents,
+            contents,
+            "expected a list of starting numbers",
+        ));
+    }
+    line.split(',').map(|n| parse_number(line, n)).collect()
+}
+
+impl Solution for Day15 {
+    type Input = Vec<usize>;
+
+    fn parse(&self, input: &str) -> Result<Vec<usize>> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, start: &Vec<usize>) -> Result<Answer> {
+        let mut nums = Numbers::from_vec(start.to_vec());
+        Ok(solve_part_1(&mut nums).into())
+    }
+
+    fn part_2(&self, start: &Vec<usize>) -> Result<Answer> {
+        let mut nums = Numbers::from_vec(start.to_vec());
+        Ok(solve_part_2(&mut nums).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    #[test]
+    fn part_1_examples() {
+        let examples = [
+            ("0,3,6", "436"),
+            ("1,3,2", "1"),
+            ("2,1,3", "10"),
+            ("1,2,3", "27"),
+            ("2,3,1", "78"),
+            ("3,2,1", "438"),
+            ("3,1,2", "1836"),
+        ];
+        for (start, answer) in examples.iter() {
+            assert_eq!(&Day15.run(1, start).unwrap().to_string(), answer);
+        }
+    }
+
+    // thirty million turns is slow in a debug build
+    #[test]
+    #[ignore]
+    fn part_2_example() {
+        assert_eq!(Day15.run(2, "0,3,6").unwrap().to_string(), "175594");
+    }
+
+    #[test]
+    fn rejects_empty_input() {
+        assert!(matches!(Day15.parse("\n"), Err(Error::Parse { .. })));
+    }
 }
@@ -1,340 +1,375 @@
-use itertools::Itertools;
-use std::collections::HashMap;
-use std::convert::TryInto;
-use std::fs::File;
-use std::io::prelude::*;
+use super::{Answer, Solution};
+use crate::deduction::{self, Deduction, Explanation, Failure};
+use crate::error::{parse_groups, parse_lines, parse_number, Error, Result};
+use std::convert::TryFrom;
+use std::ops::RangeInclusive;
 
-#[derive(Debug)]
-struct Rules {
-    numbers: [usize; 4],
-}
-
-impl Rules {
-    fn in_range(&self, num: usize) -> bool {
-        let Rules {
-            numbers: [a, b, c, d],
-        } = self;
-        (num >= *a && num <= *b) || (num >= *c && num <= *d)
-    }
-}
+pub struct Day16;
 
-// it turns out that using a HashMap intead of a Struct would have avoided
-// a bunch of repetitive boilerplate code later - but I couldn't be bothered changing.
-// A Struct feels "correct" because all of these fields are included in a set of rules
-struct AllRules {
-    departure_location: Rules,
-    departure_station: Rules,
-    departure_platform: Rules,
-    departure_track: Rules,
-    departure_date: Rules,
-    departure_time: Rules,
-    arrival_location: Rules,
-    arrival_station: Rules,
-    arrival_platform: Rules,
-    arrival_track: Rules,
-    class: Rules,
-    duration: Rules,
-    price: Rules,
-    route: Rules,
-    row: Rules,
-    seat: Rules,
-    train: Rules,
-    type_: Rules,
-    wagon: Rules,
-    zone: Rules,
+/// A field a ticket can have, and the ranges its value must fall in one of.
+#[derive(Debug)]
+struct Field {
+    name: String,
+    ranges: Vec<RangeInclusive<usize>>,
 }
 
-impl AllRules {
-    fn invalid_for_any(&self, num: usize) -> bool {
-        !self.departure_location.in_range(num)
-            && !self.departure_station.in_range(num)
-            && !self.departure_platform.in_range(num)
-            && !self.departure_track.in_range(num)
-            && !self.departure_date.in_range(num)
-            && !self.departure_time.in_range(num)
-            && !self.arrival_location.in_range(num)
-            && !self.arrival_station.in_range(num)
-            && !self.arrival_platform.in_range(num)
-            && !self.arrival_track.in_range(num)
-            && !self.class.in_range(num)
-            && !self.duration.in_range(num)
-            && !self.price.in_range(num)
-            && !self.route.in_range(num)
-            && !self.row.in_range(num)
-            && !self.seat.in_range(num)
-            && !self.train.in_range(num)
-            && !self.type_.in_range(num)
-            && !self.wagon.in_range(num)
-            && !self.zone.in_range(num)
+impl Field {
+    fn allows(&self, num: usize) -> bool {
+        self.ranges.iter().any(|range| range.contains(&num))
     }
 }
 
 struct Ticket {
-    values: [usize; 20],
+    values: Vec<usize>,
 }
 
 impl Ticket {
-    fn invalid_for_any(&self, rules: &AllRules) -> Vec<usize> {
+    fn invalid_for_any(&self, fields: &[Field]) -> Vec<usize> {
         self.values
             .iter()
-            .filter(|&n| rules.invalid_for_any(*n))
-            .map(|&n| n)
+            .filter(|&&n| !fields.iter().any(|field| field.allows(n)))
+            .copied()
             .collect()
     }
 }
 
-struct Info {
-    rules: AllRules,
+pub struct Info {
+    fields: Vec<Field>,
     my_ticket: Ticket,
     nearby_tickets: Vec<Ticket>,
 }
 
-fn read_file() -> Info {
-    let mut file = File::open("./input/input16.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    parse_file(&contents)
+// the three blank-line separated parts of the input, told apart by their first line
+enum Section {
+    Fields(Vec<Field>),
+    MyTicket(Ticket),
+    NearbyTickets(Vec<Ticket>),
 }
 
-fn parse_file(s: &str) -> Info {
-    let v: Vec<String> = s
-        .lines()
-        .group_by(|s| s.is_empty())
-        .into_iter()
-        .filter(|(k, _g)| !k)
-        .map(|(_k, g)| g.collect::<Vec<&str>>().join("\n"))
-        .collect();
-
-    let rules = parse_rules(&v[0]);
-    let my_ticket = parse_ticket(&v[1].lines().collect::<Vec<&str>>()[1]);
-    let nearby_tickets = v[2].lines().collect::<Vec<&str>>()[1..]
-        .iter()
-        .map(|&t| parse_ticket(t))
-        .collect();
+fn parse_section(s: &str) -> Result<Section> {
+    match s.split_once('\n') {
+        Some(("your ticket:", rest)) => parse_ticket(rest)
+            .map(Section::MyTicket)
+            .map_err(|e| e.offset_lines(1)),
+        Some(("nearby tickets:", rest)) => parse_lines(rest, parse_ticket)
+            .map(Section::NearbyTickets)
+            .map_err(|e| e.offset_lines(1)),
+        _ => parse_fields(s).map(Section::Fields),
+    }
+}
 
-    Info {
-        rules,
-        my_ticket,
-        nearby_tickets,
+fn parse_file(s: &str) -> Result<Info> {
+    let sections = parse_groups(s, parse_section)?;
+    let info = match <[Section; 3]>::try_from(sections) {
+        Ok(
+            [Section::Fields(fields), Section::MyTicket(my_ticket), Section::NearbyTickets(nearby_tickets)],
+        ) => Info {
+            fields,
+            my_ticket,
+            nearby_tickets,
+        },
+        _ => {
+            return Err(Error::parse(
+                s,
+                s,
+                "expected the fields, then your ticket, then nearby tickets",
+            ))
+        }
+    };
+    let tickets = std::iter::once(&info.my_ticket).chain(&info.nearby_tickets);
+    if let Some(ticket) = tickets
+        .into_iter()
+        .find(|t| t.values.len() != info.fields.len())
+    {
+        return Err(Error::Unsupported(format!(
+            "there are {} fields, but a ticket has {} values",
+            info.fields.len(),
+            ticket.values.len()
+        )));
     }
+    Ok(info)
 }
 
-fn parse_rules(s: &str) -> AllRules {
-    let v: Vec<Rules> = s
-        .lines()
-        .map(|l| parse_range(l.split(": ").collect::<Vec<&str>>()[1]))
-        .collect();
-    let [
-        departure_location,
-        departure_station,
-        departure_platform,
-        departure_track,
-        departure_date,
-        departure_time,
-        arrival_location,
-        arrival_station,
-        arrival_platform,
-        arrival_track,
-        class,
-        duration,
-        price,
-        route,
-        row,
-        seat,
-        train,
-        type_,
-        wagon,
-        zone
-        ]: [Rules; 20] =
-        v.try_into().unwrap();
-    AllRules {
-        departure_location,
-        departure_station,
-        departure_platform,
-        departure_track,
-        departure_date,
-        departure_time,
-        arrival_location,
-        arrival_station,
-        arrival_platform,
-        arrival_track,
-        class,
-        duration,
-        price,
-        route,
-        row,
-        seat,
-        train,
-        type_,
-        wagon,
-        zone,
+fn parse_fields(s: &str) -> Result<Vec<Field>> {
+    let fields: Vec<Field> = parse_lines(s, |l| match l.split_once(": ") {
+        Some((name, ranges)) => Ok(Field {
+            name: name.to_owned(),
+            ranges: parse_ranges(l, ranges)?,
+        }),
+        None => Err(Error::parse(
+            l,
+            l,
+            "expected a field like 'name: 1-3 or 5-7'",
+        )),
+    })?;
+    for (i, field) in fields.iter().enumerate() {
+        if fields[..i].iter().any(|f| f.name == field.name) {
+            return Err(Error::Unsupported(format!(
+                "the field '{}' is given twice",
+                field.name
+            )));
+        }
     }
+    Ok(fields)
 }
 
-fn parse_range(s: &str) -> Rules {
-    let nums: Vec<usize> = s
-        .split(" or ")
-        .flat_map(|s| s.split("-").map(|n| n.parse().unwrap()))
-        .collect();
-    let numbers: [usize; 4] = nums.try_into().unwrap();
-    Rules { numbers }
+fn parse_ranges(line: &str, s: &str) -> Result<Vec<RangeInclusive<usize>>> {
+    s.split(" or ")
+        .map(|range| match range.split_once('-') {
+            Some((start, end)) => Ok(parse_number(line, start)?..=parse_number(line, end)?),
+            None => Err(Error::parse(line, range, "expected a range like '1-3'")),
+        })
+        .collect()
 }
 
-fn parse_ticket(s: &str) -> Ticket {
+fn parse_ticket(s: &str) -> Result<Ticket> {
     let values = s
-        .split(",")
-        .map(|n| n.parse().unwrap())
-        .collect::<Vec<usize>>()
-        .try_into()
-        .unwrap();
-    Ticket { values }
+        .split(',')
+        .map(|n| parse_number(s, n))
+        .collect::<Result<Vec<usize>>>()?;
+    Ok(Ticket { values })
 }
 
 fn solve_part_1(info: &Info) -> usize {
     info.nearby_tickets
         .iter()
-        .flat_map(|t| t.invalid_for_any(&info.rules))
-        .collect::<Vec<usize>>()
-        .iter()
+        .flat_map(|t| t.invalid_for_any(&info.fields))
         .sum()
 }
 
-pub fn part_1() -> usize {
-    let info = read_file();
-    solve_part_1(&info)
+// the fields each position on the tickets could be, as indices into `fields`
+fn candidates(fields: &[Field], tickets: &[&Ticket]) -> Vec<Vec<usize>> {
+    (0..fields.len())
+        .map(|position| {
+            (0..fields.len())
+                .filter(|&field| {
+                    tickets
+                        .iter()
+                        .all(|ticket| fields[field].allows(ticket.values[position]))
+                })
+                .collect()
+        })
+        .collect()
 }
 
-fn solve_ticket_parts(rules: &AllRules, tickets: Vec<&Ticket>) -> [String; 20] {
-    let mut possibilities = Vec::new();
-    for i in 0..20 {
-        let mut fields = vec![
-            "departure_location",
-            "departure_station",
-            "departure_platform",
-            "departure_track",
-            "departure_date",
-            "departure_time",
-            "arrival_location",
-            "arrival_station",
-            "arrival_platform",
-            "arrival_track",
-            "class",
-            "duration",
-            "price",
-            "route",
-            "row",
-            "seat",
-            "train",
-            "type_",
-            "wagon",
-            "zone",
-        ];
-        for ticket in &tickets {
-            if !rules.departure_location.in_range(ticket.values[i]) {
-                fields = remove(fields, "departure_location");
-            }
-            if !rules.departure_station.in_range(ticket.values[i]) {
-                fields = remove(fields, "departure_station");
-            }
-            if !rules.departure_platform.in_range(ticket.values[i]) {
-                fields = remove(fields, "departure_platform");
-            }
-            if !rules.departure_track.in_range(ticket.values[i]) {
-                fields = remove(fields, "departure_track");
-            }
-            if !rules.departure_date.in_range(ticket.values[i]) {
-                fields = remove(fields, "departure_date");
-            }
-            if !rules.departure_time.in_range(ticket.values[i]) {
-                fields = remove(fields, "departure_time");
-            }
-            if !rules.arrival_location.in_range(ticket.values[i]) {
-                fields = remove(fields, "arrival_location");
-            }
-            if !rules.arrival_station.in_range(ticket.values[i]) {
-                fields = remove(fields, "arrival_station");
-            }
-            if !rules.arrival_platform.in_range(ticket.values[i]) {
-                fields = remove(fields, "arrival_platform");
-            }
-            if !rules.arrival_track.in_range(ticket.values[i]) {
-                fields = remove(fields, "arrival_track");
-            }
-            if !rules.class.in_range(ticket.values[i]) {
-                fields = remove(fields, "class");
-            }
-            if !rules.duration.in_range(ticket.values[i]) {
-                fields = remove(fields, "duration");
-            }
-            if !rules.price.in_range(ticket.values[i]) {
-                fields = remove(fields, "price");
-            }
-            if !rules.route.in_range(ticket.values[i]) {
-                fields = remove(fields, "route");
-            }
-            if !rules.row.in_range(ticket.values[i]) {
-                fields = remove(fields, "row");
-            }
-            if !rules.seat.in_range(ticket.values[i]) {
-                fields = remove(fields, "seat");
-            }
-            if !rules.train.in_range(ticket.values[i]) {
-                fields = remove(fields, "train");
-            }
-            if !rules.type_.in_range(ticket.values[i]) {
-                fields = remove(fields, "type_");
-            }
-            if !rules.wagon.in_range(ticket.values[i]) {
-                fields = remove(fields, "wagon");
-            }
-            if !rules.zone.in_range(ticket.values[i]) {
-                fields = remove(fields, "zone");
-            }
+// the field at each position on the tickets, as an index into `fields`. Each position must
+// have a different field, which is worked out by deduction
+fn solve_ticket_parts(fields: &[Field], tickets: &[&Ticket]) -> Result<Deduction> {
+    let candidates = candidates(fields, tickets);
+    match deduction::solve(&candidates, fields.len()) {
+        Ok(deduction) => Ok(deduction),
+        Err(failure) => {
+            let description = failure.describe(
+                |position| format!("position {}", position + 1),
+                |field| format!("'{}'", fields[field].name),
+            );
+            Err(Error::NoSolution(match failure {
+                Failure::Ambiguous { .. } => {
+                    format!("the fields can't be told apart: {}", description)
+                }
+                Failure::Shortage { .. } => description,
+            }))
         }
-        possibilities.push(fields);
     }
-    // we'll assume that we can solve this now by "elimination" - find an index with just one
-    // choice, eliminate that from the others, and keep on going this way until we've solved for
-    // all. This indeed works!
-    let mut solutions_hm = HashMap::new();
-    let mut solutions_vec = Vec::new();
-    for _ in 0..20 {
-        let one_choice_index = possibilities.iter().position(|v| v.len() == 1).unwrap();
-        let result = possibilities[one_choice_index][0];
-        solutions_hm.insert(one_choice_index, result);
-        possibilities = possibilities
-            .iter()
-            .map(|v| remove(v.to_vec(), result))
-            .collect();
-    }
-    for i in 0..20 {
-        solutions_vec.push(solutions_hm.get(&i).unwrap().to_string());
-    }
-    solutions_vec.try_into().unwrap()
 }
 
-fn remove<'a>(v: Vec<&'a str>, s: &str) -> Vec<&'a str> {
-    v.into_iter().filter(|&field| field != s).collect()
+fn valid_tickets(info: &Info) -> Vec<&Ticket> {
+    info.nearby_tickets
+        .iter()
+        .filter(|t| t.invalid_for_any(&info.fields).is_empty())
+        .collect()
 }
 
-fn solve_part_2(info: &Info) -> usize {
-    let valid_tickets = info
-        .nearby_tickets
-        .iter()
-        .filter(|t| t.invalid_for_any(&info.rules).len() == 0)
-        .collect();
-    let correct_parts = solve_ticket_parts(&info.rules, valid_tickets);
-    let departure_positions = correct_parts
+fn solve_part_2(info: &Info) -> Result<usize> {
+    let deduction = solve_ticket_parts(&info.fields, &valid_tickets(info))?;
+    Ok(deduction
+        .assignment
         .iter()
         .enumerate()
-        .filter(|(_, s)| s.len() > 9 && &s[0..9] == "departure")
-        .map(|(i, _)| i);
-    departure_positions
-        .map(|i| info.my_ticket.values[i])
-        .product()
+        .filter(|(_, &field)| info.fields[field].name.starts_with("departure"))
+        .map(|(position, _)| info.my_ticket.values[position])
+        .product())
 }
 
-pub fn part_2() -> usize {
-    let info = read_file();
-    solve_part_2(&info)
+impl Solution for Day16 {
+    type Input = Info;
+
+    fn parse(&self, input: &str) -> Result<Info> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, info: &Info) -> Result<Answer> {
+        Ok(solve_part_1(info).into())
+    }
+
+    fn part_2(&self, info: &Info) -> Result<Answer> {
+        solve_part_2(info).map(Answer::from)
+    }
+
+    fn explain(&self, info: &Info) -> Result<Option<Explanation>> {
+        let deduction = solve_ticket_parts(&info.fields, &valid_tickets(info))?;
+        Ok(Some(Explanation {
+            items: (1..=info.fields.len())
+                .map(|position| format!("position {}", position))
+                .collect(),
+            values: info.fields.iter().map(|f| f.name.clone()).collect(),
+            trace: deduction.trace,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    const EXAMPLE: &str = "\
+class: 1-3 or 5-7
+row: 6-11 or 33-44
+seat: 13-40 or 45-50
+
+your ticket:
+7,1,14
+
+nearby tickets:
+7,3,47
+40,4,50
+55,2,20
+38,6,12
+";
+
+    const FIELDS_EXAMPLE: &str = "\
+class: 0-1 or 4-19
+row: 0-5 or 8-19
+seat: 0-13 or 16-19
+
+your ticket:
+11,12,13
+
+nearby tickets:
+3,9,18
+15,1,5
+5,14,9
+";
+
+    fn assignment(input: &str) -> Result<Vec<String>> {
+        let info = Day16.parse(input).unwrap();
+        let tickets: Vec<&Ticket> = info.nearby_tickets.iter().collect();
+        let deduction = solve_ticket_parts(&info.fields, &tickets)?;
+        Ok(deduction
+            .assignment
+            .into_iter()
+            .map(|field| info.fields[field].name.clone())
+            .collect())
+    }
+
+    #[test]
+    fn ranges() {
+        let field = Field {
+            name: String::from("class"),
+            ranges: parse_ranges("class: 1-3 or 5-7", "1-3 or 5-7").unwrap(),
+        };
+        assert!(field.allows(1));
+        assert!(field.allows(3));
+        assert!(!field.allows(4));
+        assert!(field.allows(7));
+        assert!(!field.allows(8));
+    }
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day16.run(1, EXAMPLE).unwrap().to_string(), "71");
+    }
+
+    #[test]
+    fn working_out_the_fields() {
+        assert_eq!(
+            assignment(FIELDS_EXAMPLE).unwrap(),
+            ["row", "class", "seat"]
+        );
+    }
+
+    #[test]
+    fn explaining() {
+        let explanation = Runner::explain(&Day16, FIELDS_EXAMPLE).unwrap().unwrap();
+        assert_eq!(
+            explanation.render_text(),
+            "   1. position 1 must be row, as it's the only candidate left - so position 2, \
+             position 3 can't be\n   2. position 2 must be class, as it's the only candidate \
+             left - so position 3 can't be\n   3. position 3 must be seat, as it's the only \
+             candidate left\n"
+        );
+    }
+
+    #[test]
+    fn eliminating_one_field_at_a_time() {
+        // only position 3 starts with a single candidate, and each field pinned down leaves
+        // another position with just one
+        let input = "a: 1-1\nb: 1-2\nc: 1-3\n\nyour ticket:\n1,1,1\n\nnearby tickets:\n1,2,3\n";
+        assert_eq!(assignment(input).unwrap(), ["a", "b", "c"]);
+        let input = "a: 1-3\nb: 1-3 or 5-5\nc: 1-3 or 6-6\n\nyour ticket:\n1,1,1\n\n\
+                     nearby tickets:\n1,5,6\n2,2,2\n";
+        assert_eq!(assignment(input).unwrap(), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn no_single_candidate_is_ambiguous() {
+        // c only fits position 2, but with two or more candidates for every position there
+        // are always at least two ways to fill them in (Hall's theorem), so this can't be
+        // pinned down however the search goes
+        let input = "a: 1-2\nb: 1-2\nc: 2-2\n\nyour ticket:\n1,2,1\n\nnearby tickets:\n1,2,1\n";
+        assert_eq!(
+            assignment(input).unwrap_err().to_string(),
+            "no solution exists: the fields can't be told apart: position 1 could be 'a' or 'b'"
+        );
+    }
+
+    #[test]
+    fn reports_ambiguous_fields() {
+        let input = "a: 1-5\nb: 1-5\nc: 7-7\n\nyour ticket:\n1,1,7\n\nnearby tickets:\n2,3,7\n";
+        assert_eq!(
+            assignment(input).unwrap_err().to_string(),
+            "no solution exists: the fields can't be told apart: position 1 could be 'a' or 'b'"
+        );
+    }
+
+    #[test]
+    fn reports_impossible_fields() {
+        let input = "a: 1-5\nb: 7-7\nc: 7-7\n\nyour ticket:\n1,1,7\n\nnearby tickets:\n2,3,7\n";
+        assert_eq!(
+            assignment(input).unwrap_err().to_string(),
+            "no solution exists: position 1, position 2 can only be 'a' between them"
+        );
+        let input = "a: 1-5\nb: 7-7\n\nyour ticket:\n1,1\n\nnearby tickets:\n2,6\n";
+        assert_eq!(
+            assignment(input).unwrap_err().to_string(),
+            "no solution exists: position 2 can't be anything"
+        );
+    }
+
+    #[test]
+    fn rejects_tickets_of_the_wrong_length() {
+        let input = "a: 1-5\n\nyour ticket:\n1\n\nnearby tickets:\n2,3\n";
+        assert!(matches!(Day16.parse(input), Err(Error::Unsupported(_))));
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        assert!(matches!(
+            Day16.parse("class: 1-3 or 5-x\n"),
+            Err(Error::Parse {
+                line: 1,
+                column: 17,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Day16.parse("class: 1-3 or 5\n"),
+            Err(Error::Parse {
+                line: 1,
+                column: 15,
+                ..
+            })
+        ));
+    }
 }
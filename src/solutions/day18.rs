@@ -1,136 +1,690 @@
-use nom::{
-    branch::alt,
-    bytes::complete::tag,
-    character::complete::{char, digit1},
-    combinator::map,
-    multi::fold_many0,
-    sequence::{delimited, pair},
-    IResult,
-};
-use std::fs::File;
-use std::io::prelude::*;
+use super::{Answer, Solution};
+use crate::error::{parse_lines, Error, Result};
+use std::collections::HashMap;
+use std::fmt;
 
-#[derive(Debug, Clone)]
-enum Expression {
-    Lit(usize),
-    Plus(Box<Expression>, Box<Expression>),
-    Times(Box<Expression>, Box<Expression>),
+pub struct Day18;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl BinaryOp {
+    pub const ALL: [BinaryOp; 4] = [
+        BinaryOp::Add,
+        BinaryOp::Subtract,
+        BinaryOp::Multiply,
+        BinaryOp::Divide,
+    ];
+
+    pub fn from_symbol(c: char) -> Option<BinaryOp> {
+        match c {
+            '+' => Some(BinaryOp::Add),
+            '-' => Some(BinaryOp::Subtract),
+            '*' => Some(BinaryOp::Multiply),
+            '/' => Some(BinaryOp::Divide),
+            _ => None,
+        }
+    }
+
+    fn apply(&self, a: i64, b: i64) -> Result<i64> {
+        let result = match self {
+            BinaryOp::Add => a.checked_add(b),
+            BinaryOp::Subtract => a.checked_sub(b),
+            BinaryOp::Multiply => a.checked_mul(b),
+            BinaryOp::Divide if b == 0 => {
+                return Err(Error::NoSolution(format!("{} / 0 divides by zero", a)))
+            }
+            // division rounds towards zero
+            BinaryOp::Divide => a.checked_div(b),
+        };
+        result.ok_or_else(|| {
+            Error::Unsupported(format!("{} {} {} is too big to work out", a, self, b))
+        })
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            BinaryOp::Add => '+',
+            BinaryOp::Subtract => '-',
+            BinaryOp::Multiply => '*',
+            BinaryOp::Divide => '/',
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Lit(i64),
+    Negate(Box<Expression>),
+    Binary(BinaryOp, Box<Expression>, Box<Expression>),
 }
 
 impl Expression {
-    fn eval(&self) -> usize {
+    pub fn eval(&self) -> Result<i64> {
+        match self {
+            Expression::Lit(n) => Ok(*n),
+            Expression::Negate(e) => e.eval()?.checked_neg().ok_or_else(|| {
+                Error::Unsupported(String::from("the negation is too big to work out"))
+            }),
+            Expression::Binary(op, e1, e2) => op.apply(e1.eval()?, e2.eval()?),
+        }
+    }
+
+    /// Writes the expression out in the given notation.
+    pub fn render(&self, notation: Notation) -> String {
+        match notation {
+            Notation::Infix => self.to_string(),
+            Notation::Prefix => self.prefix(),
+            Notation::Postfix => self.postfix().join(" "),
+            Notation::Dot => {
+                let mut lines = vec![
+                    String::from("digraph expression {"),
+                    // keeps each operator's left operand drawn on the left
+                    String::from("    ordering=out;"),
+                ];
+                self.dot(&mut lines, &mut 0);
+                lines.push(String::from("}"));
+                lines.join("\n")
+            }
+        }
+    }
+
+    fn prefix(&self) -> String {
+        match self {
+            Expression::Lit(n) => n.to_string(),
+            Expression::Negate(e) => format!("(- {})", e.prefix()),
+            Expression::Binary(op, e1, e2) => format!("({} {} {})", op, e1.prefix(), e2.prefix()),
+        }
+    }
+
+    // unary minus is written as "neg" so it can't be mistaken for subtraction, which
+    // takes two operands
+    fn postfix(&self) -> Vec<String> {
         match self {
-            Expression::Lit(n) => *n,
-            Expression::Plus(e1, e2) => e1.eval() + e2.eval(),
-            Expression::Times(e1, e2) => e1.eval() * e2.eval(),
-        }
-    }
-}
-
-fn read_file() -> Vec<Expression> {
-    let mut file = File::open("./input/input18.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    // because the natural way to write the parser is "right-biased",
-    // we reverse the string before parsing. Provided we remember to switch
-    // opening and closing parentheses in the relevant parser, this works fine
-    // as both operations are commutative
-    contents
-        .lines()
-        .map(|l| parse_all(&l.chars().rev().collect::<String>()[..]))
-        .collect()
-}
-
-// after failing (generally with stack overflows from infinite recursion) with
-// several attempts to write this parser, I eventually found the example at
-// https://github.com/Geal/nom/blob/master/tests/arithmetic.rs and adapted it
-
-fn parse_expr(input: &str) -> IResult<&str, Expression> {
-    let (rest, start) = alt((parse_brackets, parse_number))(input)?;
-
-    fold_many0(
-        pair(alt((tag(" + "), tag(" * "))), parse_expr),
-        start,
-        |acc, (op, val)| {
-            if op == " + " {
-                Expression::Plus(Box::new(acc), Box::new(val))
-            } else {
-                Expression::Times(Box::new(acc), Box::new(val))
+            Expression::Lit(n) => vec![n.to_string()],
+            Expression::Negate(e) => {
+                let mut tokens = e.postfix();
+                tokens.push(String::from("neg"));
+                tokens
+            }
+            Expression::Binary(op, e1, e2) => {
+                let mut tokens = e1.postfix();
+                tokens.extend(e2.postfix());
+                tokens.push(op.to_string());
+                tokens
             }
-        },
-    )(rest)
+        }
+    }
+
+    // adds a node for this expression and everything below it, numbering them in the order
+    // they're reached, and gives the node's name
+    fn dot(&self, lines: &mut Vec<String>, count: &mut usize) -> String {
+        let name = format!("n{}", count);
+        *count += 1;
+        let (label, operands) = match self {
+            Expression::Lit(n) => (n.to_string(), vec![]),
+            Expression::Negate(e) => (String::from("neg"), vec![e]),
+            Expression::Binary(op, e1, e2) => (op.to_string(), vec![e1, e2]),
+        };
+        lines.push(format!("    {} [label=\"{}\"];", name, label));
+        for operand in operands {
+            let child = operand.dot(lines, count);
+            lines.push(format!("    {} -> {};", name, child));
+        }
+        name
+    }
+}
+
+/// The ways an expression can be written out: infix with every operation in brackets,
+/// prefix as an S-expression, postfix (Reverse Polish), or as a Graphviz graph of its tree.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Notation {
+    Infix,
+    Prefix,
+    Postfix,
+    Dot,
+}
+
+impl Notation {
+    pub fn from_name(name: &str) -> Option<Notation> {
+        match name {
+            "infix" => Some(Notation::Infix),
+            "prefix" | "sexp" => Some(Notation::Prefix),
+            "postfix" | "rpn" => Some(Notation::Postfix),
+            "dot" => Some(Notation::Dot),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Notation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Notation::Infix => "infix",
+            Notation::Prefix => "prefix",
+            Notation::Postfix => "postfix",
+            Notation::Dot => "dot",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// written out with every operation in brackets, so how it was grouped is plain to see
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expression::Lit(n) => write!(f, "{}", n),
+            Expression::Negate(e) => write!(f, "-{}", e),
+            Expression::Binary(op, e1, e2) => write!(f, "({} {} {})", e1, op, e2),
+        }
+    }
 }
 
-fn parse_brackets(input: &str) -> IResult<&str, Expression> {
-    // see comment above as to why the parentheses are in the "wrong" order
-    delimited(char(')'), parse_expr, char('('))(input)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Associativity {
+    // a - b - c is (a - b) - c
+    Left,
+    // a - b - c is a - (b - c)
+    Right,
 }
 
-fn parse_number(input: &str) -> IResult<&str, Expression> {
-    map(digit1, |n: &str| Expression::Lit(n.parse().unwrap()))(input)
+impl Associativity {
+    pub fn from_name(name: &str) -> Option<Associativity> {
+        match name {
+            "left" => Some(Associativity::Left),
+            "right" => Some(Associativity::Right),
+            _ => None,
+        }
+    }
 }
 
-fn parse_all(s: &str) -> Expression {
-    let parse_result = parse_expr(s);
-    if let Ok(("", exp)) = parse_result {
-        exp
-    } else {
-        panic!("parsing failed - got {:?}", parse_result);
+impl fmt::Display for Associativity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Associativity::Left => write!(f, "left"),
+            Associativity::Right => write!(f, "right"),
+        }
     }
 }
 
-fn solve(v: Vec<Expression>) -> usize {
-    v.iter().map(|e| e.eval()).sum()
+/// How tightly each operator binds: those with a higher level are worked out first, and
+/// operators on the same level are grouped by their associativity. An operator missing from
+/// the table can't be used at all. Unary minus always binds tighter than anything else.
+#[derive(Debug, Clone)]
+pub struct Precedence {
+    levels: HashMap<BinaryOp, (u8, Associativity)>,
+}
+
+impl Precedence {
+    pub fn new(levels: &[(BinaryOp, u8, Associativity)]) -> Precedence {
+        Precedence {
+            levels: levels
+                .iter()
+                .map(|&(op, level, associativity)| (op, (level, associativity)))
+                .collect(),
+        }
+    }
+
+    /// How tightly an operator binds, and which way it groups, or None if it can't be used.
+    pub fn get(&self, op: BinaryOp) -> Option<(u8, Associativity)> {
+        self.levels.get(&op).copied()
+    }
+
+    /// Puts an operator on a level of its own choosing, or lets it be used if it couldn't.
+    pub fn set(&mut self, op: BinaryOp, level: u8, associativity: Associativity) {
+        self.levels.insert(op, (level, associativity));
+    }
+
+    /// The "new math" of part 1: everything is worked out left to right.
+    pub fn equal() -> Precedence {
+        Precedence::new(&[
+            (BinaryOp::Add, 1, Associativity::Left),
+            (BinaryOp::Subtract, 1, Associativity::Left),
+            (BinaryOp::Multiply, 1, Associativity::Left),
+            (BinaryOp::Divide, 1, Associativity::Left),
+        ])
+    }
+
+    /// The "advanced math" of part 2: addition and subtraction come before multiplication
+    /// and division.
+    pub fn addition_first() -> Precedence {
+        Precedence::new(&[
+            (BinaryOp::Add, 2, Associativity::Left),
+            (BinaryOp::Subtract, 2, Associativity::Left),
+            (BinaryOp::Multiply, 1, Associativity::Left),
+            (BinaryOp::Divide, 1, Associativity::Left),
+        ])
+    }
+
+    /// Arithmetic as taught at school: multiplication and division come first.
+    pub fn standard() -> Precedence {
+        Precedence::new(&[
+            (BinaryOp::Add, 1, Associativity::Left),
+            (BinaryOp::Subtract, 1, Associativity::Left),
+            (BinaryOp::Multiply, 2, Associativity::Left),
+            (BinaryOp::Divide, 2, Associativity::Left),
+        ])
+    }
 }
 
-pub fn part_1() -> usize {
-    let expressions = read_file();
-    solve(expressions)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    Number(i64),
+    Op(BinaryOp),
+    Open,
+    Close,
 }
 
-fn read_file_2() -> Vec<Expression> {
-    let mut file = File::open("./input/input18.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    // reversed, as before
-    contents
-        .lines()
-        .map(|l| parse_all_2(&l.chars().rev().collect::<String>()[..]))
-        .collect()
+// a token, along with the part of the line it came from so errors can point at it
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
 }
 
-fn parse_all_2(s: &str) -> Expression {
-    let parse_result = parse_expr_2(s);
-    if let Ok(("", exp)) = parse_result {
-        exp
-    } else {
-        panic!("parsing failed - got {:?}", parse_result);
+fn tokenise(line: &str) -> Result<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let mut end = start + c.len_utf8();
+        let kind = match c {
+            ' ' => continue,
+            '(' => TokenKind::Open,
+            ')' => TokenKind::Close,
+            '0'..='9' => {
+                while let Some(&(i, '0'..='9')) = chars.peek() {
+                    end = i + 1;
+                    chars.next();
+                }
+                let digits = &line[start..end];
+                let n = digits
+                    .parse()
+                    .map_err(|_| Error::parse(line, digits, "number is too big"))?;
+                TokenKind::Number(n)
+            }
+            _ => match BinaryOp::from_symbol(c) {
+                Some(op) => TokenKind::Op(op),
+                None => {
+                    return Err(Error::parse(
+                        line,
+                        &line[start..end],
+                        "unexpected character",
+                    ))
+                }
+            },
+        };
+        tokens.push(Token {
+            kind,
+            text: &line[start..end],
+        });
     }
+    Ok(tokens)
+}
+
+/*
+Precedence climbing: an operand is read, then operators and the operands after them for
+as long as the operators bind at least as tightly as `min_level`. The right hand side of
+each operator is read the same way, but only taking operators which bind more tightly -
+or, for right-associative operators, just as tightly - so they end up lower in the tree.
+This reads the line left to right in a single pass, whatever the precedence table says.
+
+Brackets, minus signs and right-associative operators each go one level further down, so
+how deep that can go is limited - otherwise a long enough line would overflow the stack.
+*/
+struct Parser<'a> {
+    line: &'a str,
+    tokens: Vec<Token<'a>>,
+    next: usize,
+    precedence: &'a Precedence,
+    // how many levels down the expression being read is
+    depth: usize,
 }
 
-fn parse_expr_2(input: &str) -> IResult<&str, Expression> {
-    let (rest, start) = parse_add(input)?;
+// deeper than any expression written by hand, but well short of overflowing the stack,
+// both here and when the tree is evaluated or written out
+const MAX_DEPTH: usize = 256;
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.next).copied()
+    }
+
+    // the part of the line to blame when something's missing: whatever comes next, or if
+    // there's nothing left, the very end of the line
+    fn here(&self) -> &'a str {
+        match self.peek() {
+            Some(token) => token.text,
+            None => &self.line[self.line.len()..],
+        }
+    }
+
+    // the levels are widened here, so that one past the highest still fits
+    // goes one level further down, unless that's too deep. A parse which fails is given up
+    // on, so only one which succeeds needs to come back up again
+    fn descend(&mut self) -> Result<()> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::parse(
+                self.line,
+                self.here(),
+                "the expression is nested too deeply",
+            ));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn expression(&mut self, min_level: u16) -> Result<Expression> {
+        self.descend()?;
+        let mut lhs = self.operand()?;
+        while let Some(Token {
+            kind: TokenKind::Op(op),
+            text,
+        }) = self.peek()
+        {
+            let (level, associativity) = match self.precedence.levels.get(&op) {
+                Some(&entry) => entry,
+                None => {
+                    return Err(Error::parse(
+                        self.line,
+                        text,
+                        "this operator can't be used here",
+                    ))
+                }
+            };
+            let level = u16::from(level);
+            if level < min_level {
+                break;
+            }
+            self.next += 1;
+            let next_level = match associativity {
+                Associativity::Left => level + 1,
+                Associativity::Right => level,
+            };
+            let rhs = self.expression(next_level)?;
+            lhs = Expression::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        self.depth -= 1;
+        Ok(lhs)
+    }
+
+    fn operand(&mut self) -> Result<Expression> {
+        let token = match self.peek() {
+            Some(token) => token,
+            None => return Err(Error::parse(self.line, self.here(), "expected a number")),
+        };
+        self.next += 1;
+        match token.kind {
+            TokenKind::Number(n) => Ok(Expression::Lit(n)),
+            TokenKind::Op(BinaryOp::Subtract) => {
+                self.descend()?;
+                let negated = self.operand()?;
+                self.depth -= 1;
+                Ok(Expression::Negate(Box::new(negated)))
+            }
+            TokenKind::Open => {
+                let inner = self.expression(0)?;
+                match self.peek() {
+                    Some(Token {
+                        kind: TokenKind::Close,
+                        ..
+                    }) => {
+                        self.next += 1;
+                        Ok(inner)
+                    }
+                    _ => Err(Error::parse(self.line, self.here(), "expected ')'")),
+                }
+            }
+            TokenKind::Op(_) | TokenKind::Close => {
+                Err(Error::parse(self.line, token.text, "expected a number"))
+            }
+        }
+    }
+}
 
-    fold_many0(pair(tag(" * "), parse_add), start, |acc, (_, val)| {
-        Expression::Times(Box::new(acc), Box::new(val))
-    })(rest)
+/// Reads an expression from a single line, with the operators binding as the precedence
+/// table says.
+pub fn parse_expression(line: &str, precedence: &Precedence) -> Result<Expression> {
+    let mut parser = Parser {
+        line,
+        tokens: tokenise(line)?,
+        next: 0,
+        precedence,
+        depth: 0,
+    };
+    let expression = parser.expression(0)?;
+    match parser.peek() {
+        None => Ok(expression),
+        Some(token) if token.kind == TokenKind::Close => {
+            Err(Error::parse(line, token.text, "unmatched ')'"))
+        }
+        Some(token) => Err(Error::parse(line, token.text, "expected an operator")),
+    }
 }
 
-fn parse_add(input: &str) -> IResult<&str, Expression> {
-    let mut base_parser = alt((parse_brackets_2, parse_number));
-    let (rest, start) = base_parser(input)?;
+fn parse_file(contents: &str, precedence: &Precedence) -> Result<Vec<Expression>> {
+    parse_lines(contents, |l| parse_expression(l, precedence))
+}
 
-    fold_many0(pair(tag(" + "), base_parser), start, |acc, (_, val)| {
-        Expression::Plus(Box::new(acc), Box::new(val))
-    })(rest)
+fn solve(v: &[Expression]) -> Result<i64> {
+    v.iter().try_fold(0i64, |total, e| {
+        let value = e.eval()?;
+        total.checked_add(value).ok_or_else(|| {
+            Error::Unsupported(format!(
+                "the total is too big to work out after adding {}",
+                value
+            ))
+        })
+    })
 }
 
-fn parse_brackets_2(input: &str) -> IResult<&str, Expression> {
-    // see comment above as to why the parentheses are in the "wrong" order
-    delimited(char(')'), parse_expr_2, char('('))(input)
+impl Solution for Day18 {
+    // the two parts read the same text with different precedence rules, so each gets its
+    // own parse
+    type Input = (Vec<Expression>, Vec<Expression>);
+
+    fn parse(&self, input: &str) -> Result<(Vec<Expression>, Vec<Expression>)> {
+        Ok((
+            parse_file(input, &Precedence::equal())?,
+            parse_file(input, &Precedence::addition_first())?,
+        ))
+    }
+
+    fn part_1(&self, expressions: &(Vec<Expression>, Vec<Expression>)) -> Result<Answer> {
+        solve(&expressions.0).map(Answer::from)
+    }
+
+    fn part_2(&self, expressions: &(Vec<Expression>, Vec<Expression>)) -> Result<Answer> {
+        solve(&expressions.1).map(Answer::from)
+    }
 }
 
-pub fn part_2() -> usize {
-    let expressions = read_file_2();
-    solve(expressions)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // each example, with its value under the part 1 and part 2 rules
+    const EXAMPLES: [(&str, i64, i64); 6] = [
+        ("1 + 2 * 3 + 4 * 5 + 6", 71, 231),
+        ("1 + (2 * 3) + (4 * (5 + 6))", 51, 51),
+        ("2 * 3 + (4 * 5)", 26, 46),
+        ("5 + (8 * 3 + 9 + 3 * 4 * 3)", 437, 1445),
+        ("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))", 12240, 669060),
+        (
+            "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2",
+            13632,
+            23340,
+        ),
+    ];
+
+    #[test]
+    fn part_1_examples() {
+        for (expression, value, _) in EXAMPLES.iter() {
+            let parsed = parse_expression(expression, &Precedence::equal()).unwrap();
+            assert_eq!(parsed.eval().unwrap(), *value);
+        }
+    }
+
+    #[test]
+    fn part_2_examples() {
+        for (expression, _, value) in EXAMPLES.iter() {
+            let parsed = parse_expression(expression, &Precedence::addition_first()).unwrap();
+            assert_eq!(parsed.eval().unwrap(), *value);
+        }
+    }
+
+    #[test]
+    fn numbers_with_several_digits() {
+        let parsed = parse_file("12 + 3", &Precedence::equal()).unwrap();
+        assert_eq!(solve(&parsed).unwrap(), 15);
+    }
+
+    #[test]
+    fn total_too_big() {
+        let parsed = parse_file("9223372036854775807\n1", &Precedence::equal()).unwrap();
+        assert!(matches!(solve(&parsed), Err(Error::Unsupported(_))));
+    }
+
+    fn eval(line: &str, precedence: &Precedence) -> i64 {
+        parse_expression(line, precedence).unwrap().eval().unwrap()
+    }
+
+    #[test]
+    fn other_operators() {
+        let standard = Precedence::standard();
+        assert_eq!(eval("10 - 4 - 3", &standard), 3);
+        assert_eq!(eval("2 + 12 / 4 * 3", &standard), 11);
+        assert_eq!(eval("-2 * -(3 - 5)", &standard), -4);
+        assert_eq!(eval("7 / -2", &standard), -3);
+        assert_eq!(eval("1 + 2 * 3", &Precedence::equal()), 9);
+        assert!(matches!(
+            parse_expression("1 / (2 - 2)", &standard).unwrap().eval(),
+            Err(Error::NoSolution(_))
+        ));
+    }
+
+    #[test]
+    fn user_defined_precedence() {
+        // subtraction grouping to the right, above everything else
+        let precedence = Precedence::new(&[
+            (BinaryOp::Subtract, 3, Associativity::Right),
+            (BinaryOp::Multiply, 1, Associativity::Left),
+        ]);
+        assert_eq!(eval("10 - 4 - 3", &precedence), 9);
+        assert_eq!(eval("2 * 5 - 1", &precedence), 8);
+        assert!(matches!(
+            parse_expression("1 + 2", &precedence),
+            Err(Error::Parse { column: 3, .. })
+        ));
+        // the highest level there is still groups to the left
+        let mut precedence = Precedence::equal();
+        precedence.set(BinaryOp::Subtract, u8::MAX, Associativity::Left);
+        assert_eq!(eval("10 - 4 - 3", &precedence), 3);
+        assert_eq!(
+            precedence.get(BinaryOp::Subtract),
+            Some((u8::MAX, Associativity::Left))
+        );
+    }
+
+    #[test]
+    fn nesting_too_deeply() {
+        let standard = Precedence::standard();
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(eval(&nested(200), &standard), 1);
+        for line in [
+            nested(100_000),
+            format!("{}1", "-".repeat(100_000)),
+            "(".repeat(100_000),
+        ] {
+            assert!(matches!(
+                parse_expression(&line, &standard),
+                Err(Error::Parse { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn fully_parenthesised() {
+        let shown = |line: &str, precedence: &Precedence| {
+            parse_expression(line, precedence).unwrap().to_string()
+        };
+        assert_eq!(
+            shown("1 + 2 * 3 + 4", &Precedence::equal()),
+            "(((1 + 2) * 3) + 4)"
+        );
+        assert_eq!(
+            shown("1 + 2 * 3 + 4", &Precedence::addition_first()),
+            "((1 + 2) * (3 + 4))"
+        );
+        assert_eq!(
+            shown("-2 * -(3 - 5)", &Precedence::standard()),
+            "(-2 * -(3 - 5))"
+        );
+        assert_eq!(shown("((7))", &Precedence::standard()), "7");
+    }
+
+    #[test]
+    fn notations() {
+        let expression = parse_expression("2 * -(3 + 4) - 1", &Precedence::standard()).unwrap();
+        assert_eq!(expression.render(Notation::Infix), "((2 * -(3 + 4)) - 1)");
+        assert_eq!(
+            expression.render(Notation::Prefix),
+            "(- (* 2 (- (+ 3 4))) 1)"
+        );
+        assert_eq!(expression.render(Notation::Postfix), "2 3 4 + neg * 1 -");
+        assert_eq!(
+            Expression::Lit(5).render(Notation::Dot),
+            "digraph expression {\n    ordering=out;\n    n0 [label=\"5\"];\n}"
+        );
+    }
+
+    #[test]
+    fn dot_follows_the_tree() {
+        let dot = parse_expression("1 + 2 * 3", &Precedence::equal())
+            .unwrap()
+            .render(Notation::Dot);
+        let lines: Vec<&str> = dot.lines().collect();
+        assert_eq!(
+            lines[2..lines.len() - 1],
+            [
+                "    n0 [label=\"*\"];",
+                "    n1 [label=\"+\"];",
+                "    n2 [label=\"1\"];",
+                "    n1 -> n2;",
+                "    n3 [label=\"2\"];",
+                "    n1 -> n3;",
+                "    n0 -> n1;",
+                "    n4 [label=\"3\"];",
+                "    n0 -> n4;",
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let column = |line: &str| match parse_expression(line, &Precedence::equal()) {
+            Err(Error::Parse { column, .. }) => column,
+            other => panic!("expected a parse error, got {:?}", other),
+        };
+        assert_eq!(column("1 + (2 * 3"), 11);
+        assert_eq!(column("1 + 2)"), 6);
+        assert_eq!(column("1 + * 2"), 5);
+        assert_eq!(column("1 2"), 3);
+        assert_eq!(column("1 % 2"), 3);
+    }
+
+    #[test]
+    fn rejects_unbalanced_brackets() {
+        assert!(matches!(
+            Day18.parse("1 + 2\n1 + (2 * 3\n"),
+            Err(Error::Parse { line: 2, .. })
+        ));
+    }
 }
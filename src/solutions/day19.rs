@@ -1,139 +1,666 @@
-use itertools::Itertools;
-use nom::{
-    branch::alt, character::complete::char, combinator::map, multi::many1, sequence::tuple, IResult,
-};
+use super::{Answer, Solution};
+use crate::error::{parse_groups, parse_lines, parse_number, Error, Result};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::prelude::*;
-
-#[allow(dead_code)]
-enum Parser {
-    ParseA,
-    ParseB,
-    Single(usize),
-    Pair(usize, usize),
-    Triple(usize, usize, usize),
-    Choice(Box<Parser>, Box<Parser>),
-    Many(Box<Parser>),
-}
-
-impl Parser {
-    fn get_parser<'a>(
-        &self,
-        parsers: &'a HashMap<usize, Parser>,
-    ) -> Box<dyn FnMut(&'a str) -> IResult<&'a str, ()> + 'a> {
-        match self {
-            Parser::ParseA => Box::new(map(char('a'), |_| ())),
-            Parser::ParseB => Box::new(map(char('b'), |_| ())),
-            Parser::Single(num) => Box::new(parsers.get(num).unwrap().get_parser(parsers)),
-            Parser::Pair(num1, num2) => Box::new(map(
-                tuple((
-                    parsers.get(num1).unwrap().get_parser(parsers),
-                    parsers.get(num2).unwrap().get_parser(parsers),
-                )),
-                |_| (),
-            )),
-            Parser::Triple(num1, num2, num3) => Box::new(map(
-                tuple((
-                    parsers.get(num1).unwrap().get_parser(parsers),
-                    parsers.get(num2).unwrap().get_parser(parsers),
-                    parsers.get(num3).unwrap().get_parser(parsers),
-                )),
-                |_| (),
-            )),
-            Parser::Choice(first, second) => {
-                Box::new(alt((first.get_parser(parsers), second.get_parser(parsers))))
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::Range;
+
+pub struct Day19;
+
+// one item in a sequence: another rule, or text which must appear exactly as given
+#[derive(Clone, Debug, PartialEq)]
+enum Symbol {
+    Rule(usize),
+    Literal(String),
+}
+
+// each alternative is a sequence of symbols, which must match one after the other
+type Rule = Vec<Vec<Symbol>>;
+
+type Rules = HashMap<usize, Rule>;
+
+/*
+Matching works out every position a rule could finish at, rather than settling on the
+first way it can match. The nom parsers used before were greedy: given 8: 42 | 42 8 they
+took as many 42s as they could, and never went back to try fewer when that left nothing
+for the rules after. Keeping every possible end position means each choice is tried, so
+any rules in this format can be matched - including ones which refer to themselves, as
+long as they consume something first.
+
+The end positions of each rule are remembered for each place it starts, as the same rule
+is often tried at the same place by different alternatives. Along the way, the furthest
+place any literal was looked for is kept, with the literals wanted there, to say where a
+message which doesn't match went wrong.
+*/
+struct Matcher<'a> {
+    rules: &'a Rules,
+    message: &'a str,
+    ends: HashMap<(usize, usize), Vec<usize>>,
+    furthest: usize,
+    expected: Vec<&'a str>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(rules: &'a Rules, message: &'a str) -> Matcher<'a> {
+        Matcher {
+            rules,
+            message,
+            ends: HashMap::new(),
+            furthest: 0,
+            expected: vec![],
+        }
+    }
+
+    fn rule_ends(&mut self, rule: usize, start: usize) -> Vec<usize> {
+        if let Some(ends) = self.ends.get(&(rule, start)) {
+            return ends.clone();
+        }
+        let rules = self.rules;
+        let mut ends: Vec<usize> = rules[&rule]
+            .iter()
+            .flat_map(|sequence| self.sequence_ends(sequence, start))
+            .collect();
+        ends.sort_unstable();
+        ends.dedup();
+        self.ends.insert((rule, start), ends.clone());
+        ends
+    }
+
+    fn symbol_ends(&mut self, symbol: &'a Symbol, start: usize) -> Vec<usize> {
+        match symbol {
+            Symbol::Rule(rule) => self.rule_ends(*rule, start),
+            Symbol::Literal(text) if self.message[start..].starts_with(text.as_str()) => {
+                vec![start + text.len()]
+            }
+            Symbol::Literal(text) => {
+                if start > self.furthest {
+                    self.furthest = start;
+                    self.expected.clear();
+                }
+                if start == self.furthest && !self.expected.contains(&text.as_str()) {
+                    self.expected.push(text);
+                }
+                vec![]
             }
-            Parser::Many(repeated) => Box::new(map(many1(repeated.get_parser(parsers)), |_| ())),
         }
     }
 
-    fn can_parse(&self, parsers: &HashMap<usize, Parser>, input: String) -> bool {
-        let mut parser = self.get_parser(parsers);
+    fn sequence_ends(&mut self, sequence: &'a [Symbol], start: usize) -> Vec<usize> {
+        let mut ends = vec![start];
+        for symbol in sequence {
+            let mut next: Vec<usize> = ends
+                .iter()
+                .flat_map(|&end| self.symbol_ends(symbol, end))
+                .collect();
+            next.sort_unstable();
+            next.dedup();
+            ends = next;
+            if ends.is_empty() {
+                break;
+            }
+        }
+        ends
+    }
 
-        if let Ok(("", ())) = parser(&input) {
-            return true;
+    // how a rule matched exactly the given span, taking the first alternative that did
+    fn tree(&mut self, rule: usize, span: Range<usize>) -> Option<ParseTree<'a>> {
+        let rules = self.rules;
+        rules[&rule]
+            .iter()
+            .enumerate()
+            .find_map(|(alternative, sequence)| {
+                let children = self.sequence_trees(sequence, span.clone())?;
+                Some(ParseTree {
+                    rule,
+                    alternative: alternative + 1,
+                    text: &self.message[span.clone()],
+                    span: span.clone(),
+                    children,
+                })
+            })
+    }
+
+    // splits the span between the symbols of a sequence, giving the trees of those which
+    // are rules
+    fn sequence_trees(
+        &mut self,
+        sequence: &'a [Symbol],
+        span: Range<usize>,
+    ) -> Option<Vec<ParseTree<'a>>> {
+        let (first, rest) = match sequence.split_first() {
+            Some(split) => split,
+            None if span.is_empty() => return Some(vec![]),
+            None => return None,
+        };
+        for end in self.symbol_ends(first, span.start) {
+            if end > span.end {
+                break;
+            }
+            let rest_trees = match self.sequence_trees(rest, end..span.end) {
+                Some(trees) => trees,
+                None => continue,
+            };
+            let mut trees = match first {
+                Symbol::Rule(rule) => vec![self.tree(*rule, span.start..end)?],
+                Symbol::Literal(_) => vec![],
+            };
+            trees.extend(rest_trees);
+            return Some(trees);
         }
-        false
+        None
     }
 }
 
-struct PuzzleInfo {
-    parsers: HashMap<usize, Parser>,
-    strings: Vec<String>,
+fn match_ends(rules: &Rules, rule: usize, message: &str, start: usize) -> Vec<usize> {
+    Matcher::new(rules, message).rule_ends(rule, start)
+}
+
+fn matches_fully(rules: &Rules, rule: usize, message: &str) -> bool {
+    match_ends(rules, rule, message, 0).contains(&message.len())
+}
+
+/// Which rule matched which part of a message, and how: the alternative used (counting from
+/// 1) and the trees of the rules in it. Literals aren't given trees of their own.
+#[derive(Debug, PartialEq)]
+pub struct ParseTree<'a> {
+    pub rule: usize,
+    pub alternative: usize,
+    pub text: &'a str,
+    pub span: Range<usize>,
+    pub children: Vec<ParseTree<'a>>,
+}
+
+impl ParseTree<'_> {
+    fn write_indented(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        writeln!(
+            f,
+            "{}{} (alternative {}) matched {:?} at {}..{}",
+            "  ".repeat(depth),
+            self.rule,
+            self.alternative,
+            self.text,
+            self.span.start,
+            self.span.end
+        )?;
+        for child in &self.children {
+            child.write_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+// a line for each rule, indented under the one it's part of
+impl fmt::Display for ParseTree<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_indented(f, 0)
+    }
 }
 
-fn read_file() -> PuzzleInfo {
-    let mut file = File::open("./input/input19.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
+/// Where a message stopped matching: the furthest position reached, and what could have
+/// come next to carry on from there.
+#[derive(Debug, PartialEq)]
+pub struct Mismatch {
+    pub position: usize,
+    pub expected: Vec<String>,
+}
 
-    let parts: Vec<String> = contents
-        .lines()
-        .group_by(|s| s.is_empty())
-        .into_iter()
-        .filter(|(k, _g)| !k)
-        .map(|(_k, g)| g.collect::<Vec<&str>>().join("\n"))
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "no match at {}: expected {}",
+            self.position,
+            self.expected.join(" or ")
+        )
+    }
+}
+
+// matches a whole message against a rule, giving how it matched, or where it went wrong
+fn explain<'a>(
+    rules: &'a Rules,
+    rule: usize,
+    message: &'a str,
+) -> std::result::Result<ParseTree<'a>, Mismatch> {
+    let mut matcher = Matcher::new(rules, message);
+    let ends = matcher.rule_ends(rule, 0);
+    if ends.contains(&message.len()) {
+        if let Some(tree) = matcher.tree(rule, 0..message.len()) {
+            return Ok(tree);
+        }
+    }
+    let mut expected: Vec<String> = matcher
+        .expected
+        .iter()
+        .map(|text| format!("{:?}", text))
         .collect();
+    let mut position = matcher.furthest;
+    // the rule finishing before the end of the message, further on than any literal
+    // failed, is what went wrong
+    if let Some(&end) = ends.last() {
+        if end > position {
+            position = end;
+            expected.clear();
+        }
+        if end == position {
+            expected.push(String::from("the end of the message"));
+        }
+    }
+    Err(Mismatch { position, expected })
+}
+
+pub struct PuzzleInfo {
+    rules: Rules,
+    strings: Vec<String>,
+}
+
+impl PuzzleInfo {
+    pub fn messages(&self) -> &[String] {
+        &self.strings
+    }
+
+    /// The same messages, with rules 8 and 11 replaced by the looping ones of part 2.
+    pub fn with_looping_rules(&self) -> Result<PuzzleInfo> {
+        Ok(PuzzleInfo {
+            rules: looping_rules(&self.rules)?,
+            strings: self.strings.clone(),
+        })
+    }
 
-    PuzzleInfo {
-        parsers: build_parsers(parts[0].lines().map(|s| s.to_owned()).collect()),
-        strings: parts[1].lines().map(|s| s.to_owned()).collect(),
+    /// How a message matches rule 0, or where it stops matching - for finding out why a
+    /// message isn't counted.
+    pub fn explain<'a>(&'a self, message: &'a str) -> std::result::Result<ParseTree<'a>, Mismatch> {
+        explain(&self.rules, 0, message)
     }
+
+    /// How many of the messages match rule 0, checked with the given engine.
+    pub fn count_matches(&self, engine: Engine) -> Result<usize> {
+        count_matches(&self.rules, &self.strings, engine)
+    }
+
+    /// The messages rule 0 matches, as a regular expression, or None if the rules are
+    /// recursive and so can't be written as one.
+    pub fn regex(&self) -> Option<String> {
+        if is_recursive(&self.rules, 0) {
+            None
+        } else {
+            Some(format!("^{}$", regex(&self.rules, 0)))
+        }
+    }
+}
+
+// the rules and the messages are separated by a blank line, and told apart by whether
+// they contain a colon
+enum Section {
+    Rules(Rules),
+    Messages(Vec<String>),
+}
+
+pub fn parse_file(contents: &str) -> Result<PuzzleInfo> {
+    let sections = parse_groups(contents, |group| {
+        if group.contains(':') {
+            build_rules(group).map(Section::Rules)
+        } else {
+            Ok(Section::Messages(
+                group.lines().map(|s| s.to_owned()).collect(),
+            ))
+        }
+    })?;
+    let (rules, strings) = match <[Section; 2]>::try_from(sections) {
+        Ok([Section::Rules(rules), Section::Messages(strings)]) => (rules, strings),
+        _ => {
+            return Err(Error::parse(
+                contents,
+                contents,
+                "expected the rules, then a blank line, then the messages",
+            ))
+        }
+    };
+    check_rules(&rules)?;
+    Ok(PuzzleInfo { rules, strings })
+}
+
+fn build_rules(s: &str) -> Result<Rules> {
+    let rules = parse_lines(s, |l| match l.split_once(": ") {
+        Some((number, expr)) => Ok((parse_number(l, number)?, build_rule(l, expr)?)),
+        None => Err(Error::parse(l, l, "expected a rule like '0: 1 2'")),
+    })?;
+    Ok(rules.into_iter().collect())
 }
 
-fn build_parsers(v: Vec<String>) -> HashMap<usize, Parser> {
-    let mut parsers = HashMap::new();
-    for s in v {
-        let parts: Vec<&str> = s.split(": ").collect();
-        let number = parts[0].parse().unwrap();
-        let expr = parts[1];
-        parsers.insert(number, build_parser(expr));
+// a rule is any number of alternatives separated by '|', each a sequence of rule numbers
+// and quoted literals, such as 1 "ab" 2 | "c". Literals can hold any text but '"'
+fn build_rule(line: &str, s: &str) -> Result<Rule> {
+    let mut alternatives = vec![];
+    let mut sequence = vec![];
+    // where the alternative being read starts, to point at if it turns out to be empty
+    let mut alternative_start = 0;
+    let mut chars = s.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            ' ' => {}
+            '|' => {
+                alternatives.push(finish_alternative(
+                    line,
+                    &s[alternative_start..start],
+                    sequence,
+                )?);
+                sequence = vec![];
+                alternative_start = start + 1;
+            }
+            '"' => {
+                let end = match s[start + 1..].find('"') {
+                    Some(length) => start + 1 + length,
+                    None => return Err(Error::parse(line, &s[start..], "unterminated literal")),
+                };
+                if end == start + 1 {
+                    // an empty literal would let rules match without consuming anything
+                    return Err(Error::parse(
+                        line,
+                        &s[start..end + 1],
+                        "literals can't be empty",
+                    ));
+                }
+                sequence.push(Symbol::Literal(s[start + 1..end].to_owned()));
+                while chars.next_if(|&(i, _)| i <= end).is_some() {}
+            }
+            _ => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, _)) = chars.next_if(|&(_, c)| !matches!(c, ' ' | '|' | '"')) {
+                    end = i + 1;
+                }
+                sequence.push(Symbol::Rule(parse_number(line, &s[start..end])?));
+            }
+        }
     }
-    parsers
+    alternatives.push(finish_alternative(line, &s[alternative_start..], sequence)?);
+    Ok(alternatives)
 }
 
-fn build_parser(s: &str) -> Parser {
-    if s == "\"a\"" {
-        Parser::ParseA
-    } else if s == "\"b\"" {
-        Parser::ParseB
+fn finish_alternative(line: &str, text: &str, sequence: Vec<Symbol>) -> Result<Vec<Symbol>> {
+    if sequence.is_empty() {
+        // a rule matching nothing at all could loop forever without consuming anything,
+        // so isn't allowed
+        Err(Error::parse(line, text, "expected at least one rule"))
     } else {
-        let options: Vec<&str> = s.split(" | ").collect();
-        if options.len() == 2 {
-            Parser::Choice(
-                Box::new(build_parser(options[0])),
-                Box::new(build_parser(options[1])),
-            )
-        } else {
-            let parts: Vec<&str> = s.split(" ").collect();
-            if parts.len() > 2 {
-                Parser::Triple(
-                    parts[0].parse().unwrap(),
-                    parts[1].parse().unwrap(),
-                    parts[2].parse().unwrap(),
-                )
-            } else if parts.len() == 2 {
-                Parser::Pair(parts[0].parse().unwrap(), parts[1].parse().unwrap())
-            } else {
-                Parser::Single(parts[0].parse().unwrap())
+        Ok(sequence)
+    }
+}
+
+// makes sure that rule 0, and every rule referred to, exists, and that no rule can come
+// back round to itself before matching anything - matching that would never finish
+fn check_rules(rules: &Rules) -> Result<()> {
+    let references = |rule: &Rule| -> Vec<usize> {
+        rule.iter()
+            .flatten()
+            .filter_map(|symbol| match symbol {
+                Symbol::Rule(num) => Some(*num),
+                Symbol::Literal(_) => None,
+            })
+            .collect()
+    };
+    let missing = std::iter::once(0)
+        .chain(rules.values().flat_map(references))
+        .find(|num| !rules.contains_key(num));
+    if let Some(num) = missing {
+        return Err(Error::Unsupported(format!(
+            "rule {} is used but never given",
+            num
+        )));
+    }
+
+    // the rules each one could start by matching. Rules still being looked at are in
+    // `visiting`, and finished ones in `done`
+    fn visit(
+        num: usize,
+        rules: &Rules,
+        visiting: &mut Vec<usize>,
+        done: &mut Vec<usize>,
+    ) -> Result<()> {
+        if done.contains(&num) {
+            return Ok(());
+        }
+        if visiting.contains(&num) {
+            return Err(Error::Unsupported(format!(
+                "rule {} can refer back to itself before matching anything",
+                num
+            )));
+        }
+        visiting.push(num);
+        for sequence in &rules[&num] {
+            if let Symbol::Rule(first) = sequence[0] {
+                visit(first, rules, visiting, done)?;
             }
         }
+        visiting.pop();
+        done.push(num);
+        Ok(())
+    }
+    let mut done = Vec::new();
+    for &num in rules.keys() {
+        visit(num, rules, &mut Vec::new(), &mut done)?;
     }
+    Ok(())
 }
 
-fn solve_part_1(info: PuzzleInfo) -> usize {
-    let PuzzleInfo { parsers, strings } = info;
-    let zero_parser = parsers.get(&0).unwrap();
-    strings
+// whether any rule reachable from the given one refers back to itself, in which case the
+// messages it matches can't be described by a regular expression
+fn is_recursive(rules: &Rules, rule: usize) -> bool {
+    fn visit(num: usize, rules: &Rules, visiting: &mut Vec<usize>, done: &mut Vec<usize>) -> bool {
+        if done.contains(&num) {
+            return false;
+        }
+        if visiting.contains(&num) {
+            return true;
+        }
+        visiting.push(num);
+        let recursive = rules[&num].iter().flatten().any(|symbol| match symbol {
+            Symbol::Rule(next) => visit(*next, rules, visiting, done),
+            Symbol::Literal(_) => false,
+        });
+        visiting.pop();
+        done.push(num);
+        recursive
+    }
+    visit(rule, rules, &mut Vec::new(), &mut Vec::new())
+}
+
+// the messages a non-recursive rule matches, as a regular expression
+fn regex(rules: &Rules, rule: usize) -> String {
+    let alternatives: Vec<String> = rules[&rule]
         .iter()
-        .filter(|&s| zero_parser.can_parse(&parsers, s.to_string()))
-        .count()
+        .map(|sequence| {
+            sequence
+                .iter()
+                .map(|symbol| match symbol {
+                    Symbol::Rule(num) => regex(rules, *num),
+                    Symbol::Literal(text) => text
+                        .chars()
+                        .map(|c| {
+                            if "\\.+*?()|[]{}^$".contains(c) {
+                                format!("\\{}", c)
+                            } else {
+                                c.to_string()
+                            }
+                        })
+                        .collect(),
+                })
+                .collect()
+        })
+        .collect();
+    if alternatives.len() == 1 {
+        alternatives.into_iter().next().unwrap()
+    } else {
+        format!("(?:{})", alternatives.join("|"))
+    }
+}
+
+/*
+A rule which doesn't refer back to itself matches a finite set of messages, so it can be
+compiled into a deterministic finite automaton, which checks a message in a single pass
+without ever backtracking.
+
+The rules are first turned into a nondeterministic automaton in the usual way, with each
+reference to a rule replaced by a copy of that rule's states. Each state of the DFA then
+stands for the set of NFA states which could be reached after reading the same text, and
+is built from the sets of states which follow it on each character.
+*/
+#[derive(Default)]
+struct NfaState {
+    // the states which can be moved to without reading anything
+    free: Vec<usize>,
+    on: Vec<(char, usize)>,
+}
+
+struct Nfa {
+    states: Vec<NfaState>,
+}
+
+impl Nfa {
+    fn add_state(&mut self) -> usize {
+        self.states.push(NfaState::default());
+        self.states.len() - 1
+    }
+
+    // adds the states matching a rule, giving the ones it starts and finishes at
+    fn add_rule(&mut self, rules: &Rules, rule: usize) -> (usize, usize) {
+        let (start, finish) = (self.add_state(), self.add_state());
+        for sequence in &rules[&rule] {
+            let mut at = start;
+            for symbol in sequence {
+                match symbol {
+                    Symbol::Rule(num) => {
+                        let (rule_start, rule_finish) = self.add_rule(rules, *num);
+                        self.states[at].free.push(rule_start);
+                        at = rule_finish;
+                    }
+                    Symbol::Literal(text) => {
+                        for c in text.chars() {
+                            let next = self.add_state();
+                            self.states[at].on.push((c, next));
+                            at = next;
+                        }
+                    }
+                }
+            }
+            self.states[at].free.push(finish);
+        }
+        (start, finish)
+    }
+
+    // every state which can be reached from the given ones without reading anything
+    fn closure(&self, states: impl IntoIterator<Item = usize>) -> Vec<usize> {
+        let mut reached: Vec<usize> = states.into_iter().collect();
+        let mut next = 0;
+        while next < reached.len() {
+            for &state in &self.states[reached[next]].free {
+                if !reached.contains(&state) {
+                    reached.push(state);
+                }
+            }
+            next += 1;
+        }
+        reached.sort_unstable();
+        reached
+    }
 }
 
-pub fn part_1() -> usize {
-    let info = read_file();
-    solve_part_1(info)
+struct Dfa {
+    transitions: Vec<HashMap<char, usize>>,
+    accepting: Vec<bool>,
+}
+
+impl Dfa {
+    // the automaton for a rule, or None if it's recursive and so can't have one
+    fn compile(rules: &Rules, rule: usize) -> Option<Dfa> {
+        if is_recursive(rules, rule) {
+            return None;
+        }
+        let mut nfa = Nfa { states: vec![] };
+        let (start, finish) = nfa.add_rule(rules, rule);
+
+        let mut dfa = Dfa {
+            transitions: vec![],
+            accepting: vec![],
+        };
+        let mut ids: HashMap<Vec<usize>, usize> = HashMap::new();
+        let mut sets = vec![nfa.closure(vec![start])];
+        ids.insert(sets[0].clone(), 0);
+        while dfa.transitions.len() < sets.len() {
+            let set = sets[dfa.transitions.len()].clone();
+            let mut targets: HashMap<char, Vec<usize>> = HashMap::new();
+            for &state in &set {
+                for &(c, next) in &nfa.states[state].on {
+                    targets.entry(c).or_default().push(next);
+                }
+            }
+            let mut transitions = HashMap::new();
+            for (c, next) in targets {
+                let next = nfa.closure(next);
+                let id = *ids.entry(next.clone()).or_insert_with(|| {
+                    sets.push(next);
+                    sets.len() - 1
+                });
+                transitions.insert(c, id);
+            }
+            dfa.transitions.push(transitions);
+            dfa.accepting.push(set.binary_search(&finish).is_ok());
+        }
+        Some(dfa)
+    }
+
+    fn matches(&self, message: &str) -> bool {
+        let mut state = 0;
+        for c in message.chars() {
+            state = match self.transitions[state].get(&c) {
+                Some(&next) => next,
+                None => return false,
+            };
+        }
+        self.accepting[state]
+    }
+}
+
+/// How messages are checked against the rules: with an automaton, which only works when
+/// the rules aren't recursive, by backtracking, or with an automaton whenever it can be.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Engine {
+    Auto,
+    Automaton,
+    Backtracking,
+}
+
+impl Engine {
+    pub fn from_name(name: &str) -> Option<Engine> {
+        match name {
+            "auto" => Some(Engine::Auto),
+            "automaton" | "dfa" => Some(Engine::Automaton),
+            "backtracking" => Some(Engine::Backtracking),
+            _ => None,
+        }
+    }
+}
+
+fn count_matches(rules: &Rules, strings: &[String], engine: Engine) -> Result<usize> {
+    let dfa = match engine {
+        Engine::Backtracking => None,
+        Engine::Auto => Dfa::compile(rules, 0),
+        Engine::Automaton => Some(Dfa::compile(rules, 0).ok_or_else(|| {
+            Error::Unsupported(String::from(
+                "the rules are recursive, so can't be checked with an automaton",
+            ))
+        })?),
+    };
+    Ok(match dfa {
+        Some(dfa) => strings.iter().filter(|s| dfa.matches(s)).count(),
+        None => strings
+            .iter()
+            .filter(|s| matches_fully(rules, 0, s))
+            .count(),
+    })
+}
+
+fn solve_part_1(info: &PuzzleInfo) -> Result<usize> {
+    count_matches(&info.rules, &info.strings, Engine::Auto)
 }
 
 /*
@@ -141,32 +668,264 @@ pub fn part_1() -> usize {
 corresponds to "any number (at least 1) of 42s", while
 11: 42 31 | 42 11 31
 corresponds to "any numbers (at least 1) of 42s, followed by the same number of 31s".
-We can easily encode these directly with the Parser::Many variant, which I've added to the enum
+Neither needs special treatment now that matching backtracks, so the new rules are used
+just as they're given.
 */
-#[allow(dead_code)]
-fn solve_part_2(info: PuzzleInfo) -> usize {
-    let PuzzleInfo {
-        mut parsers,
-        strings,
-    } = info;
-
-    // this doesn't work, because the many1 parser combinator is "greedy" - it takes as
-    // many copies of parser 42 as immediately work, and won't backtrack due to a later failure
-    // need to figure out how to get this backtracking!
-    // (perhaps many_till would work - but that needs to know what the "next" parser is to try to apply,
-    // and it's not clear how I could do this with the code as it stands)
-    parsers.insert(8, Parser::Many(Box::new(Parser::Single(42))));
-    // parsers.insert(11, Parser::Many(Box::new(Parser::Pair(42, 31)))); this isn't right, but won't fix until
-    // I figure out why we get a *lower* answer than part 1!
-    let zero_parser = parsers.get(&0).unwrap();
-    strings
-        .iter()
-        .filter(|&s| zero_parser.can_parse(&parsers, s.to_string()))
-        .count()
+fn looping_rules(rules: &Rules) -> Result<Rules> {
+    let mut rules = rules.clone();
+    rules.insert(8, build_rule("", "42 | 42 8")?);
+    rules.insert(11, build_rule("", "42 31 | 42 11 31")?);
+    check_rules(&rules)?;
+    Ok(rules)
 }
 
-#[allow(dead_code)]
-pub fn part_2() -> usize {
-    let info = read_file();
-    solve_part_2(info)
+fn solve_part_2(info: &PuzzleInfo) -> Result<usize> {
+    count_matches(&looping_rules(&info.rules)?, &info.strings, Engine::Auto)
+}
+
+impl Solution for Day19 {
+    type Input = PuzzleInfo;
+
+    fn parse(&self, input: &str) -> Result<PuzzleInfo> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, info: &PuzzleInfo) -> Result<Answer> {
+        solve_part_1(info).map(Answer::from)
+    }
+
+    fn part_2(&self, info: &PuzzleInfo) -> Result<Answer> {
+        solve_part_2(info).map(Answer::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    const EXAMPLE: &str = "\
+0: 4 1 5
+1: 2 3 | 3 2
+2: 4 4 | 5 5
+3: 4 5 | 5 4
+4: \"a\"
+5: \"b\"
+
+ababbb
+bababa
+abbbab
+aaabbb
+aaaabbb
+";
+
+    const LOOPING_EXAMPLE: &str = "\
+42: 9 14 | 10 1
+9: 14 27 | 1 26
+10: 23 14 | 28 1
+1: \"a\"
+11: 42 31
+5: 1 14 | 15 1
+19: 14 1 | 14 14
+12: 24 14 | 19 1
+16: 15 1 | 14 14
+31: 14 17 | 1 13
+6: 14 14 | 1 14
+2: 1 24 | 14 4
+0: 8 11
+13: 14 3 | 1 12
+15: 1 | 14
+17: 14 2 | 1 7
+23: 25 1 | 22 14
+28: 16 1
+4: 1 1
+20: 14 14 | 1 15
+3: 5 14 | 16 1
+27: 1 6 | 14 18
+14: \"b\"
+21: 14 1 | 1 14
+25: 1 1 | 1 14
+22: 14 14
+8: 42
+26: 14 22 | 1 20
+18: 15 15
+7: 14 5 | 1 21
+24: 14 1
+
+abbbbbabbbaaaababbaabbbbabababbbabbbbbbabaaaa
+bbabbbbaabaabba
+babbbbaabbbbbabbbbbbaabaaabaaa
+aaabbbbbbaaaabaababaabababbabaaabbababababaaa
+bbbbbbbaaaabbbbaaabbabaaa
+bbbababbbbaaaaaaaabbababaaababaabab
+ababaaaaaabaaab
+ababaaaaabbbaba
+baabbaaaabbaaaababbaababb
+abbbbabbbbaaaababbbbbbaaaababb
+aaaaabbaabaaaaababaa
+aaaabbaaaabbaaa
+aaaabbaabbaaaaaaabbbabbbaaabbaabaaa
+babaaabbbaaabaababbaabababaaab
+aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba
+";
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day19.run(1, EXAMPLE).unwrap().to_string(), "2");
+    }
+
+    #[test]
+    fn rejects_missing_rules() {
+        assert!(matches!(
+            Day19.parse("0: 1 2\n1: \"a\"\n\nab\n"),
+            Err(Error::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn part_2_example() {
+        assert_eq!(Day19.run(1, LOOPING_EXAMPLE).unwrap().to_string(), "3");
+        assert_eq!(Day19.run(2, LOOPING_EXAMPLE).unwrap().to_string(), "12");
+    }
+
+    #[test]
+    fn every_end_position() {
+        let info = Day19
+            .parse("0: 1 | 1 0\n1: 2 | 2 3\n2: \"a\"\n3: \"b\"\n\nab\n")
+            .unwrap();
+        assert_eq!(match_ends(&info.rules, 0, "abaab", 0), vec![1, 2, 3, 4, 5]);
+        assert_eq!(match_ends(&info.rules, 1, "abaab", 0), vec![1, 2]);
+    }
+
+    #[test]
+    fn right_recursive_rules_are_allowed() {
+        let input = "0: 1 0 | 1\n1: \"a\"\n\na\naaaa\nab\n";
+        assert_eq!(Day19.run(1, input).unwrap().to_string(), "2");
+    }
+
+    #[test]
+    fn rejects_left_recursive_rules() {
+        assert!(matches!(
+            Day19.parse("0: 1\n1: 2 | 1 0\n2: \"a\"\n\nab\n"),
+            Err(Error::Unsupported(_))
+        ));
+        assert!(matches!(
+            Day19.parse("0: 1 2\n1: 0 2\n2: \"a\"\n\nab\n"),
+            Err(Error::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(
+            build_rule("", "1\"a\" | \"b c\" 2 3").unwrap(),
+            vec![
+                vec![Symbol::Rule(1), Symbol::Literal(String::from("a"))],
+                vec![
+                    Symbol::Literal(String::from("b c")),
+                    Symbol::Rule(2),
+                    Symbol::Rule(3)
+                ],
+            ]
+        );
+        let input = "0: \"ab\" 1 | 1 \"x|y\"\n1: \"c\"\n\nabc\ncx|y\nab\nc\n";
+        assert_eq!(Day19.run(1, input).unwrap().to_string(), "2");
+        assert!(matches!(
+            Day19.parse("0: 1 \"ab\n1: \"a\"\n\na\n"),
+            Err(Error::Parse { column: 6, .. })
+        ));
+        assert!(matches!(
+            Day19.parse("0: 1 \"\"\n1: \"a\"\n\na\n"),
+            Err(Error::Parse { column: 6, .. })
+        ));
+    }
+
+    #[test]
+    fn parse_trees() {
+        let info = Day19.parse(EXAMPLE).unwrap();
+        assert_eq!(
+            info.explain("ababbb").unwrap().to_string(),
+            "\
+0 (alternative 1) matched \"ababbb\" at 0..6
+  4 (alternative 1) matched \"a\" at 0..1
+  1 (alternative 2) matched \"babb\" at 1..5
+    3 (alternative 2) matched \"ba\" at 1..3
+      5 (alternative 1) matched \"b\" at 1..2
+      4 (alternative 1) matched \"a\" at 2..3
+    2 (alternative 2) matched \"bb\" at 3..5
+      5 (alternative 1) matched \"b\" at 3..4
+      5 (alternative 1) matched \"b\" at 4..5
+  5 (alternative 1) matched \"b\" at 5..6
+"
+        );
+    }
+
+    #[test]
+    fn explaining_mismatches() {
+        let info = Day19.parse(EXAMPLE).unwrap();
+        assert_eq!(
+            info.explain("aaabbb").unwrap_err().to_string(),
+            "no match at 4: expected \"a\""
+        );
+        assert_eq!(
+            info.explain("aaaabbb").unwrap_err(),
+            Mismatch {
+                position: 6,
+                expected: vec![String::from("the end of the message")]
+            }
+        );
+        let mismatch = info.explain("b").unwrap_err();
+        assert_eq!(mismatch.position, 0);
+    }
+
+    #[test]
+    fn regular_expressions() {
+        let info = Day19.parse(EXAMPLE).unwrap();
+        assert_eq!(
+            info.regex().unwrap(),
+            "^a(?:(?:aa|bb)(?:ab|ba)|(?:ab|ba)(?:aa|bb))b$"
+        );
+        let info = Day19.parse("0: \"a.\" 1\n1: \"(b)\"\n\na\n").unwrap();
+        assert_eq!(info.regex().unwrap(), "^a\\.\\(b\\)$");
+    }
+
+    #[test]
+    fn automaton_agrees_with_backtracking() {
+        for input in [EXAMPLE, LOOPING_EXAMPLE] {
+            let info = Day19.parse(input).unwrap();
+            let dfa = Dfa::compile(&info.rules, 0).unwrap();
+            for message in &info.strings {
+                assert_eq!(
+                    dfa.matches(message),
+                    matches_fully(&info.rules, 0, message),
+                    "{}",
+                    message
+                );
+            }
+            assert!(!dfa.matches(""));
+        }
+    }
+
+    #[test]
+    fn recursive_rules_have_no_automaton() {
+        let input = "0: 1 0 | 1\n1: \"a\"\n\na\n";
+        let info = Day19.parse(input).unwrap();
+        assert!(Dfa::compile(&info.rules, 0).is_none());
+        assert_eq!(info.regex(), None);
+        assert!(matches!(
+            info.count_matches(Engine::Automaton),
+            Err(Error::Unsupported(_))
+        ));
+        // but still match, by backtracking
+        assert_eq!(info.count_matches(Engine::Backtracking).unwrap(), 1);
+        assert_eq!(Day19.run(1, input).unwrap().to_string(), "1");
+    }
+
+    #[test]
+    fn rejects_empty_alternatives() {
+        assert!(matches!(
+            Day19.parse("0: 1 |\n1: \"a\"\n\na\n"),
+            Err(Error::Parse { line: 1, .. })
+        ));
+    }
 }
@@ -1,35 +1,39 @@
-use std::fs::File;
-use std::io::prelude::*;
+use super::{Answer, Solution};
+use crate::error::{parse_lines, parse_number, Error, Result};
 
-struct Policy {
+pub struct Day2;
+
+pub struct Policy {
     min: usize,
     max: usize,
     char: char,
 }
 
-fn read_file() -> Vec<(Policy, String)> {
-    let mut file = File::open("./input/input2.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    contents.lines().map(|l| parse_line(l.to_owned())).collect()
+fn parse_file(contents: &str) -> Result<Vec<(Policy, String)>> {
+    parse_lines(contents, parse_line)
 }
 
-fn parse_line(l: String) -> (Policy, String) {
+fn parse_line(l: &str) -> Result<(Policy, String)> {
     let v: Vec<&str> = l.split(": ").collect();
+    if v.len() != 2 {
+        return Err(Error::parse(l, l, "expected a policy and a password"));
+    }
     let policy = v[0];
     let password = v[1];
-    (parse_policy(policy), password.to_owned())
+    Ok((parse_policy(l, policy)?, password.to_owned()))
 }
 
-fn parse_policy(p: &str) -> Policy {
+fn parse_policy(l: &str, p: &str) -> Result<Policy> {
     let v: Vec<&str> = p.split(' ').collect();
-    let range = v[0];
-    let char = v[1];
-    let ends: Vec<&str> = range.split('-').collect();
-    Policy {
-        min: ends[0].parse().unwrap(),
-        max: ends[1].parse().unwrap(),
-        char: char.chars().nth(0).unwrap(),
+    let ends: Vec<&str> = v[0].split('-').collect();
+    let mut chars = v.get(1).map(|s| s.chars());
+    match (&ends[..], chars.as_mut().and_then(|c| c.next())) {
+        ([min, max], Some(char)) if v.len() == 2 => Ok(Policy {
+            min: parse_number(l, min)?,
+            max: parse_number(l, max)?,
+            char,
+        }),
+        _ => Err(Error::parse(l, p, "expected a policy like '1-3 a'")),
     }
 }
 
@@ -38,26 +42,64 @@ fn is_valid(policy: &Policy, pw: &str) -> bool {
     policy.min <= actual_number && policy.max >= actual_number
 }
 
-fn solve_part_1(v: Vec<(Policy, String)>) -> usize {
+fn solve_part_1(v: &[(Policy, String)]) -> usize {
     v.iter().filter(|(pol, pw)| is_valid(pol, pw)).count()
 }
 
-pub fn part_1() -> usize {
-    let info = read_file();
-    solve_part_1(info)
-}
-
 fn is_valid_new(policy: &Policy, pw: &str) -> bool {
-    let get_char = |pos| pw.chars().nth(pos - 1).unwrap();
-    let is_correct = |pos| get_char(pos) == policy.char;
+    // positions count from 1, and any outside the password can't hold the character
+    let get_char = |pos: usize| pos.checked_sub(1).and_then(|i| pw.chars().nth(i));
+    let is_correct = |pos| get_char(pos) == Some(policy.char);
     is_correct(policy.min) != is_correct(policy.max)
 }
 
-fn solve_part_2(v: Vec<(Policy, String)>) -> usize {
+fn solve_part_2(v: &[(Policy, String)]) -> usize {
     v.iter().filter(|(pol, pw)| is_valid_new(pol, pw)).count()
 }
 
-pub fn part_2() -> usize {
-    let info = read_file();
-    solve_part_2(info)
+impl Solution for Day2 {
+    type Input = Vec<(Policy, String)>;
+
+    fn parse(&self, input: &str) -> Result<Vec<(Policy, String)>> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, info: &Vec<(Policy, String)>) -> Result<Answer> {
+        Ok(solve_part_1(info).into())
+    }
+
+    fn part_2(&self, info: &Vec<(Policy, String)>) -> Result<Answer> {
+        Ok(solve_part_2(info).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    const EXAMPLE: &str = "1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc\n";
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day2.run(1, EXAMPLE).unwrap().to_string(), "2");
+    }
+
+    #[test]
+    fn part_2_example() {
+        assert_eq!(Day2.run(2, EXAMPLE).unwrap().to_string(), "1");
+    }
+
+    #[test]
+    fn positions_past_the_end_do_not_match() {
+        assert_eq!(Day2.run(2, "1-20 a: abc\n").unwrap().to_string(), "1");
+    }
+
+    #[test]
+    fn rejects_malformed_policies() {
+        assert!(matches!(
+            Day2.parse("1-3 a: abcde\n1-x b: cdefg\n"),
+            Err(Error::Parse { line: 2, .. })
+        ));
+    }
 }
@@ -1,10 +1,11 @@
-use itertools::Itertools;
-use std::collections::HashMap;
-use std::convert::TryInto;
-use std::fs::File;
-use std::io::prelude::*;
+use super::{Answer, Solution};
+use crate::error::{parse_groups, parse_number, Error, Result};
+use crate::grid::Grid;
+use std::collections::{HashMap, HashSet};
 
-#[derive(Copy, Clone, Debug)]
+pub struct Day20;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 enum Transformation {
     None,
     RotateOnce,
@@ -31,250 +32,580 @@ impl Transformation {
     }
 }
 
-#[derive(Copy, Clone)]
+impl Transformation {
+    fn apply<T: Clone>(&self, grid: &Grid<T>) -> Grid<T> {
+        match self {
+            Transformation::None => grid.clone(),
+            Transformation::RotateOnce => grid.rotate_right(),
+            Transformation::RotateTwice => grid.rotate_right().rotate_right(),
+            Transformation::RotateThrice => grid.rotate_right().rotate_right().rotate_right(),
+            Transformation::Flip => grid.flip(),
+            Transformation::FlipAndRotateOnce => grid.flip().rotate_right(),
+            Transformation::FlipAndRotateTwice => grid.flip().rotate_right().rotate_right(),
+            Transformation::FlipAndRotateThrice => {
+                grid.flip().rotate_right().rotate_right().rotate_right()
+            }
+        }
+    }
+}
+
+const TILE_SIZE: usize = 10;
+
+#[derive(Clone)]
 struct Tile {
-    content: [[char; 10]; 10],
+    content: Grid<char>,
 }
 
 impl Tile {
-    fn top_edge(&self) -> [char; 10] {
-        self.content[0]
+    fn top_edge(&self) -> Vec<char> {
+        self.content.row(0).to_vec()
     }
 
-    fn bottom_edge(&self) -> [char; 10] {
-        self.content[9]
+    fn bottom_edge(&self) -> Vec<char> {
+        self.content.row(TILE_SIZE - 1).to_vec()
     }
 
-    fn left_edge(&self) -> [char; 10] {
-        self.content
-            .iter()
-            .map(|row| row[0])
-            .collect::<Vec<char>>()
-            .try_into()
-            .unwrap()
+    fn left_edge(&self) -> Vec<char> {
+        (0..TILE_SIZE).map(|row| self.content[(row, 0)]).collect()
     }
 
-    fn right_edge(&self) -> [char; 10] {
-        self.content
-            .iter()
-            .map(|row| row[9])
-            .collect::<Vec<char>>()
-            .try_into()
-            .unwrap()
-    }
-
-    fn flip(&self) -> Tile {
-        Tile {
-            content: self
-                .content
-                .iter()
-                .map(|&row| row)
-                .rev()
-                .collect::<Vec<[char; 10]>>()
-                .try_into()
-                .unwrap(),
-        }
+    fn right_edge(&self) -> Vec<char> {
+        (0..TILE_SIZE)
+            .map(|row| self.content[(row, TILE_SIZE - 1)])
+            .collect()
     }
 
-    fn rotate_right(&self) -> Tile {
-        Tile {
-            content: (0..10)
-                .map(|n| {
-                    self.content
-                        .iter()
-                        .map(|row| row[n])
-                        .collect::<Vec<char>>()
-                        .try_into()
-                        .unwrap()
-                })
-                .rev()
-                .collect::<Vec<[char; 10]>>()
-                .try_into()
-                .unwrap(),
+    fn edges(&self) -> Edges {
+        Edges {
+            top: self.top_edge(),
+            bottom: self.bottom_edge(),
+            left: self.left_edge(),
+            right: self.right_edge(),
         }
     }
 
     fn apply_transformation(&self, t: &Transformation) -> Tile {
-        match t {
-            Transformation::None => Tile {
-                content: self.content,
-            },
-            Transformation::RotateOnce => self.rotate_right(),
-            Transformation::RotateTwice => self.rotate_right().rotate_right(),
-            Transformation::RotateThrice => self.rotate_right().rotate_right().rotate_right(),
-            Transformation::Flip => self.flip(),
-            Transformation::FlipAndRotateOnce => self.flip().rotate_right(),
-            Transformation::FlipAndRotateTwice => self.flip().rotate_right().rotate_right(),
-            Transformation::FlipAndRotateThrice => {
-                self.flip().rotate_right().rotate_right().rotate_right()
-            }
+        Tile {
+            content: t.apply(&self.content),
         }
     }
+}
 
-    fn top_matches(&self, t: Tile) -> Vec<Transformation> {
-        let top = self.top_edge();
+// the picture the tiles make up once they're put together, without their borders
+struct Image {
+    pixels: Grid<char>,
+}
 
-        Transformation::all()
+impl Image {
+    fn assemble(arranged: &[Vec<Tile>]) -> Image {
+        let rows = arranged
             .iter()
-            .filter(|&trans| t.apply_transformation(trans).bottom_edge() == top)
-            .map(|&trans| trans)
-            .collect()
+            .flat_map(|row| {
+                (1..TILE_SIZE - 1).map(move |line| {
+                    row.iter()
+                        .flat_map(|tile| tile.content.row(line)[1..TILE_SIZE - 1].iter().copied())
+                        .collect()
+                })
+            })
+            .collect();
+        Image {
+            pixels: Grid::from_rows(rows).unwrap(),
+        }
     }
 
-    fn bottom_matches(&self, t: Tile) -> Vec<Transformation> {
-        let bottom = self.bottom_edge();
-
-        Transformation::all()
-            .iter()
-            .filter(|&trans| t.apply_transformation(trans).top_edge() == bottom)
-            .map(|&trans| trans)
-            .collect()
+    fn apply_transformation(&self, t: &Transformation) -> Image {
+        Image {
+            pixels: t.apply(&self.pixels),
+        }
     }
 
-    fn left_matches(&self, t: Tile) -> Vec<Transformation> {
-        let left = self.left_edge();
-
-        Transformation::all()
-            .iter()
-            .filter(|&trans| t.apply_transformation(trans).right_edge() == left)
-            .map(|&trans| trans)
+    // the top left corners of everywhere the pattern, given as the positions of its
+    // '#'s, appears in full
+    fn monsters_at(&self, pattern: &[(usize, usize)]) -> Vec<(usize, usize)> {
+        self.pixels
+            .positions()
+            .filter(|&(row, col)| {
+                pattern
+                    .iter()
+                    .all(|&(r, c)| self.pixels.get(row + r, col + c) == Some(&'#'))
+            })
             .collect()
     }
 
-    fn right_matches(&self, t: Tile) -> Vec<Transformation> {
-        let right = self.right_edge();
-
-        Transformation::all()
-            .iter()
-            .filter(|&trans| t.apply_transformation(trans).left_edge() == right)
-            .map(|&trans| trans)
-            .collect()
+    fn count_rough(&self) -> usize {
+        self.pixels.iter().filter(|&&c| c == '#').count()
     }
 }
 
-struct AllTiles {
+// a tile's four edges, each read left to right or top to bottom
+#[derive(Debug, PartialEq)]
+struct Edges {
+    top: Vec<char>,
+    bottom: Vec<char>,
+    left: Vec<char>,
+    right: Vec<char>,
+}
+
+pub struct AllTiles {
     tiles: HashMap<usize, Tile>,
+    // the edges of each tile in all 8 of its orientations, in the order of
+    // `Transformation::all`, worked out once so that trying a tile every way round doesn't
+    // mean turning it round again each time
+    orientations: HashMap<usize, Vec<Edges>>,
 }
 
 impl AllTiles {
-    fn match_info(&self, i: usize) -> [Vec<(usize, Transformation)>; 4] {
-        let tile_to_test = self.tiles.get(&i).unwrap();
-
-        let top_matches = self
-            .tiles
+    fn new(tiles: HashMap<usize, Tile>) -> AllTiles {
+        let orientations = tiles
             .iter()
-            .filter(|(&idx, _)| idx != i)
-            .flat_map(|(&idx, &tile)| {
-                tile_to_test
-                    .top_matches(tile)
-                    .into_iter()
-                    .map(move |trans| (idx, trans))
+            .map(|(&idx, tile)| {
+                let edges = Transformation::all()
+                    .iter()
+                    .map(|trans| tile.apply_transformation(trans).edges())
+                    .collect();
+                (idx, edges)
             })
             .collect();
+        AllTiles {
+            tiles,
+            orientations,
+        }
+    }
 
-        let bottom_matches = self
-            .tiles
-            .iter()
-            .filter(|(&idx, _)| idx != i)
-            .flat_map(|(&idx, &tile)| {
-                tile_to_test
-                    .bottom_matches(tile)
-                    .into_iter()
-                    .map(move |trans| (idx, trans))
-            })
-            .collect();
+    fn edges(&self, idx: usize, trans: Transformation) -> &Edges {
+        &self.orientations[&idx][trans as usize]
+    }
 
-        let left_matches = self
-            .tiles
+    // every other tile, and the way round it would have to be, whose edge picked out by
+    // `other_edge` is the same as `edge`
+    fn matching(
+        &self,
+        i: usize,
+        edge: &[char],
+        other_edge: fn(&Edges) -> &Vec<char>,
+    ) -> Vec<(usize, Transformation)> {
+        self.orientations
             .iter()
             .filter(|(&idx, _)| idx != i)
-            .flat_map(|(&idx, &tile)| {
-                tile_to_test
-                    .left_matches(tile)
-                    .into_iter()
-                    .map(move |trans| (idx, trans))
+            .flat_map(|(&idx, orientations)| {
+                orientations
+                    .iter()
+                    .zip(Transformation::all())
+                    .filter(|(edges, _)| other_edge(edges) == edge)
+                    .map(move |(_, trans)| (idx, trans))
             })
-            .collect();
+            .collect()
+    }
+
+    fn match_info(&self, i: usize) -> [Vec<(usize, Transformation)>; 4] {
+        let edges = self.edges(i, Transformation::None);
+        [
+            self.matching(i, &edges.top, |e| &e.bottom),
+            self.matching(i, &edges.bottom, |e| &e.top),
+            self.matching(i, &edges.left, |e| &e.right),
+            self.matching(i, &edges.right, |e| &e.left),
+        ]
+    }
+}
+
+impl AllTiles {
+    // a printout of all possible matches gives a very pleasing result - there are exactly
+    // 48 cases where no match has been found for a side, 12 for each side. This instantly
+    // tells us what the corner tiles are - they are the ones with exactly 2 empty sets in
+    // their match info!
+    fn corners(&self) -> Result<Vec<usize>> {
+        let mut corners = vec![];
+        for &idx in self.tiles.keys() {
+            let info = self.match_info(idx);
+            let is_corner = info.iter().filter(|v| v.is_empty()).count() == 2;
+            if is_corner {
+                corners.push(idx);
+            }
+        }
+        if corners.len() == 4 {
+            Ok(corners)
+        } else {
+            Err(Error::NoSolution(format!(
+                "expected 4 corner tiles, but found {}",
+                corners.len()
+            )))
+        }
+    }
 
-        let right_matches = self
-            .tiles
+    /*
+    Every edge that belongs next to another only matches that one, so putting the tiles
+    together needs no searching. A corner is turned so that nothing fits above it or to its
+    left, and goes top left. After that each square is filled, a row at a time, with
+    whichever tile is left that fits against the tile to its left and the tile above it -
+    there's only ever one which does, and only one way round. Only the edges are compared
+    along the way, and the tiles are turned round once they've all found their places.
+    */
+    fn arrange(&self) -> Result<Vec<Vec<Tile>>> {
+        let size = (0..).find(|n| n * n >= self.tiles.len()).unwrap_or(0);
+        if size * size != self.tiles.len() {
+            return Err(Error::Unsupported(format!(
+                "{} tiles can't be put together into a square",
+                self.tiles.len()
+            )));
+        }
+
+        let corner = self.corners()?[0];
+        let mut unused: HashSet<usize> = self.tiles.keys().copied().collect();
+        unused.remove(&corner);
+        let top_left = Transformation::all()
             .iter()
-            .filter(|(&idx, _)| idx != i)
-            .flat_map(|(&idx, &tile)| {
-                tile_to_test
-                    .right_matches(tile)
-                    .into_iter()
-                    .map(move |trans| (idx, trans))
+            .copied()
+            .find(|&trans| {
+                let placed = self.edges(corner, trans);
+                unused.iter().all(|other| {
+                    self.orientations[other]
+                        .iter()
+                        .all(|edges| edges.bottom != placed.top && edges.right != placed.left)
+                })
             })
-            .collect();
-
-        [top_matches, bottom_matches, left_matches, right_matches]
+            .ok_or_else(|| {
+                Error::NoSolution(format!("corner tile {} can't go in the corner", corner))
+            })?;
+
+        let mut rows: Vec<Vec<(usize, Transformation)>> = vec![];
+        for row in 0..size {
+            let mut placed: Vec<(usize, Transformation)> = vec![];
+            for col in 0..size {
+                if row == 0 && col == 0 {
+                    placed.push((corner, top_left));
+                    continue;
+                }
+                let above = rows.last().map(|r| self.edges(r[col].0, r[col].1));
+                let left = placed.last().map(|&(idx, trans)| self.edges(idx, trans));
+                let found = unused.iter().find_map(|&idx| {
+                    Transformation::all()
+                        .iter()
+                        .copied()
+                        .find(|&trans| {
+                            let edges = self.edges(idx, trans);
+                            above.is_none_or(|a| a.bottom == edges.top)
+                                && left.is_none_or(|l| l.right == edges.left)
+                        })
+                        .map(|trans| (idx, trans))
+                });
+                match found {
+                    Some((idx, trans)) => {
+                        unused.remove(&idx);
+                        placed.push((idx, trans));
+                    }
+                    None => {
+                        return Err(Error::NoSolution(format!(
+                            "no tile fits at row {}, column {}",
+                            row + 1,
+                            col + 1
+                        )))
+                    }
+                }
+            }
+            rows.push(placed);
+        }
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|(idx, trans)| self.tiles[&idx].apply_transformation(&trans))
+                    .collect()
+            })
+            .collect())
     }
 }
 
-fn read_file() -> AllTiles {
-    let mut file = File::open("./input/input20.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-
-    let parts: Vec<String> = contents
-        .lines()
-        .group_by(|s| s.is_empty())
-        .into_iter()
-        .filter(|(k, _g)| !k)
-        .map(|(_k, g)| g.collect::<Vec<&str>>().join("\n"))
-        .collect();
-
+fn parse_file(contents: &str) -> Result<AllTiles> {
     let mut tiles = HashMap::new();
 
-    for part in parts {
-        let (id, tile) = parse_tile(part);
-        tiles.insert(id, tile);
+    for (id, tile) in parse_groups(contents, parse_tile)? {
+        if tiles.insert(id, tile).is_some() {
+            return Err(Error::Unsupported(format!("tile {} appears twice", id)));
+        }
     }
 
-    AllTiles { tiles }
+    Ok(AllTiles::new(tiles))
 }
 
-fn parse_tile(s: String) -> (usize, Tile) {
-    let lines: Vec<&str> = s.lines().collect();
-    let id = lines[0][5..9].parse().unwrap();
-    let tile = Tile {
-        content: lines[1..]
-            .iter()
-            .map(|line| line.chars().collect::<Vec<char>>().try_into().unwrap())
-            .collect::<Vec<[char; 10]>>()
-            .try_into()
-            .unwrap(),
+fn parse_tile(s: &str) -> Result<(usize, Tile)> {
+    let (header, rows) = s.split_once('\n').unwrap_or((s, ""));
+    let id = match header
+        .strip_prefix("Tile ")
+        .and_then(|rest| rest.strip_suffix(':'))
+    {
+        Some(id) => parse_number(header, id)?,
+        None => {
+            return Err(Error::parse(
+                header,
+                header,
+                "expected a header like 'Tile 1234:'",
+            ))
+        }
     };
+    let content = Grid::parse(
+        rows,
+        |c| Some(c).filter(|&c| c == '.' || c == '#'),
+        "expected '.' or '#'",
+    )
+    .map_err(|e| e.offset_lines(1))?;
+    if content.width() != TILE_SIZE || content.height() != TILE_SIZE {
+        return Err(Error::parse(rows, rows, "tiles must be 10 by 10").offset_lines(1));
+    }
 
-    (id, tile)
+    Ok((id, Tile { content }))
 }
 
-fn solve_part_1(tiles: AllTiles) -> usize {
-    // we can try to get a printout of all possible matches:
-    // this works and gives a very pleasing result - there are exactly 48 cases where
-    // no match has been found for a side, 12 for each side. This instantly tells us what the
-    // corner tiles are - they are the ones with exactly 2 empty sets in their match info!
-
-    // (In fact, there is only one possibility for each side, which makes reconstructing the
-    // full pattern simple. Will do, and thereby attempt to solve part 2, when I have more time!)
-    let mut corners = vec![];
-    for &idx in tiles.tiles.keys() {
-        let info = tiles.match_info(idx);
-        // println!("match information about tile #{}: {:?}", idx, info);
-        let is_corner = info.iter().filter(|v| v.len() == 0).count() == 2;
-        if is_corner {
-            corners.push(idx);
+fn solve_part_1(tiles: &AllTiles) -> Result<usize> {
+    Ok(tiles.corners()?.iter().product())
+}
+
+/*
+The sea monster, as it appears in the image. Only the '#'s are part of it - the spaces
+could be anything.
+*/
+const SEA_MONSTER: [&str; 3] = [
+    "                  # ",
+    "#    ##    ##    ###",
+    " #  #  #  #  #  #   ",
+];
+
+fn solve_part_2(tiles: &AllTiles) -> Result<usize> {
+    let image = Image::assemble(&tiles.arrange()?);
+    let monster: Vec<(usize, usize)> = SEA_MONSTER
+        .iter()
+        .enumerate()
+        .flat_map(|(row, line)| {
+            line.chars()
+                .enumerate()
+                .filter(|&(_, c)| c == '#')
+                .map(move |(col, _)| (row, col))
+        })
+        .collect();
+
+    // the monsters only show up with the image the right way round, so look at each
+    // orientation until some are found. Monsters could in principle overlap, so the
+    // squares belonging to any of them are collected up before counting
+    for trans in Transformation::all().iter() {
+        let oriented = image.apply_transformation(trans);
+        let in_monsters: HashSet<(usize, usize)> = oriented
+            .monsters_at(&monster)
+            .into_iter()
+            .flat_map(|(row, col)| monster.iter().map(move |&(r, c)| (row + r, col + c)))
+            .collect();
+        if !in_monsters.is_empty() {
+            return Ok(oriented.count_rough() - in_monsters.len());
         }
     }
-    if corners.len() == 4 {
-        corners.iter().product()
-    } else {
-        panic!("not 4 corners!");
+    Err(Error::NoSolution(String::from(
+        "no sea monsters are in the image, however it's turned",
+    )))
+}
+
+impl Solution for Day20 {
+    type Input = AllTiles;
+
+    fn parse(&self, input: &str) -> Result<AllTiles> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, tiles: &AllTiles) -> Result<Answer> {
+        solve_part_1(tiles).map(Answer::from)
+    }
+
+    fn part_2(&self, tiles: &AllTiles) -> Result<Answer> {
+        solve_part_2(tiles).map(Answer::from)
     }
 }
 
-pub fn part_1() -> usize {
-    let tiles = read_file();
-    solve_part_1(tiles)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    const EXAMPLE: &str = "\
+Tile 2311:
+..##.#..#.
+##..#.....
+#...##..#.
+####.#...#
+##.##.###.
+##...#.###
+.#.#.#..##
+..#....#..
+###...#.#.
+..###..###
+
+Tile 1951:
+#.##...##.
+#.####...#
+.....#..##
+#...######
+.##.#....#
+.###.#####
+###.##.##.
+.###....#.
+..#.#..#.#
+#...##.#..
+
+Tile 1171:
+####...##.
+#..##.#..#
+##.#..#.#.
+.###.####.
+..###.####
+.##....##.
+.#...####.
+#.##.####.
+####..#...
+.....##...
+
+Tile 1427:
+###.##.#..
+.#..#.##..
+.#.##.#..#
+#.#.#.##.#
+....#...##
+...##..##.
+...#.#####
+.#.####.#.
+..#..###.#
+..##.#..#.
+
+Tile 1489:
+##.#.#....
+..##...#..
+.##..##...
+..#...#...
+#####...#.
+#..#.#.#.#
+...#.#.#..
+##.#...##.
+..##.##.##
+###.##.#..
+
+Tile 2473:
+#....####.
+#..#.##...
+#.##..#...
+######.#.#
+.#...#.#.#
+.#########
+.###.#..#.
+########.#
+##...##.#.
+..###.#.#.
+
+Tile 2971:
+..#.#....#
+#...###...
+#.#.###...
+##.##..#..
+.#####..##
+.#..####.#
+#..#.#..#.
+..####.###
+..#.#.###.
+...#.#.#.#
+
+Tile 2729:
+...#.#.#.#
+####.#....
+..#.#.....
+....#..#.#
+.##..##.#.
+.#.####...
+####.#.#..
+##.####...
+##..#.##..
+#.##...##.
+
+Tile 3079:
+#.#.#####.
+.#..######
+..#.......
+######....
+####.#..#.
+.#...#.##.
+#.#####.##
+..#.###...
+..#.......
+..#.###...
+";
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day20.run(1, EXAMPLE).unwrap().to_string(), "20899048083289");
+    }
+
+    #[test]
+    fn part_2_example() {
+        assert_eq!(Day20.run(2, EXAMPLE).unwrap().to_string(), "273");
+    }
+
+    #[test]
+    fn assembles_a_square_image() {
+        let tiles = Day20.parse(EXAMPLE).unwrap();
+        let arranged = tiles.arrange().unwrap();
+        assert_eq!(arranged.len(), 3);
+        assert!(arranged.iter().all(|row| row.len() == 3));
+        let image = Image::assemble(&arranged);
+        assert_eq!((image.pixels.width(), image.pixels.height()), (24, 24));
+    }
+
+    #[test]
+    fn rejects_tiles_that_cant_make_a_square() {
+        let two_tiles = EXAMPLE
+            .split("\n\n")
+            .take(2)
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let tiles = Day20.parse(&two_tiles).unwrap();
+        assert!(matches!(tiles.arrange(), Err(Error::Unsupported(_))));
+    }
+
+    #[test]
+    fn transformations_keep_edges() {
+        let tiles = Day20.parse(EXAMPLE).unwrap();
+        let tile = &tiles.tiles[&2311];
+        let flipped = tile.apply_transformation(&Transformation::Flip);
+        assert_eq!(flipped.top_edge(), tile.bottom_edge());
+        assert_eq!(
+            flipped.apply_transformation(&Transformation::Flip).content,
+            tile.content
+        );
+        let rotated = tile.apply_transformation(&Transformation::RotateOnce);
+        let mut left = tile.left_edge();
+        left.reverse();
+        assert_eq!(rotated.top_edge(), left);
+        assert_eq!(
+            rotated
+                .apply_transformation(&Transformation::RotateThrice)
+                .content,
+            tile.content
+        );
+    }
+
+    #[test]
+    fn rejects_narrow_tiles() {
+        assert!(matches!(
+            Day20.parse("Tile 1:\n..#\n"),
+            Err(Error::Parse {
+                line: 2,
+                column: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        assert!(matches!(
+            Day20.parse("Tile one:\n"),
+            Err(Error::Parse {
+                line: 1,
+                column: 6,
+                ..
+            })
+        ));
+    }
 }
@@ -1,160 +1,206 @@
-use std::collections::HashMap;
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::prelude::*;
+use super::{Answer, Solution};
+use crate::deduction::{self, Deduction, Explanation};
+use crate::error::{parse_lines, Error, Result};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+pub struct Day21;
 
 #[derive(Debug)]
-struct AllergenInfo {
+pub struct AllergenInfo {
     ingredients: HashSet<String>,
     allergens: HashSet<String>,
 }
 
 struct InformationSummary {
-    // associates each allergen with the set of ingredients which could potentially contain it
-    summary: HashMap<String, HashSet<String>>,
+    // associates each allergen with the set of ingredients which could potentially contain
+    // it: those on the list of every food known to contain the allergen
+    summary: BTreeMap<String, BTreeSet<String>>,
 }
 
 impl InformationSummary {
-    fn new() -> InformationSummary {
-        InformationSummary {
-            summary: HashMap::new(),
+    fn new(foods: &[AllergenInfo]) -> InformationSummary {
+        let mut summary: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for food in foods {
+            for allergen in &food.allergens {
+                let possible_ingredients = summary
+                    .entry(allergen.to_owned())
+                    .or_insert_with(|| food.ingredients.iter().cloned().collect());
+                possible_ingredients.retain(|ingredient| food.ingredients.contains(ingredient));
+            }
         }
+        InformationSummary { summary }
     }
 
-    fn add_allergen_information(&mut self, info: &AllergenInfo) -> () {
-        for allergen in info.allergens.iter() {
-            let possible_ingredients = self.summary.get(allergen);
-
-            let new_ingredients = match possible_ingredients {
-                Some(ingredient_set) => ingredient_set
-                    .intersection(&info.ingredients)
-                    .map(|s| s.to_owned())
-                    .collect(),
-                None => info.ingredients.iter().map(|s| s.to_owned()).collect(),
-            };
+    fn can_contain_allergen(&self, ingredient: &str) -> bool {
+        self.summary.values().any(|set| set.contains(ingredient))
+    }
 
-            self.summary.insert(allergen.to_owned(), new_ingredients);
+    // works out the ingredient containing each allergen. Each allergen is in exactly one
+    // ingredient, and each ingredient has at most one allergen. The allergens and the
+    // ingredients which might have them are given too, in alphabetical order, as the
+    // deduction numbers them
+    fn deduce(&self) -> Result<(Vec<&str>, Vec<&str>, Deduction)> {
+        let allergens: Vec<&str> = self.summary.keys().map(|s| s.as_str()).collect();
+        let ingredients: Vec<&str> = self
+            .summary
+            .values()
+            .flatten()
+            .map(|s| s.as_str())
+            .collect::<BTreeSet<&str>>()
+            .into_iter()
+            .collect();
+        let candidates: Vec<Vec<usize>> = self
+            .summary
+            .values()
+            .map(|set| {
+                set.iter()
+                    .map(|ingredient| {
+                        ingredients
+                            .binary_search(&ingredient.as_str())
+                            .expect("every candidate is in the list of ingredients")
+                    })
+                    .collect()
+            })
+            .collect();
+        match deduction::solve(&candidates, ingredients.len()) {
+            Ok(deduction) => Ok((allergens, ingredients, deduction)),
+            Err(failure) => Err(Error::NoSolution(failure.describe(
+                |allergen| format!("'{}'", allergens[allergen]),
+                |ingredient| format!("'{}'", ingredients[ingredient]),
+            ))),
         }
     }
 
-    fn can_contain_allergen(&self, ingredient: String) -> bool {
-        let res = self
-            .summary
-            .iter()
-            .any(|(_, set)| set.contains(&ingredient));
-        res
+    // the ingredient containing each allergen, in alphabetical order of the allergens
+    fn dangerous_ingredients(&self) -> Result<Vec<&str>> {
+        let (_, ingredients, deduction) = self.deduce()?;
+        Ok(deduction
+            .assignment
+            .into_iter()
+            .map(|ingredient| ingredients[ingredient])
+            .collect())
     }
 }
 
-fn read_file() -> Vec<AllergenInfo> {
-    let mut file = File::open("./input/input21.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-
-    contents.lines().map(parse_line).collect()
+fn parse_file(contents: &str) -> Result<Vec<AllergenInfo>> {
+    parse_lines(contents, parse_line)
 }
 
-fn parse_line(s: &str) -> AllergenInfo {
-    let parts: Vec<&str> = s.split(" (contains ").collect();
-    let ingredients = parts[0].split(" ").map(|s| s.to_owned()).collect();
+// a food needn't list any allergens, in which case the brackets are left off
+fn parse_line(s: &str) -> Result<AllergenInfo> {
+    let (ingredients, allergens) = match s.split_once(" (contains ") {
+        Some((ingredients, rest)) => match rest.strip_suffix(')') {
+            Some(allergens) => (ingredients, allergens.split(", ").collect()),
+            None => return Err(Error::parse(s, rest, "expected a closing ')'")),
+        },
+        None => (s, vec![]),
+    };
+    if ingredients.is_empty() {
+        return Err(Error::parse(s, s, "expected a list of ingredients"));
+    }
 
-    let allergens = parts[1][..parts[1].len() - 1]
-        .split(", ")
-        .map(|s| s.to_owned())
-        .collect();
+    Ok(AllergenInfo {
+        ingredients: ingredients.split(' ').map(|s| s.to_owned()).collect(),
+        allergens: allergens.into_iter().map(|s| s.to_owned()).collect(),
+    })
+}
 
-    AllergenInfo {
-        ingredients,
-        allergens,
-    }
+fn solve_part_1(info: &[AllergenInfo]) -> usize {
+    let summary = InformationSummary::new(info);
+    info.iter()
+        .flat_map(|food| &food.ingredients)
+        .filter(|ingredient| !summary.can_contain_allergen(ingredient))
+        .count()
 }
 
-fn all_ingredients(v: &Vec<AllergenInfo>) -> Vec<String> {
-    let mut ingredients: HashSet<String> = HashSet::new();
-    v.iter().for_each(|info| {
-        ingredients = ingredients
-            .union(&info.ingredients.iter().map(|s| s.to_owned()).collect())
-            .map(|s| s.to_owned())
-            .collect();
-    });
-    ingredients.iter().map(|s| s.to_owned()).collect()
+fn solve_part_2(info: &[AllergenInfo]) -> Result<String> {
+    Ok(InformationSummary::new(info)
+        .dangerous_ingredients()?
+        .join(","))
 }
 
-fn solve_part_1(info: Vec<AllergenInfo>) -> usize {
-    let mut summary = InformationSummary::new();
-    for line in info.iter() {
-        summary.add_allergen_information(line);
+impl Solution for Day21 {
+    type Input = Vec<AllergenInfo>;
+
+    fn parse(&self, input: &str) -> Result<Vec<AllergenInfo>> {
+        parse_file(input)
     }
 
-    let no_allergens: Vec<String> = all_ingredients(&info)
-        .iter()
-        .filter(|&ing| !summary.can_contain_allergen(ing.to_owned()))
-        .map(|s| s.to_owned())
-        .collect();
+    fn part_1(&self, info: &Vec<AllergenInfo>) -> Result<Answer> {
+        Ok(solve_part_1(info).into())
+    }
 
-    info.iter()
-        .map(|line| {
-            line.ingredients
-                .iter()
-                .filter(|i| no_allergens.iter().any(|ing| &ing == i))
-                .count()
-        })
-        .sum()
-}
+    fn part_2(&self, info: &Vec<AllergenInfo>) -> Result<Answer> {
+        solve_part_2(info).map(Answer::from)
+    }
 
-pub fn part_1() -> usize {
-    let info = read_file();
-    solve_part_1(info)
+    fn explain(&self, info: &Vec<AllergenInfo>) -> Result<Option<Explanation>> {
+        let summary = InformationSummary::new(info);
+        let (allergens, ingredients, deduction) = summary.deduce()?;
+        let names = |names: Vec<&str>| names.into_iter().map(String::from).collect();
+        Ok(Some(Explanation {
+            items: names(allergens),
+            values: names(ingredients),
+            trace: deduction.trace,
+        }))
+    }
 }
 
-fn solve_part_2(info: Vec<AllergenInfo>) -> String {
-    // very messy way to compute the answer in code. Would have been much quicker just to work with the
-    // summary printout and do by hand!
-    let mut summary = InformationSummary::new();
-    for line in info.iter() {
-        summary.add_allergen_information(line);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    const EXAMPLE: &str = "\
+mxmxvkd kfcds sqjhc nhms (contains dairy, fish)
+trh fvjkl sbzzf mxmxvkd (contains dairy)
+sqjhc fvjkl (contains soy)
+sqjhc mxmxvkd sbzzf (contains fish)
+";
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day21.run(1, EXAMPLE).unwrap().to_string(), "5");
     }
-    let allergen_count = summary.summary.iter().count();
-
-    let mut results: HashMap<String, String> = HashMap::new();
-
-    while results.len() < allergen_count {
-        let mut reduced_summary = InformationSummary {
-            summary: HashMap::new(),
-        };
-        {
-            let one_left = summary.summary.iter().find(|(_, s)| s.len() == 1).unwrap();
-            let (allergen, set_of_one) = one_left;
-            let ingredient = set_of_one.iter().nth(0).unwrap().to_owned();
-            results.insert(allergen.to_owned(), ingredient);
-            let ingredient_again = results.get(allergen).unwrap();
-            for allergen in summary.summary.keys() {
-                let reduced_set = summary.summary.get(allergen).unwrap();
-                let mut new_set = HashSet::new();
-                for ing in reduced_set {
-                    if ing != ingredient_again {
-                        new_set.insert(ing.to_owned());
-                    }
-                }
-                reduced_summary.summary.insert(allergen.to_owned(), new_set);
-            }
-        }
-        summary = reduced_summary;
+
+    #[test]
+    fn part_2_example() {
+        assert_eq!(
+            Day21.run(2, EXAMPLE).unwrap().to_string(),
+            "mxmxvkd,sqjhc,fvjkl"
+        );
     }
 
-    let mut pairs = results
-        .iter()
-        .map(|(k, v)| (k.to_owned(), v.to_owned()))
-        .collect::<Vec<(String, String)>>();
-    pairs.sort_unstable_by(|(k1, _), (k2, _)| k1.partial_cmp(k2).unwrap());
-    pairs
-        .iter()
-        .map(|(_, v)| v.to_owned())
-        .collect::<Vec<String>>()
-        .join(",")
-}
+    #[test]
+    fn explaining() {
+        let explanation = Runner::explain(&Day21, EXAMPLE).unwrap().unwrap();
+        assert_eq!(
+            explanation.render_text(),
+            "   1. dairy must be mxmxvkd, as it's the only candidate left - so fish can't be\n   \
+             2. fish must be sqjhc, as it's the only candidate left - so soy can't be\n   \
+             3. soy must be fvjkl, as it's the only candidate left\n"
+        );
+    }
 
-pub fn part_2() -> String {
-    let info = read_file();
-    solve_part_2(info)
+    #[test]
+    fn reports_allergens_which_cant_be_told_apart() {
+        let input = "a b (contains dairy, fish)\na b c\n";
+        assert_eq!(
+            Day21.run(2, input).unwrap_err().to_string(),
+            "no solution exists: 'dairy' could be 'a' or 'b'"
+        );
+        let input = "a (contains dairy)\na b (contains fish, soy)\n";
+        assert_eq!(
+            Day21.run(2, input).unwrap_err().to_string(),
+            "no solution exists: 'dairy', 'fish', 'soy' can only be 'a', 'b' between them"
+        );
+    }
+
+    #[test]
+    fn rejects_unclosed_allergen_lists() {
+        assert!(matches!(
+            Day21.parse("mxmxvkd kfcds (contains dairy\n"),
+            Err(Error::Parse { line: 1, .. })
+        ));
+    }
 }
@@ -1,8 +1,14 @@
-use std::fs::File;
-use std::io::prelude::*;
+use super::{Answer, Solution};
+use crate::error::{parse_groups, parse_lines, parse_number, Error, Result};
+use std::cmp::Reverse;
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
 
+pub struct Day22;
+
+#[derive(Clone)]
 struct Deck {
-    content: Vec<usize>,
+    content: VecDeque<usize>,
 }
 
 impl Deck {
@@ -14,191 +20,752 @@ impl Deck {
             .map(|(idx, card)| (idx + 1) * card)
             .sum()
     }
-}
 
-#[derive(Copy, Clone)]
-enum GameWinner {
-    Player1,
-    Player2,
+    fn cards(&self) -> Vec<usize> {
+        self.content.iter().copied().collect()
+    }
+
+    // a new deck of copies of the top `count` cards, for a sub-game
+    fn top(&self, count: usize) -> Deck {
+        Deck {
+            content: self.content.iter().take(count).copied().collect(),
+        }
+    }
 }
 
-struct Game {
-    player1: Deck,
-    player2: Deck,
+/// The players' decks, in the order the input gives them. Players are numbered from 0
+/// here, and from 1 wherever they're written out. A player with no cards left is out of
+/// the game.
+#[derive(Clone)]
+pub struct Game {
+    decks: Vec<Deck>,
 }
 
 impl Game {
-    fn winner(&self) -> Option<GameWinner> {
-        if self.player2.content.len() == 0 {
-            Some(GameWinner::Player1)
-        } else if self.player1.content.len() == 0 {
-            Some(GameWinner::Player2)
-        } else {
-            None
+    fn players_in(&self) -> impl DoubleEndedIterator<Item = usize> + '_ {
+        (0..self.decks.len()).filter(move |&player| !self.decks[player].content.is_empty())
+    }
+
+    // the game is won once only one player has any cards left
+    fn winner(&self) -> Option<usize> {
+        let mut players = self.players_in();
+        match (players.next(), players.next()) {
+            (Some(player), None) => Some(player),
+            _ => None,
         }
     }
 
-    fn play_round(&mut self) -> Option<GameWinner> {
-        let p1_card = self.player1.content[0];
-        let p2_card = self.player2.content[0];
-        self.player1.content = self.player1.content[1..].to_vec();
-        self.player2.content = self.player2.content[1..].to_vec();
-        if p1_card > p2_card {
-            self.player1.content.push(p1_card);
-            self.player1.content.push(p2_card);
-        } else {
-            self.player2.content.push(p2_card);
-            self.player2.content.push(p1_card);
+    fn decks(&self) -> Vec<Vec<usize>> {
+        self.decks.iter().map(|deck| deck.cards()).collect()
+    }
+
+    // a key for the decks as they are, to tell whether they've come round again: every
+    // card in order, with a separator after each deck so the same cards split differently
+    // between the players give a different key
+    fn state_key(&self) -> Vec<usize> {
+        let mut key =
+            Vec::with_capacity(self.decks.iter().map(|deck| deck.content.len() + 1).sum());
+        for deck in &self.decks {
+            key.extend(deck.content.iter().copied());
+            key.push(usize::MAX);
         }
-        self.winner()
+        key
     }
 
-    fn winner_score(&self) -> usize {
-        match self.winner().unwrap() {
-            GameWinner::Player1 => self.player1.deck_score(),
-            GameWinner::Player2 => self.player2.deck_score(),
+    // each player still in takes their top card, which are put in `drawn` along with who
+    // drew them. `drawn` is kept from round to round, so it isn't allocated each time
+    fn draw(&mut self, drawn: &mut Vec<(usize, usize)>) {
+        drawn.clear();
+        for (player, deck) in self.decks.iter_mut().enumerate() {
+            if let Some(card) = deck.content.pop_front() {
+                drawn.push((player, card));
+            }
         }
     }
+
+    // the winner of a round puts the cards on the bottom of their deck, their own first and
+    // then the rest from highest to lowest
+    fn take_cards(&mut self, drawn: &mut [(usize, usize)], winner: usize) {
+        drawn.sort_unstable_by_key(|&(player, card)| (player != winner, Reverse(card)));
+        self.decks[winner]
+            .content
+            .extend(drawn.iter().map(|&(_, card)| card));
+    }
+
+    // a new game for deciding a round, in which each player still in has copies of as many
+    // of their next cards as the card they drew
+    fn sub_game(&self, drawn: &[(usize, usize)]) -> Game {
+        let mut decks = vec![self.decks[0].top(0); self.decks.len()];
+        for &(player, card) in drawn {
+            decks[player] = self.decks[player].top(card);
+        }
+        Game { decks }
+    }
+
+    // the player who drew the highest card
+    fn highest(drawn: &[(usize, usize)]) -> usize {
+        drawn.iter().max_by_key(|&&(_, card)| card).unwrap().0
+    }
 }
 
-struct RecursiveGame {
-    game: Game,
-    previous_states: Vec<(Vec<usize>, Vec<usize>)>,
+/// When a round is decided by playing a sub-game, rather than by the highest card.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SubGames {
+    /// Never, as in part 1.
+    Never,
+    /// Whenever every player still in has at least as many cards left as the card they
+    /// drew, as in part 2.
+    EnoughCards,
 }
 
-impl RecursiveGame {
-    fn new(game: Game) -> RecursiveGame {
-        RecursiveGame {
-            game,
-            previous_states: vec![],
+impl SubGames {
+    pub fn from_name(name: &str) -> Option<SubGames> {
+        match name {
+            "never" => Some(SubGames::Never),
+            "enough-cards" => Some(SubGames::EnoughCards),
+            _ => None,
         }
     }
+}
 
-    fn play_round(&mut self) -> Option<GameWinner> {
-        let current_state = (
-            self.game.player1.content.to_vec(),
-            self.game.player2.content.to_vec(),
-        );
-        if self.previous_states.contains(&current_state) {
-            return Some(GameWinner::Player1);
+/// What happens when the decks come round to how they were at the start of an earlier
+/// round of the same game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Loops {
+    /// Play carries on, as in part 1. A game which never ends is only stopped by
+    /// `max_rounds`, so with this rule it's [`DEFAULT_MAX_ROUNDS`] unless given.
+    Ignore,
+    /// The first player still in wins the game, as in part 2.
+    FirstPlayerWins,
+    /// The player with the most cards wins the game, or the first of them on a tie.
+    MostCardsWins,
+}
+
+impl Loops {
+    pub fn from_name(name: &str) -> Option<Loops> {
+        match name {
+            "ignore" => Some(Loops::Ignore),
+            "first-player" => Some(Loops::FirstPlayerWins),
+            "most-cards" => Some(Loops::MostCardsWins),
+            _ => None,
         }
-        self.previous_states.push((
-            self.game.player1.content.to_vec(),
-            self.game.player2.content.to_vec(),
-        ));
+    }
+}
 
-        let player1_top_card = self.game.player1.content[0];
-        let player2_top_card = self.game.player2.content[0];
-
-        let turn_winner;
-        let mut game_winner = None;
-
-        if self.game.player1.content.len() > player1_top_card
-            && self.game.player2.content.len() > player2_top_card
-        {
-            let player1_subdeck = self.game.player1.content[1..(player1_top_card + 1)].to_vec();
-            let player2_subdeck = self.game.player2.content[1..(player2_top_card + 1)].to_vec();
-
-            let mut subgame = RecursiveGame::new(Game {
-                player1: Deck {
-                    content: player1_subdeck.to_vec(),
-                },
-                player2: Deck {
-                    content: player2_subdeck.to_vec(),
-                },
-            });
+/// How many rounds a game can go on for when repeats are ignored and no other limit is
+/// given. The puzzle's own part 1 games take a few hundred.
+pub const DEFAULT_MAX_ROUNDS: usize = 100_000;
 
-            turn_winner = subgame.play_game();
-        } else {
-            turn_winner = if player1_top_card > player2_top_card {
-                GameWinner::Player1
-            } else {
-                GameWinner::Player2
-            };
+/// The rules a game is played by, which the puzzle's two parts are just two settings of.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rules {
+    pub sub_games: SubGames,
+    /// How many games deep sub-games can go, after which rounds go to the highest card.
+    pub max_depth: Option<usize>,
+    pub loops: Loops,
+    /// How many rounds any one game can go on for before it's given up on.
+    pub max_rounds: Option<usize>,
+}
+
+impl Rules {
+    pub fn part_1() -> Rules {
+        Rules {
+            sub_games: SubGames::Never,
+            max_depth: None,
+            loops: Loops::Ignore,
+            max_rounds: None,
         }
+    }
 
-        self.game.player1.content = self.game.player1.content[1..].to_vec();
-        self.game.player2.content = self.game.player2.content[1..].to_vec();
+    pub fn part_2() -> Rules {
+        Rules {
+            sub_games: SubGames::EnoughCards,
+            max_depth: None,
+            loops: Loops::FirstPlayerWins,
+            max_rounds: None,
+        }
+    }
 
-        match turn_winner {
-            GameWinner::Player1 => {
-                self.game.player1.content.push(player1_top_card);
-                self.game.player1.content.push(player2_top_card);
-            }
-            GameWinner::Player2 => {
-                self.game.player2.content.push(player2_top_card);
-                self.game.player2.content.push(player1_top_card);
+    // nothing else stops a game going round in circles when repeats are ignored
+    fn round_limit(&self) -> Option<usize> {
+        match (self.max_rounds, self.loops) {
+            (None, Loops::Ignore) => Some(DEFAULT_MAX_ROUNDS),
+            (limit, _) => limit,
+        }
+    }
+
+    fn plays_sub_game(&self, game: &Game, drawn: &[(usize, usize)], depth: usize) -> bool {
+        match self.sub_games {
+            SubGames::Never => false,
+            SubGames::EnoughCards => {
+                self.max_depth.is_none_or(|max| depth < max)
+                    && drawn
+                        .iter()
+                        .all(|&(player, card)| game.decks[player].content.len() >= card)
             }
         }
+    }
 
-        if self.game.player1.content.len() == 0 {
-            game_winner = Some(GameWinner::Player2);
-        } else if self.game.player2.content.len() == 0 {
-            game_winner = Some(GameWinner::Player1);
+    // who wins a game once its decks have come round again, if anyone
+    fn loop_winner(&self, game: &Game) -> Option<usize> {
+        match self.loops {
+            Loops::Ignore => None,
+            Loops::FirstPlayerWins => game.players_in().next(),
+            Loops::MostCardsWins => game
+                .players_in()
+                .rev()
+                .max_by_key(|&player| game.decks[player].content.len()),
         }
+    }
+}
+
+/// Something which happened while playing, in the order it happened. Games are numbered
+/// from 1 in the order they started, and `depth` is how many games down from the first
+/// one a game was started.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    GameStarted {
+        game: usize,
+        depth: usize,
+    },
+    /// A round began with these decks, and each player still in drew the top card of theirs.
+    RoundStarted {
+        game: usize,
+        round: usize,
+        decks: Vec<Vec<usize>>,
+    },
+    RoundWon {
+        game: usize,
+        round: usize,
+        winner: usize,
+    },
+    /// The decks were the same as at the start of an earlier round of the game.
+    Repeated {
+        game: usize,
+        round: usize,
+    },
+    GameWon {
+        game: usize,
+        depth: usize,
+        winner: usize,
+        rounds: usize,
+    },
+}
+
+/// Everything which happened in a game and its sub-games.
+#[derive(Debug, Default)]
+pub struct GameLog {
+    pub events: Vec<Event>,
+}
+
+impl GameLog {
+    /// The game written out round by round, as the puzzle shows it.
+    pub fn render_text(&self) -> String {
+        let cards = |deck: &[usize]| {
+            deck.iter()
+                .map(|card| card.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        // the games still being played, innermost last
+        let mut games = Vec::new();
+        let mut text = String::new();
+        for event in self.events.iter() {
+            let line = match event {
+                Event::GameStarted { game, .. } => {
+                    let sub_game = if games.is_empty() {
+                        ""
+                    } else {
+                        "Playing a sub-game to determine the winner...\n\n"
+                    };
+                    games.push(game);
+                    format!("{}=== Game {} ===\n", sub_game, game)
+                }
+                Event::RoundStarted { game, round, decks } => {
+                    let mut lines = format!("\n-- Round {} (Game {}) --\n", round, game);
+                    for (player, deck) in decks.iter().enumerate() {
+                        lines.push_str(&format!("Player {}'s deck: {}\n", player + 1, cards(deck)));
+                    }
+                    for (player, deck) in decks.iter().enumerate() {
+                        if let Some(card) = deck.first() {
+                            lines.push_str(&format!("Player {} plays: {}\n", player + 1, card));
+                        }
+                    }
+                    lines
+                }
+                Event::RoundWon {
+                    game,
+                    round,
+                    winner,
+                } => format!(
+                    "Player {} wins round {} of game {}!\n",
+                    winner + 1,
+                    round,
+                    game
+                ),
+                Event::Repeated { game, round } => format!(
+                    "\n-- Round {} (Game {}) --\nThe decks are the same as in an earlier round.\n",
+                    round, game
+                ),
+                Event::GameWon { game, winner, .. } => {
+                    games.pop();
+                    let back = match games.last() {
+                        Some(parent) => format!("\n...anyway, back to game {}.\n", parent),
+                        None => String::new(),
+                    };
+                    format!(
+                        "The winner of game {} is player {}!\n{}",
+                        game,
+                        winner + 1,
+                        back
+                    )
+                }
+            };
+            text.push_str(&line);
+        }
+        text
+    }
 
-        game_winner
+    /// A JSON array with an object for each event, giving its kind and the details above.
+    pub fn render_json(&self) -> String {
+        let deck = |deck: &[usize]| {
+            let cards: Vec<String> = deck.iter().map(|card| card.to_string()).collect();
+            format!("[{}]", cards.join(", "))
+        };
+        let events: Vec<String> = self
+            .events
+            .iter()
+            .map(|event| match event {
+                Event::GameStarted { game, depth } => format!(
+                    "  {{\"event\": \"game_started\", \"game\": {}, \"depth\": {}}}",
+                    game, depth
+                ),
+                Event::RoundStarted { game, round, decks } => {
+                    let decks: Vec<String> = decks.iter().map(|d| deck(d)).collect();
+                    format!(
+                        "  {{\"event\": \"round_started\", \"game\": {}, \"round\": {}, \
+                         \"decks\": [{}]}}",
+                        game,
+                        round,
+                        decks.join(", ")
+                    )
+                }
+                Event::RoundWon {
+                    game,
+                    round,
+                    winner,
+                } => format!(
+                    "  {{\"event\": \"round_won\", \"game\": {}, \"round\": {}, \"winner\": {}}}",
+                    game,
+                    round,
+                    winner + 1
+                ),
+                Event::Repeated { game, round } => format!(
+                    "  {{\"event\": \"repeated\", \"game\": {}, \"round\": {}}}",
+                    game, round
+                ),
+                Event::GameWon {
+                    game,
+                    depth,
+                    winner,
+                    rounds,
+                } => format!(
+                    "  {{\"event\": \"game_won\", \"game\": {}, \"depth\": {}, \"winner\": {}, \
+                     \"rounds\": {}}}",
+                    game,
+                    depth,
+                    winner + 1,
+                    rounds
+                ),
+            })
+            .collect();
+        if events.is_empty() {
+            String::from("[]")
+        } else {
+            format!("[\n{}\n]", events.join(",\n"))
+        }
     }
+}
 
-    fn play_game(&mut self) -> GameWinner {
-        let mut res = self.play_round();
-        while let None = res {
-            res = self.play_round();
+/// How a game log is written out: as the puzzle shows games, or as JSON.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl LogFormat {
+    pub fn from_name(name: &str) -> Option<LogFormat> {
+        match name {
+            "text" => Some(LogFormat::Text),
+            "json" => Some(LogFormat::Json),
+            _ => None,
         }
-        res.unwrap()
     }
 }
 
-fn read_file() -> Game {
-    let mut file = File::open("./input/input22.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    let all_lines: Vec<&str> = contents.lines().collect();
+/// How much play it took to settle a game, counting its sub-games too.
+#[derive(Debug, Default, PartialEq)]
+pub struct Statistics {
+    pub rounds: usize,
+    pub sub_games: usize,
+    /// How many games down the deepest sub-game was, which is 0 if there were none.
+    pub max_depth: usize,
+    /// How many times the decks came round to how they were earlier in a game.
+    pub repeats: usize,
+}
 
-    let p1deck = Deck {
-        content: all_lines[1..26]
-            .iter()
-            .map(|n| n.parse().unwrap())
-            .collect(),
-    };
+impl Statistics {
+    pub fn render_json(&self) -> String {
+        format!(
+            "{{\"rounds\": {}, \"sub_games\": {}, \"max_depth\": {}, \"repeats\": {}}}",
+            self.rounds, self.sub_games, self.max_depth, self.repeats
+        )
+    }
+}
 
-    let p2deck = Deck {
-        content: all_lines[28..53]
-            .iter()
-            .map(|n| n.parse().unwrap())
-            .collect(),
-    };
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "rounds played     {}", self.rounds)?;
+        writeln!(f, "sub-games         {}", self.sub_games)?;
+        writeln!(f, "deepest sub-game  {}", self.max_depth)?;
+        write!(f, "repeated decks    {}", self.repeats)
+    }
+}
+
+/// How a game went: who won it, their score, and how much play that took, along with the
+/// log of everything that happened if one was kept.
+pub struct Outcome {
+    pub winner: usize,
+    pub score: usize,
+    pub statistics: Statistics,
+    pub log: Option<GameLog>,
+}
 
-    Game {
-        player1: p1deck,
-        player2: p2deck,
+// plays games by a set of rules, keeping count of how many games there have been, to
+// number them, and the log, if one is being kept. Events are only made when they'll be
+// kept, as copying the decks for each round would slow down solving
+struct Simulator {
+    rules: Rules,
+    games: usize,
+    statistics: Statistics,
+    log: Option<GameLog>,
+}
+
+impl Simulator {
+    fn record(&mut self, event: impl FnOnce() -> Event) {
+        if let Some(log) = &mut self.log {
+            log.events.push(event());
+        }
+    }
+
+    fn play_game(&mut self, game: &mut Game, number: usize, depth: usize) -> Result<usize> {
+        self.statistics.max_depth = self.statistics.max_depth.max(depth);
+        self.record(|| Event::GameStarted {
+            game: number,
+            depth,
+        });
+        let mut previous_states = HashSet::new();
+        let mut drawn = Vec::with_capacity(game.decks.len());
+        let round_limit = self.rules.round_limit();
+        let mut rounds = 0;
+        let winner = loop {
+            if let Some(winner) = game.winner() {
+                break winner;
+            }
+            if self.rules.loops != Loops::Ignore && !previous_states.insert(game.state_key()) {
+                self.record(|| Event::Repeated {
+                    game: number,
+                    round: rounds + 1,
+                });
+                self.statistics.repeats += 1;
+                if let Some(winner) = self.rules.loop_winner(game) {
+                    break winner;
+                }
+            }
+            if round_limit == Some(rounds) {
+                return Err(Error::NoSolution(format!(
+                    "game {} was still going after {} rounds",
+                    number, rounds
+                )));
+            }
+
+            rounds += 1;
+            self.statistics.rounds += 1;
+            self.record(|| Event::RoundStarted {
+                game: number,
+                round: rounds,
+                decks: game.decks(),
+            });
+            game.draw(&mut drawn);
+            let round_winner = if self.rules.plays_sub_game(game, &drawn, depth) {
+                self.games += 1;
+                self.statistics.sub_games += 1;
+                let number = self.games;
+                self.play_game(&mut game.sub_game(&drawn), number, depth + 1)?
+            } else {
+                Game::highest(&drawn)
+            };
+            game.take_cards(&mut drawn, round_winner);
+            self.record(|| Event::RoundWon {
+                game: number,
+                round: rounds,
+                winner: round_winner,
+            });
+        };
+        self.record(|| Event::GameWon {
+            game: number,
+            depth,
+            winner,
+            rounds,
+        });
+        Ok(winner)
     }
 }
 
-fn solve_part_1(mut hands: Game) -> usize {
-    let mut res = hands.winner();
-    while let None = res {
-        hands.play_round();
-        res = hands.winner();
+/// Plays the game by the given rules, keeping a log of every round of it if `logged`.
+pub fn simulate(hands: &Game, rules: Rules, logged: bool) -> Result<Outcome> {
+    let mut simulator = Simulator {
+        rules,
+        games: 1,
+        statistics: Statistics::default(),
+        log: if logged {
+            Some(GameLog::default())
+        } else {
+            None
+        },
+    };
+    let mut game = hands.clone();
+    let winner = simulator.play_game(&mut game, 1, 0)?;
+    Ok(Outcome {
+        winner,
+        score: game.decks[winner].deck_score(),
+        statistics: simulator.statistics,
+        log: simulator.log,
+    })
+}
+
+pub fn parse_file(contents: &str) -> Result<Game> {
+    let decks = parse_groups(contents, parse_deck)?;
+    if decks.len() < 2 {
+        return Err(Error::parse(
+            contents,
+            contents,
+            "expected at least two players' decks",
+        ));
+    }
+
+    // with a repeated card a round could be tied, which the rules don't cover
+    let mut all_cards: Vec<usize> = decks
+        .iter()
+        .flat_map(|deck| deck.content.iter())
+        .copied()
+        .collect();
+    all_cards.sort_unstable();
+    if let Some(pair) = all_cards.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(Error::Unsupported(format!(
+            "card {} appears more than once",
+            pair[0]
+        )));
     }
-    hands.winner_score()
+
+    Ok(Game { decks })
 }
 
-pub fn part_1() -> usize {
-    let hands = read_file();
-    solve_part_1(hands)
+fn parse_deck(s: &str) -> Result<Deck> {
+    let (header, cards) = s.split_once('\n').unwrap_or((s, ""));
+    if !(header.starts_with("Player ") && header.ends_with(':')) {
+        return Err(Error::parse(
+            header,
+            header,
+            "expected a header like 'Player 1:'",
+        ));
+    }
+    let content: Vec<usize> =
+        parse_lines(cards, |n| parse_number(n, n)).map_err(|e| e.offset_lines(1))?;
+    if content.is_empty() {
+        return Err(Error::parse(
+            header,
+            header,
+            "each player needs at least one card",
+        ));
+    }
+    Ok(Deck {
+        content: content.into(),
+    })
 }
 
-fn solve_part_2(hands: Game) -> usize {
-    let mut game = RecursiveGame::new(hands);
-    let winner = game.play_game();
-    match winner {
-        GameWinner::Player1 => game.game.player1.deck_score(),
-        GameWinner::Player2 => game.game.player2.deck_score(),
+impl Solution for Day22 {
+    type Input = Game;
+
+    fn parse(&self, input: &str) -> Result<Game> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, hands: &Game) -> Result<Answer> {
+        simulate(hands, Rules::part_1(), false).map(|outcome| outcome.score.into())
+    }
+
+    fn part_2(&self, hands: &Game) -> Result<Answer> {
+        simulate(hands, Rules::part_2(), false).map(|outcome| outcome.score.into())
     }
 }
 
-pub fn part_2() -> usize {
-    let hands = read_file();
-    solve_part_2(hands)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    const EXAMPLE: &str = "Player 1:\n9\n2\n6\n3\n1\n\nPlayer 2:\n5\n8\n4\n7\n10\n";
+
+    // without the rule against repeating, this game would go on forever
+    const LOOPING_EXAMPLE: &str = "Player 1:\n43\n19\n\nPlayer 2:\n2\n29\n14\n";
+
+    fn play(input: &str, rules: Rules) -> Result<Outcome> {
+        simulate(&parse_file(input).unwrap(), rules, false)
+    }
+
+    fn replay(input: &str, rules: Rules) -> (Outcome, GameLog) {
+        let mut outcome = simulate(&parse_file(input).unwrap(), rules, true).unwrap();
+        let log = outcome.log.take().unwrap();
+        (outcome, log)
+    }
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day22.run(1, EXAMPLE).unwrap().to_string(), "306");
+    }
+
+    #[test]
+    fn part_2_example() {
+        assert_eq!(Day22.run(2, EXAMPLE).unwrap().to_string(), "291");
+    }
+
+    #[test]
+    fn repeated_states_end_the_game() {
+        assert!(Day22.run(2, LOOPING_EXAMPLE).is_ok());
+        let (outcome, log) = replay(LOOPING_EXAMPLE, Rules::part_2());
+        assert!(log.events.contains(&Event::Repeated { game: 1, round: 7 }));
+        assert_eq!(outcome.winner, 0);
+    }
+
+    #[test]
+    fn replaying_games() {
+        let (outcome, log) = replay(EXAMPLE, Rules::part_2());
+        assert_eq!((outcome.winner, outcome.score), (1, 291));
+        let text = log.render_text();
+        assert!(text.starts_with(
+            "=== Game 1 ===\n\n-- Round 1 (Game 1) --\nPlayer 1's deck: 9, 2, 6, 3, 1\n\
+             Player 2's deck: 5, 8, 4, 7, 10\nPlayer 1 plays: 9\nPlayer 2 plays: 5\n\
+             Player 1 wins round 1 of game 1!\n"
+        ));
+        assert!(text.contains(
+            "Player 2 plays: 3\nPlaying a sub-game to determine the winner...\n\n\
+             === Game 2 ===\n"
+        ));
+        assert!(text.contains(
+            "The winner of game 2 is player 2!\n\n...anyway, back to game 1.\n\
+             Player 2 wins round 9 of game 1!\n"
+        ));
+        assert!(text.ends_with("The winner of game 1 is player 2!\n"));
+        assert!(log.events.contains(&Event::GameWon {
+            game: 1,
+            depth: 0,
+            winner: 1,
+            rounds: 17,
+        }));
+    }
+
+    #[test]
+    fn exporting_games() {
+        let (_, log) = replay("Player 1:\n2\n\nPlayer 2:\n1\n", Rules::part_1());
+        assert_eq!(
+            log.render_json(),
+            "[\n  {\"event\": \"game_started\", \"game\": 1, \"depth\": 0},\n  \
+             {\"event\": \"round_started\", \"game\": 1, \"round\": 1, \"decks\": [[2], [1]]},\n  \
+             {\"event\": \"round_won\", \"game\": 1, \"round\": 1, \"winner\": 1},\n  \
+             {\"event\": \"game_won\", \"game\": 1, \"depth\": 0, \"winner\": 1, \"rounds\": 1}\n]"
+        );
+    }
+
+    #[test]
+    fn statistics() {
+        let outcome = play(EXAMPLE, Rules::part_2()).unwrap();
+        assert_eq!(
+            outcome.statistics,
+            Statistics {
+                rounds: 29,
+                sub_games: 4,
+                max_depth: 2,
+                repeats: 0,
+            }
+        );
+        let outcome = play(EXAMPLE, Rules::part_1()).unwrap();
+        assert_eq!(outcome.statistics.rounds, 29);
+        assert_eq!(outcome.statistics.sub_games, 0);
+    }
+
+    #[test]
+    fn more_players() {
+        // the winner of the first round takes their own card, then the others from highest
+        // to lowest, and player 3 is out after it
+        let input = "Player 1:\n3\n\nPlayer 2:\n1\n5\n\nPlayer 3:\n2\n";
+        let outcome = play(input, Rules::part_1()).unwrap();
+        assert_eq!((outcome.winner, outcome.score), (1, 33));
+        assert_eq!(outcome.statistics.rounds, 4);
+        let outcome = play(input, Rules::part_2()).unwrap();
+        assert_eq!(outcome.winner, 1);
+    }
+
+    #[test]
+    fn rule_variants() {
+        // with sub-games not allowed to go any deeper than the first game, part 2 plays out
+        // just like part 1
+        let shallow = Rules {
+            max_depth: Some(0),
+            ..Rules::part_2()
+        };
+        let outcome = play(EXAMPLE, shallow).unwrap();
+        assert_eq!((outcome.score, outcome.statistics.sub_games), (306, 0));
+
+        let endless = Rules {
+            max_rounds: Some(1000),
+            ..Rules::part_1()
+        };
+        assert_eq!(
+            play(LOOPING_EXAMPLE, endless).err().unwrap().to_string(),
+            "no solution exists: game 1 was still going after 1000 rounds"
+        );
+        let most_cards = Rules {
+            loops: Loops::MostCardsWins,
+            ..Rules::part_1()
+        };
+        let outcome = play(LOOPING_EXAMPLE, most_cards).unwrap();
+        assert_eq!((outcome.winner, outcome.statistics.repeats), (1, 1));
+
+        // ignoring repeats with no limit given still doesn't play forever
+        assert_eq!(
+            play(LOOPING_EXAMPLE, Rules::part_1())
+                .err()
+                .unwrap()
+                .to_string(),
+            "no solution exists: game 1 was still going after 100000 rounds"
+        );
+    }
+
+    #[test]
+    fn rejects_repeated_cards() {
+        assert!(matches!(
+            Day22.parse("Player 1:\n1\n2\n\nPlayer 2:\n2\n3\n"),
+            Err(Error::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn needs_two_players() {
+        assert!(matches!(
+            Day22.parse("Player 1:\n1\n2\n"),
+            Err(Error::Parse { .. })
+        ));
+    }
 }
@@ -1,108 +1,188 @@
+use super::{Answer, Solution};
+use crate::error::{Error, Result};
+use std::iter;
+
+pub struct Day23;
+
+/*
+Each move only changes which cup follows which for a handful of cups, so rather than keep
+the circle in order (which made every move cost a scan of the whole circle, far too slow for
+a million cups), we keep a "successor array": for each label, the label of the cup clockwise
+of it. Picking up three cups and putting them down after the destination is then a few
+updates, and the destination never needs searching for, as each label is its own index.
+*/
 struct CupCycle {
-    cups: Vec<usize>,
-    current_index: usize,
+    // next[label] is the label of the cup clockwise of it. Labels start from 1, so index 0
+    // is unused
+    next: Vec<usize>,
+    current: usize,
 }
 
 impl CupCycle {
-    fn new(start: &[usize]) -> CupCycle {
+    // the given cups in order, then every other label up to `total`, counting up. The
+    // given cups must be labelled 1 up to however many there are
+    fn new(start: &[usize], total: usize) -> CupCycle {
+        let labels: Vec<usize> = start
+            .iter()
+            .copied()
+            .chain(start.len() + 1..=total)
+            .collect();
+        let mut next = vec![0; labels.len() + 1];
+        for (i, &label) in labels.iter().enumerate() {
+            next[label] = labels[(i + 1) % labels.len()];
+        }
         CupCycle {
-            cups: start.to_vec(),
-            current_index: 0,
+            next,
+            current: labels[0],
         }
     }
 
-    fn read_from_position(&self, i: usize) -> usize {
-        let number_of_cups = self.cups.len();
-        self.cups[i % number_of_cups]
+    fn max_label(&self) -> usize {
+        self.next.len() - 1
     }
 
-    fn single_cycle(&mut self) -> () {
-        let number_of_cups = self.cups.len();
-
-        let next_three = [
-            self.read_from_position(self.current_index + 1),
-            self.read_from_position(self.current_index + 2),
-            self.read_from_position(self.current_index + 3),
-        ];
-        let current = self.read_from_position(self.current_index);
-
-        let mut target = current;
-        let mut found_target = false;
-        while !found_target {
-            target -= 1;
-            if target == 0 {
-                target = 9;
-            }
-            found_target = !next_three.contains(&target);
-        }
+    fn single_cycle(&mut self) {
+        let first = self.next[self.current];
+        let second = self.next[first];
+        let third = self.next[second];
+        self.next[self.current] = self.next[third];
 
-        for i in 0..3 {
-            let position_to_remove = self.cups.iter().position(|&n| n == next_three[i]).unwrap();
-            self.cups.remove(position_to_remove);
+        let mut destination = self.current;
+        loop {
+            destination = if destination == 1 {
+                self.max_label()
+            } else {
+                destination - 1
+            };
+            if destination != first && destination != second && destination != third {
+                break;
+            }
         }
 
-        let insert_position = self.cups.iter().position(|&n| n == target).unwrap();
-        self.cups.insert(insert_position + 1, next_three[0]);
-        self.cups.insert(insert_position + 2, next_three[1]);
-        self.cups.insert(insert_position + 3, next_three[2]);
-
-        self.current_index =
-            (self.cups.iter().position(|&n| n == current).unwrap() + 1) % number_of_cups;
+        self.next[third] = self.next[destination];
+        self.next[destination] = first;
+        self.current = self.next[self.current];
     }
 
-    fn cycle(&mut self, n: usize) -> () {
+    fn cycle(&mut self, n: usize) {
         for _ in 0..n {
             self.single_cycle();
         }
     }
 
+    // the labels going clockwise from cup 1, not including it
+    fn after_one(&self) -> impl Iterator<Item = usize> + '_ {
+        iter::successors(Some(self.next[1]), move |&label| Some(self.next[label]))
+            .take_while(|&label| label != 1)
+    }
+
     fn output(&self) -> String {
-        let mut result = String::new();
-        let index_of_one = self.cups.iter().position(|&n| n == 1).unwrap();
-        let number_of_cups = self.cups.len();
-        for i in index_of_one + 1..number_of_cups {
-            result.push_str(&self.cups[i].to_string());
-        }
-        for i in 0..index_of_one {
-            result.push_str(&self.cups[i].to_string());
-        }
-        result
+        self.after_one().map(|label| label.to_string()).collect()
     }
 }
 
+const PART_1_MOVES: usize = 100;
+const PART_2_CUPS: usize = 1_000_000;
+const PART_2_MOVES: usize = 10_000_000;
+
 fn solve_part_1(cups: &mut CupCycle) -> String {
-    cups.cycle(100);
+    cups.cycle(PART_1_MOVES);
     cups.output()
 }
 
-pub fn part_1() -> String {
-    let mut cups = CupCycle::new(&[2, 1, 9, 7, 4, 8, 3, 6, 5]);
-    solve_part_1(&mut cups)
+fn solve_part_2(cups: &mut CupCycle) -> usize {
+    cups.cycle(PART_2_MOVES);
+    cups.after_one().take(2).product()
+}
+
+fn parse_file(contents: &str) -> Result<Vec<usize>> {
+    let line = contents.trim();
+    let cups = line
+        .char_indices()
+        .map(|(i, c)| match c.to_digit(10) {
+            Some(d) if d > 0 => Ok(d as usize),
+            _ => Err(Error::parse(
+                line,
+                &line[i..i + c.len_utf8()],
+                "expected a cup labelled 1 to 9",
+            )),
+        })
+        .collect::<Result<Vec<usize>>>()?;
+    // the destination cup wraps round from 1 to the highest label, so none can be missing.
+    // With fewer than five cups, there'd be nowhere to put the three picked up
+    let mut sorted = cups.clone();
+    sorted.sort_unstable();
+    if sorted.len() < 5 || sorted != (1..=cups.len()).collect::<Vec<usize>>() {
+        return Err(Error::Unsupported(String::from(
+            "the cups must be labelled from 1 up, each used once, with at least 5 of them",
+        )));
+    }
+    Ok(cups)
 }
 
-#[allow(dead_code)]
-fn solve_part_2(cups: &mut CupCycle) -> usize {
-    cups.cycle(10000000);
-    let one_index = cups.cups.iter().position(|&n| n == 1).unwrap();
-    cups.read_from_position(one_index + 1) * cups.read_from_position(one_index + 2)
+impl Solution for Day23 {
+    type Input = Vec<usize>;
+
+    fn parse(&self, input: &str) -> Result<Vec<usize>> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, start: &Vec<usize>) -> Result<Answer> {
+        let mut cups = CupCycle::new(start, start.len());
+        Ok(solve_part_1(&mut cups).into())
+    }
+
+    fn part_2(&self, start: &Vec<usize>) -> Result<Answer> {
+        let mut cups = CupCycle::new(start, PART_2_CUPS);
+        Ok(solve_part_2(&mut cups).into())
+    }
 }
 
-// takes several seconds per thousand cycles, obviously impractical to run for 10 million
-// (which would take many hours). Need a different approach!
-#[allow(dead_code)]
-pub fn part_2() -> usize {
-    // note: an additinal change would be needed, when checking for the "destination cup",
-    // now need to count down from a million, rather than just 9!
-    let mut starting_cups: Vec<usize> = (1..100001).collect();
-    starting_cups[0] = 2;
-    starting_cups[1] = 1;
-    starting_cups[2] = 9;
-    starting_cups[3] = 7;
-    starting_cups[4] = 4;
-    starting_cups[5] = 8;
-    starting_cups[6] = 3;
-    starting_cups[7] = 6;
-    starting_cups[8] = 5;
-    let mut cups = CupCycle::new(&starting_cups);
-    solve_part_2(&mut cups)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    const EXAMPLE: &str = "389125467";
+
+    #[test]
+    fn ten_moves() {
+        let mut cups = CupCycle::new(&Day23.parse(EXAMPLE).unwrap(), 9);
+        cups.cycle(10);
+        assert_eq!(cups.output(), "92658374");
+    }
+
+    #[test]
+    fn destination_wraps_to_the_highest_label() {
+        // cup 1 is current, with 2, 3 and 4 picked up, so the destination wraps round to 12
+        let mut cups = CupCycle::new(&[1, 2, 3, 4, 5], 12);
+        cups.cycle(1);
+        assert_eq!(cups.current, 5);
+        assert_eq!(
+            cups.after_one().collect::<Vec<usize>>(),
+            vec![5, 6, 7, 8, 9, 10, 11, 12, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day23.run(1, EXAMPLE).unwrap().to_string(), "67384529");
+    }
+
+    #[test]
+    fn part_2_example() {
+        assert_eq!(Day23.run(2, EXAMPLE).unwrap().to_string(), "149245887792");
+    }
+
+    #[test]
+    fn rejects_missing_cups() {
+        assert!(matches!(
+            Day23.parse("38912546"),
+            Err(Error::Unsupported(_))
+        ));
+        assert!(matches!(
+            Day23.parse("3891254607"),
+            Err(Error::Parse { column: 9, .. })
+        ));
+    }
 }
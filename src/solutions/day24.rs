@@ -1,7 +1,10 @@
+use super::{Answer, Solution};
+use crate::automaton::{Automaton, Neighbourhood, Point, Rule, Sparse};
+use crate::error::{parse_lines, Error, Result};
 use nom::{branch::alt, bytes::complete::tag, multi::fold_many1, IResult};
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::prelude::*;
+use std::collections::HashSet;
+
+pub struct Day24;
 
 #[derive(Clone, PartialEq)]
 enum Direction {
@@ -16,104 +19,21 @@ enum Direction {
 // represents co-ordinates on a hex grid. The "x-axis" is a horizontal line,
 // while the "y-axis" runs NW to SE
 #[derive(Clone, Copy)]
-struct HexPosition {
+pub struct HexPosition {
     x: isize,
     y: isize,
 }
 
-struct Floor {
-    flipped: HashMap<(isize, isize), bool>,
-}
-
-impl Floor {
-    fn new() -> Floor {
-        Floor {
-            flipped: HashMap::new(),
-        }
-    }
-
-    fn toggle_position(&mut self, pos: HexPosition) -> () {
-        let already_flipped = self.flipped.get(&(pos.x, pos.y)).unwrap_or(&false);
-        let toggled = !already_flipped;
-        self.flipped.insert((pos.x, pos.y), toggled);
-    }
-
-    fn from_vector(v: Vec<HexPosition>) -> Floor {
-        let mut floor = Floor::new();
-        for hex in v {
-            floor.toggle_position(hex);
-        }
-        floor
-    }
-
-    fn count_flipped(&self) -> usize {
-        self.flipped.values().filter(|&&flipped| flipped).count()
-    }
-
-    fn flipped_neighbours(&self, pos: HexPosition) -> usize {
-        let HexPosition { x, y } = pos;
-        let neighbours = [
-            (x - 1, y),
-            (x, y - 1),
-            (x + 1, y - 1),
-            (x + 1, y),
-            (x, y + 1),
-            (x - 1, y + 1),
-        ];
-        neighbours
-            .iter()
-            .filter(|&(xpos, ypos)| *self.flipped.get(&(*xpos, *ypos)).unwrap_or(&false))
-            .count()
-    }
-
-    fn evolve_once(&mut self) -> () {
-        let mut to_flip = vec![];
-
-        // the following tiles should be flipped
-        // 1) any currently-flipped tiles which have any number of flipped neighbours other than 1 or 2
-        // 2) any tile that is currently not flipped, which has exactly 2 flipped neighbours
-        // 3) as in 2), but remember to take into account any tiles which are not yet in the hashmap!
-        // So first we build up a vector of all keys, making sure to include all neighbouring hexes of
-        // all those we have
-        let all_keys: Vec<(isize, isize)> =
-            self.flipped.keys().map(|pair| pair.to_owned()).collect();
-        let mut new_keys = all_keys.to_vec();
-        for &(x, y) in all_keys.iter() {
-            let neighbours = [
-                (x - 1, y),
-                (x, y - 1),
-                (x + 1, y - 1),
-                (x + 1, y),
-                (x, y + 1),
-                (x - 1, y + 1),
-            ];
-            for neighbour in &neighbours {
-                if !new_keys.contains(&neighbour) {
-                    new_keys.push(*neighbour);
-                }
-            }
-        }
-
-        for key in new_keys {
-            let is_flipped = *self.flipped.get(&key).unwrap_or(&false);
-            let flipped_neighbours = self.flipped_neighbours(HexPosition { x: key.0, y: key.1 });
-            if (is_flipped && flipped_neighbours != 1 && flipped_neighbours != 2)
-                || (!is_flipped && flipped_neighbours == 2)
-            {
-                to_flip.push(key);
-            }
-        }
-
-        to_flip
-            .iter()
-            .for_each(|&pos| self.toggle_position(HexPosition { x: pos.0, y: pos.1 }));
-    }
-
-    fn evolve(&mut self, times: usize) -> () {
-        for _ in 0..times {
-            self.evolve_once();
+// the tiles which end up black side up, once every tile on the list has been flipped -
+// which is those listed an odd number of times
+fn black_tiles(hexes_flipped: &[HexPosition]) -> HashSet<Point<2>> {
+    let mut black = HashSet::new();
+    for pos in hexes_flipped {
+        if !black.insert([pos.x, pos.y]) {
+            black.remove(&[pos.x, pos.y]);
         }
     }
+    black
 }
 
 fn movement(pos: HexPosition, dir: Direction) -> HexPosition {
@@ -145,15 +65,11 @@ fn movement(pos: HexPosition, dir: Direction) -> HexPosition {
     }
 }
 
-fn read_file() -> Vec<HexPosition> {
-    let mut file = File::open("./input/input24.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-
-    contents.lines().map(parse_line).collect()
+fn parse_file(contents: &str) -> Result<Vec<HexPosition>> {
+    parse_lines(contents, parse_line)
 }
 
-fn parse_line(s: &str) -> HexPosition {
+fn parse_line(s: &str) -> Result<HexPosition> {
     let mut parser = fold_many1(
         alt((
             tag("e"),
@@ -175,36 +91,106 @@ fn parse_line(s: &str) -> HexPosition {
                 movement(current, Direction::West)
             } else if newdir == "nw" {
                 movement(current, Direction::NorthWest)
-            } else if newdir == "ne" {
-                movement(current, Direction::NorthEast)
             } else {
-                panic!("this can't happen!")
+                // the only tag left
+                movement(current, Direction::NorthEast)
             }
         },
     );
     let result: IResult<&str, HexPosition> = parser(s);
-    result.unwrap().1
+    match result {
+        Ok(("", position)) => Ok(position),
+        Ok((rest, _)) => Err(Error::parse(s, rest, "expected a direction")),
+        Err(_) => Err(Error::parse(s, s, "expected a direction")),
+    }
 }
 
-fn solve_part_1(hexes_flipped: Vec<HexPosition>) -> usize {
-    let floor = Floor::from_vector(hexes_flipped);
-    floor.count_flipped()
+fn solve_part_1(hexes_flipped: &[HexPosition]) -> usize {
+    black_tiles(hexes_flipped).len()
 }
 
-pub fn part_1() -> usize {
-    let hexes_flipped = read_file();
-    solve_part_1(hexes_flipped)
+// each day, a black tile with no black neighbours or more than 2 is flipped to white, and
+// a white tile with exactly 2 black neighbours is flipped to black
+fn solve_part_2(hexes_flipped: &[HexPosition]) -> usize {
+    let mut floor = Sparse::new(
+        black_tiles(hexes_flipped),
+        Neighbourhood::Hex,
+        Rule::new(&[2], &[1, 2]),
+    );
+    floor.run(100);
+    floor.count_alive()
 }
 
-// ran for around 10 minutes via cargo run - but after compilation in release mode,
-// went down to around 10 seconds!
-fn solve_part_2(hexes_flipped: Vec<HexPosition>) -> usize {
-    let mut floor = Floor::from_vector(hexes_flipped);
-    floor.evolve(100);
-    floor.count_flipped()
+impl Solution for Day24 {
+    type Input = Vec<HexPosition>;
+
+    fn parse(&self, input: &str) -> Result<Vec<HexPosition>> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, hexes_flipped: &Vec<HexPosition>) -> Result<Answer> {
+        Ok(solve_part_1(hexes_flipped).into())
+    }
+
+    fn part_2(&self, hexes_flipped: &Vec<HexPosition>) -> Result<Answer> {
+        Ok(solve_part_2(hexes_flipped).into())
+    }
 }
 
-pub fn part_2() -> usize {
-    let hexes_flipped = read_file();
-    solve_part_2(hexes_flipped)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    const EXAMPLE: &str = "\
+sesenwnenenewseeswwswswwnenewsewsw
+neeenesenwnwwswnenewnwwsewnenwseswesw
+seswneswswsenwwnwse
+nwnwneseeswswnenewneswwnewseswneseene
+swweswneswnenwsewnwneneseenw
+eesenwseswswnenwswnwnwsewwnwsene
+sewnenenenesenwsewnenwwwse
+wenwwweseeeweswwwnwwe
+wsweesenenewnwwnwsenewsenwwsesesenwne
+neeswseenwwswnwswswnw
+nenwswwsewswnenenewsenwsenwnesesenew
+enewnwewneswsewnwswenweswnenwsenwsw
+sweneswneswneneenwnewenewwneswswnese
+swwesenesewenwneswnwwneseswwne
+enesenwswwswneneswsenwnewswseenwsese
+wnwnesenesenenwwnenwsewesewsesesew
+nenewswnwewswnenesenwnesewesw
+eneswnwswnwsenenwnwnwwseeswneewsenese
+neswnwewnwnwseenwseesewsenwsweewe
+wseweeenwnesenwwwswnew
+";
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day24.run(1, EXAMPLE).unwrap().to_string(), "10");
+    }
+
+    #[test]
+    fn part_2_example() {
+        assert_eq!(Day24.run(2, EXAMPLE).unwrap().to_string(), "2208");
+    }
+
+    #[test]
+    fn paths_that_loop_back() {
+        let positions = Day24.parse("nwwswee\n").unwrap();
+        assert_eq!(positions[0].x, 0);
+        assert_eq!(positions[0].y, 0);
+    }
+
+    #[test]
+    fn rejects_unknown_directions() {
+        assert!(matches!(
+            Day24.parse("esew\nnwx\n"),
+            Err(Error::Parse {
+                line: 2,
+                column: 3,
+                ..
+            })
+        ));
+    }
 }
@@ -1,42 +1,111 @@
-use std::fs::File;
-use std::io::prelude::*;
+use super::{Answer, Solution};
+use crate::error::{parse_lines, parse_number, Error, Result};
+use crate::modular;
 
-fn read_file() -> (usize, usize) {
-    let mut file = File::open("./input/input25.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
+pub struct Day25;
 
-    let vals: Vec<usize> = contents.lines().map(|n| n.parse().unwrap()).collect();
+/// The modulus and subject number the puzzle's handshake uses.
+pub const MODULUS: u64 = 20201227;
+pub const SUBJECT: u64 = 7;
 
-    (vals[0], vals[1])
-}
-
-fn discrete_logarithm(target: usize, p: usize, base: usize) -> usize {
-    let mut power = 1;
-    let mut current = base;
-    loop {
-        if current == target {
-            return power;
+/// Reads the two public keys, which must be remainders modulo `modulus`.
+pub fn parse_keys(contents: &str, modulus: u64) -> Result<(u64, u64)> {
+    let vals: Vec<u64> = parse_lines(contents, |n| {
+        let key = parse_number(n, n)?;
+        // anything else isn't a power of the subject number, so would never be found
+        if key == 0 || key >= modulus {
+            return Err(Error::parse(
+                n,
+                n,
+                &format!(
+                    "public keys must be between 1 and {}",
+                    modulus.saturating_sub(1)
+                ),
+            ));
         }
-        power += 1;
-        current = (current * base) % p;
+        Ok(key)
+    })?;
+
+    match vals[..] {
+        [first, second] => Ok((first, second)),
+        _ => Err(Error::parse(
+            contents,
+            contents,
+            "expected exactly two public keys",
+        )),
     }
 }
 
-fn solve_part_1(nums: (usize, usize)) -> usize {
-    let base = 20201227;
-    let subject = 7;
-    let second_log = discrete_logarithm(nums.1, base, subject);
+/*
+Each public key is the subject number raised to a secret loop size, and the encryption key
+is either public key raised to the other's loop size. So finding one loop size - a discrete
+logarithm - is enough to work out the encryption key, whatever the subject and modulus.
+*/
+pub fn encryption_key(keys: (u64, u64), subject: u64, modulus: u64) -> Result<u64> {
+    let (card_key, door_key) = keys;
+    let door_loop_size = modular::discrete_log(subject, door_key, modulus)?.ok_or_else(|| {
+        Error::NoSolution(format!(
+            "{} isn't a power of the subject number {}",
+            door_key, subject
+        ))
+    })?;
+    Ok(modular::pow(card_key, door_loop_size, modulus))
+}
+
+fn solve_part_1(keys: &(u64, u64)) -> Result<u64> {
+    encryption_key(*keys, SUBJECT, MODULUS)
+}
+
+impl Solution for Day25 {
+    type Input = (u64, u64);
 
-    // use naive loop to calculate power
-    let mut res = 1;
-    for _ in 0..second_log {
-        res = (res * nums.0) % base;
+    fn parse(&self, input: &str) -> Result<(u64, u64)> {
+        parse_keys(input, MODULUS)
+    }
+
+    fn part_1(&self, keys: &(u64, u64)) -> Result<Answer> {
+        solve_part_1(keys).map(Answer::from)
     }
-    res
 }
 
-pub fn part_1() -> usize {
-    let nums = read_file();
-    solve_part_1(nums)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    #[test]
+    fn other_handshakes() {
+        // 2 has loop size 3 and 3 has loop size 4, both modulo 11 with subject 7
+        assert_eq!(encryption_key((2, 3), 7, 11).unwrap(), 5);
+        assert!(matches!(
+            encryption_key((2, 3), 2, 10),
+            Err(Error::Unsupported(_))
+        ));
+        assert!(matches!(
+            encryption_key((2, 3), 7, 0),
+            Err(Error::Unsupported(_))
+        ));
+        assert!(matches!(
+            encryption_key((2, 2), 3, 11),
+            Err(Error::NoSolution(_))
+        ));
+    }
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(
+            Day25.run(1, "5764801\n17807724\n").unwrap().to_string(),
+            "14897079"
+        );
+    }
+
+    #[test]
+    fn rejects_keys_out_of_range() {
+        assert!(matches!(
+            Day25.parse("5764801\n20201227\n"),
+            Err(Error::Parse { line: 2, .. })
+        ));
+        assert_eq!(parse_keys("2\n10\n", 11).unwrap(), (2, 10));
+        assert!(parse_keys("2\n11\n", 11).is_err());
+    }
 }
@@ -1,5 +1,8 @@
-use std::fs::File;
-use std::io::prelude::*;
+use super::{Answer, Solution};
+use crate::error::Result;
+use crate::grid::Grid;
+
+pub struct Day3;
 
 #[derive(PartialEq)]
 enum Ground {
@@ -7,21 +10,21 @@ enum Ground {
     Open,
 }
 
-struct Run {
-    content: Vec<Vec<Ground>>,
+pub struct Run {
+    content: Grid<Ground>,
 }
 
 impl Run {
+    // the pattern repeats to the right as far as needed
     fn ground_at(&self, row: usize, col: usize) -> &Ground {
-        let width = self.content[0].len();
-        &self.content[row][col % width]
+        self.content.get_wrapping(row, col)
     }
 
     fn slope_total(&self, right: usize, down: usize) -> usize {
         let mut row = 0;
         let mut col = 0;
         let mut tree_count = 0;
-        while row < self.content.len() {
+        while row < self.content.height() {
             let res = self.ground_at(row, col);
             if res == &Ground::Tree {
                 tree_count += 1;
@@ -33,45 +36,99 @@ impl Run {
     }
 }
 
-fn read_file() -> Run {
-    let mut file = File::open("./input/input3.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    Run {
-        content: contents.lines().map(|l| parse_line(l.to_owned())).collect(),
-    }
-}
-
-fn parse_line(l: String) -> Vec<Ground> {
-    l.chars().map(parse_char).collect()
+fn parse_file(contents: &str) -> Result<Run> {
+    let content = Grid::parse(contents, parse_char, "expected '.' or '#'")?;
+    Ok(Run { content })
 }
 
-fn parse_char(c: char) -> Ground {
+fn parse_char(c: char) -> Option<Ground> {
     match c {
-        '.' => Ground::Open,
-        '#' => Ground::Tree,
-        c => panic!(format!("couldn't read character '{}'", c)),
+        '.' => Some(Ground::Open),
+        '#' => Some(Ground::Tree),
+        _ => None,
     }
 }
 
-fn solve_part_1(r: Run) -> usize {
+fn solve_part_1(r: &Run) -> usize {
     r.slope_total(3, 1)
 }
 
-pub fn part_1() -> usize {
-    let run = read_file();
-    solve_part_1(run)
+fn solve_part_2(r: &Run) -> usize {
+    r.slope_total(1, 1)
+        * r.slope_total(3, 1)
+        * r.slope_total(5, 1)
+        * r.slope_total(7, 1)
+        * r.slope_total(1, 2)
 }
 
-fn solve_part_2(r: Run) -> usize {
-    r.slope_total(1, 1)
-    * r.slope_total(3, 1)
-    * r.slope_total(5, 1)
-    * r.slope_total(7, 1)
-    * r.slope_total(1, 2)
+impl Solution for Day3 {
+    type Input = Run;
+
+    fn parse(&self, input: &str) -> Result<Run> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, run: &Run) -> Result<Answer> {
+        Ok(solve_part_1(run).into())
+    }
+
+    fn part_2(&self, run: &Run) -> Result<Answer> {
+        Ok(solve_part_2(run).into())
+    }
 }
 
-pub fn part_2() -> usize {
-    let run = read_file();
-    solve_part_2(run)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::solutions::Runner;
+
+    const EXAMPLE: &str = "\
+..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#
+";
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day3.run(1, EXAMPLE).unwrap().to_string(), "7");
+    }
+
+    #[test]
+    fn part_2_example() {
+        assert_eq!(Day3.run(2, EXAMPLE).unwrap().to_string(), "336");
+    }
+
+    #[test]
+    fn rejects_unknown_squares() {
+        assert!(matches!(
+            Day3.parse("..#\n.X.\n"),
+            Err(Error::Parse {
+                line: 2,
+                column: 2,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_ragged_rows() {
+        assert!(matches!(
+            Day3.parse("..#\n..\n"),
+            Err(Error::Parse { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_empty_input() {
+        assert!(matches!(Day3.parse(""), Err(Error::Parse { .. })));
+    }
 }
@@ -1,8 +1,11 @@
-use std::fs::File;
-use std::io::prelude::*;
+use super::{Answer, Solution};
+use crate::error::{parse_groups, parse_number, Error, Result};
+
+pub struct Day4;
 
 #[allow(dead_code)]
-struct Passport {
+#[derive(Default)]
+pub struct Passport {
     byr: Option<u16>,
     iyr: Option<u16>,
     eyr: Option<u16>,
@@ -13,120 +16,97 @@ struct Passport {
     cid: Option<String>,
 }
 
-fn read_file() -> Vec<Passport> {
-    let mut file = File::open("./input/input4.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    contents.split("\r\n\r\n").map(parse_group).collect()
-}
-
-fn parse_group(g: &str) -> Passport {
-    let parts = g.split_whitespace().map(parse_part);
-    let mut byr = None;
-    let mut iyr = None;
-    let mut eyr = None;
-    let mut hgt = None;
-    let mut hcl = None;
-    let mut ecl = None;
-    let mut pid = None;
-    let mut cid = None;
-
-    for (name, val) in parts {
+impl Passport {
+    // sets one field from a "name:value" pair, found in the given line
+    fn set_field(&mut self, line: &str, part: &str) -> Result<()> {
+        let (name, val) = parse_part(line, part)?;
         match name {
-            "byr" => {
-                byr = Some(val.parse().unwrap());
-            }
-            "iyr" => {
-                iyr = Some(val.parse().unwrap());
-            }
-            "eyr" => {
-                eyr = Some(val.parse().unwrap());
-            }
-            "hgt" => {
-                hgt = Some(val.to_owned());
-            }
-            "hcl" => {
-                hcl = Some(val.to_owned());
-            }
-            "ecl" => {
-                ecl = Some(val.to_owned());
-            }
-            "pid" => {
-                pid = Some(val.to_owned());
-            }
-            "cid" => {
-                cid = Some(val.to_owned());
-            }
-            s => {
-                panic!(format!("unknown password part: {}", s))
-            }
+            "byr" => self.byr = Some(parse_number(line, val)?),
+            "iyr" => self.iyr = Some(parse_number(line, val)?),
+            "eyr" => self.eyr = Some(parse_number(line, val)?),
+            "hgt" => self.hgt = Some(val.to_owned()),
+            "hcl" => self.hcl = Some(val.to_owned()),
+            "ecl" => self.ecl = Some(val.to_owned()),
+            "pid" => self.pid = Some(val.to_owned()),
+            "cid" => self.cid = Some(val.to_owned()),
+            _ => return Err(Error::parse(line, name, "unknown passport field")),
         }
+        Ok(())
     }
+}
 
-    Passport {
-        byr,
-        iyr,
-        eyr,
-        hgt,
-        hcl,
-        ecl,
-        pid,
-        cid,
-    }
+fn parse_file(contents: &str) -> Result<Vec<Passport>> {
+    parse_groups(contents, parse_group)
 }
 
-fn parse_part(p: &str) -> (&str, &str) {
-    let parts: Vec<&str> = p.split(':').take(2).collect();
-    (parts[0], parts[1])
+fn parse_group(g: &str) -> Result<Passport> {
+    let mut passport = Passport::default();
+    for (i, line) in g.lines().enumerate() {
+        for part in line.split_whitespace() {
+            passport
+                .set_field(line, part)
+                .map_err(|e| e.offset_lines(i))?;
+        }
+    }
+    Ok(passport)
 }
 
-fn has_all_required_fields(p: &Passport) -> bool {
-    p.byr != None
-        && p.iyr != None
-        && p.eyr != None
-        && p.hgt != None
-        && p.hcl != None
-        && p.ecl != None
-        && p.pid != None
+fn parse_part<'a>(line: &str, p: &'a str) -> Result<(&'a str, &'a str)> {
+    let parts: Vec<&str> = p.splitn(2, ':').collect();
+    match parts[..] {
+        [name, val] => Ok((name, val)),
+        _ => Err(Error::parse(line, p, "expected a field like 'name:value'")),
+    }
 }
 
-fn solve_part_1(v: Vec<Passport>) -> usize {
-    v.into_iter().filter(has_all_required_fields).count()
+fn has_all_required_fields(p: &Passport) -> bool {
+    p.byr.is_some()
+        && p.iyr.is_some()
+        && p.eyr.is_some()
+        && p.hgt.is_some()
+        && p.hcl.is_some()
+        && p.ecl.is_some()
+        && p.pid.is_some()
 }
 
-pub fn part_1() -> usize {
-    let passports = read_file();
-    solve_part_1(passports)
+fn solve_part_1(v: &[Passport]) -> usize {
+    v.iter().filter(|p| has_all_required_fields(p)).count()
 }
 
 fn byr_valid(year: Option<u16>) -> bool {
     match year {
-        Some(y) => y >= 1920 && y <= 2002,
+        Some(y) => (1920..=2002).contains(&y),
         None => false,
     }
 }
 
 fn iyr_valid(year: Option<u16>) -> bool {
     match year {
-        Some(y) => y >= 2010 && y <= 2020,
+        Some(y) => (2010..=2020).contains(&y),
         None => false,
     }
 }
 
 fn eyr_valid(year: Option<u16>) -> bool {
     match year {
-        Some(y) => y >= 2020 && y <= 2030,
+        Some(y) => (2020..=2030).contains(&y),
         None => false,
     }
 }
 
-fn is_valid_height_str(height_str: &String) -> bool {
-    let mut numeric_part = height_str.chars().take_while(|c| c.is_digit(10));
-    let number: u16 = numeric_part.by_ref().collect::<String>().parse().unwrap();
-    let suffix = height_str[format!("{}", number).len()..].to_owned();
+fn is_valid_height_str(height_str: &str) -> bool {
+    let numeric_part: String = height_str
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    let number: u16 = match numeric_part.parse() {
+        Ok(number) => number,
+        Err(_) => return false,
+    };
+    let suffix = height_str[numeric_part.len()..].to_owned();
     match suffix.as_str() {
-        "cm" => number >= 150 && number <= 193,
-        "in" => number >= 59 && number <= 76,
+        "cm" => (150..=193).contains(&number),
+        "in" => (59..=76).contains(&number),
         _ => false,
     }
 }
@@ -138,10 +118,13 @@ fn hgt_valid(height: &Option<String>) -> bool {
     }
 }
 
-fn is_valid_colour_str(colour_str: &String) -> bool {
-    let first_char = &colour_str[0..1];
-    let after_hash = &colour_str[1..];
-    first_char == "#" && after_hash.len() == 6 && after_hash.chars().all(|c| c.is_digit(16))
+fn is_valid_colour_str(colour_str: &str) -> bool {
+    match colour_str.strip_prefix('#') {
+        Some(after_hash) => {
+            after_hash.len() == 6 && after_hash.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => false,
+    }
 }
 
 fn hcl_valid(colour: &Option<String>) -> bool {
@@ -154,7 +137,7 @@ fn hcl_valid(colour: &Option<String>) -> bool {
 fn ecl_valid(colour: &Option<String>) -> bool {
     match colour {
         Some(str) => {
-            let allowed = vec!["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
+            let allowed = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
             allowed.contains(&str.as_str())
         }
         None => false,
@@ -163,7 +146,7 @@ fn ecl_valid(colour: &Option<String>) -> bool {
 
 fn pid_valid(pid: &Option<String>) -> bool {
     match pid {
-        Some(id) => id.len() == 9 && id.chars().all(|c| c.is_digit(10)),
+        Some(id) => id.len() == 9 && id.chars().all(|c| c.is_ascii_digit()),
         None => false,
     }
 }
@@ -178,11 +161,117 @@ fn is_valid(p: &Passport) -> bool {
         && pid_valid(&p.pid)
 }
 
-fn solve_part_2(v: Vec<Passport>) -> usize {
-    v.into_iter().filter(is_valid).count()
+fn solve_part_2(v: &[Passport]) -> usize {
+    v.iter().filter(|p| is_valid(p)).count()
+}
+
+impl Solution for Day4 {
+    type Input = Vec<Passport>;
+
+    fn parse(&self, input: &str) -> Result<Vec<Passport>> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, passports: &Vec<Passport>) -> Result<Answer> {
+        Ok(solve_part_1(passports).into())
+    }
+
+    fn part_2(&self, passports: &Vec<Passport>) -> Result<Answer> {
+        Ok(solve_part_2(passports).into())
+    }
 }
 
-pub fn part_2() -> usize {
-    let passports = read_file();
-    solve_part_2(passports)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    const EXAMPLE: &str = "\
+ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
+byr:1937 iyr:2017 cid:147 hgt:183cm
+
+iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
+hcl:#cfa07d byr:1929
+
+hcl:#ae17e1 iyr:2013
+eyr:2024
+ecl:brn pid:760753704 byr:1931
+hgt:179cm
+
+hcl:#cfa07d eyr:2025 pid:166559648
+iyr:2011 ecl:brn hgt:59in
+";
+
+    const INVALID: &str = "\
+eyr:1972 cid:100
+hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926
+
+iyr:2019
+hcl:#602927 eyr:1967 hgt:170cm
+ecl:grn pid:012533040 byr:1946
+
+hcl:dab227 iyr:2012
+ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277
+
+hgt:59cm ecl:zzz
+eyr:2038 hcl:74454a iyr:2023
+pid:3556412378 byr:2007
+";
+
+    const VALID: &str = "\
+pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980
+hcl:#623a2f
+
+eyr:2029 ecl:blu cid:129 byr:1989
+iyr:2014 pid:896056539 hcl:#a97842 hgt:165cm
+
+hcl:#888785
+hgt:164cm byr:2001 iyr:2015 cid:88
+pid:545766238 ecl:hzl
+eyr:2022
+
+iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719
+";
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day4.run(1, EXAMPLE).unwrap().to_string(), "2");
+    }
+
+    #[test]
+    fn part_2_invalid_examples() {
+        assert_eq!(Day4.run(2, INVALID).unwrap().to_string(), "0");
+    }
+
+    #[test]
+    fn part_2_valid_examples() {
+        assert_eq!(Day4.run(2, VALID).unwrap().to_string(), "4");
+    }
+
+    #[test]
+    fn field_validators() {
+        assert!(byr_valid(Some(2002)));
+        assert!(!byr_valid(Some(2003)));
+        assert!(is_valid_height_str("60in"));
+        assert!(is_valid_height_str("190cm"));
+        assert!(!is_valid_height_str("190in"));
+        assert!(!is_valid_height_str("190"));
+        assert!(is_valid_colour_str("#123abc"));
+        assert!(!is_valid_colour_str("#123abz"));
+        assert!(!is_valid_colour_str("123abc"));
+        assert!(pid_valid(&Some(String::from("000000001"))));
+        assert!(!pid_valid(&Some(String::from("0123456789"))));
+    }
+
+    #[test]
+    fn errors_point_at_the_right_line() {
+        assert!(matches!(
+            Day4.parse("byr:1937\n\niyr:2017\nfoo:bar\n"),
+            Err(Error::Parse {
+                line: 4,
+                column: 1,
+                ..
+            })
+        ));
+    }
 }
@@ -1,52 +1,107 @@
-use std::fs::File;
-use std::io::prelude::*;
-
-fn read_file() -> Vec<u16> {
-    let mut file = File::open("./input/input5.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    contents.lines().map(read_as_binary).collect()
+use super::{Answer, Solution};
+use crate::error::{parse_lines, Error, Result};
+
+pub struct Day5;
+
+fn parse_file(contents: &str) -> Result<Vec<u16>> {
+    parse_lines(contents, read_as_binary)
 }
 
 // to get the seat ID, rather than split into row and column, we
 // can just use the fact that (row * 8 + column) is the same as
 // reading each line as a 10-digit binary number, with 'R' and 'B'
 // translating to 1 and 'L' and 'F' to 0
-fn read_as_binary(s: &str) -> u16 {
-    let translated = s
-        .replace("R", "1")
-        .replace("B", "1")
-        .replace("L", "0")
-        .replace("F", "0");
-    u16::from_str_radix(&translated, 2).unwrap()
-}
-
-fn solve_part_1(ids: &Vec<u16>) -> u16 {
-    *ids.into_iter().max().unwrap()
+fn read_as_binary(s: &str) -> Result<u16> {
+    if s.is_empty() || s.len() > 10 {
+        return Err(Error::parse(s, s, "expected a seat of up to 10 letters"));
+    }
+    s.char_indices().try_fold(0, |id, (i, c)| match c {
+        'R' | 'B' => Ok(id * 2 + 1),
+        'L' | 'F' => Ok(id * 2),
+        _ => Err(Error::parse(
+            s,
+            &s[i..i + c.len_utf8()],
+            "expected F, B, L or R",
+        )),
+    })
 }
 
-pub fn part_1() -> u16 {
-    let ids = read_file();
-    solve_part_1(&ids)
+fn solve_part_1(ids: &[u16]) -> Result<u16> {
+    match ids.iter().max() {
+        Some(&max) => Ok(max),
+        None => Err(Error::NoSolution(String::from(
+            "there are no boarding passes",
+        ))),
+    }
 }
 
-fn solve_part_2(ids: Vec<u16>) -> u16 {
-    let max = solve_part_1(&ids);
+fn solve_part_2(ids: &[u16]) -> Result<u16> {
+    let max = solve_part_1(ids)?;
     let all_possible = 1..(max + 1);
     let available = |id| !ids.contains(&id);
-    let mut answer: Option<u16> = None;
     for id in all_possible {
         let preceding = id - 1;
         let following = id + 1;
         if available(id) && !available(preceding) && !available(following) {
-            answer = Some(id);
-            break;
+            return Ok(id);
         }
     }
-    answer.unwrap()
+    Err(Error::NoSolution(String::from(
+        "there is no free seat between two taken ones",
+    )))
+}
+
+impl Solution for Day5 {
+    type Input = Vec<u16>;
+
+    fn parse(&self, input: &str) -> Result<Vec<u16>> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, ids: &Vec<u16>) -> Result<Answer> {
+        solve_part_1(ids).map(Answer::from)
+    }
+
+    fn part_2(&self, ids: &Vec<u16>) -> Result<Answer> {
+        solve_part_2(ids).map(Answer::from)
+    }
 }
 
-pub fn part_2() -> u16 {
-    let ids = read_file();
-    solve_part_2(ids)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    #[test]
+    fn seat_ids() {
+        assert_eq!(read_as_binary("FBFBBFFRLR").unwrap(), 357);
+        assert_eq!(read_as_binary("BFFFBBFRRR").unwrap(), 567);
+        assert_eq!(read_as_binary("FFFBBBFRRR").unwrap(), 119);
+        assert_eq!(read_as_binary("BBFFBBFRLL").unwrap(), 820);
+    }
+
+    #[test]
+    fn part_1_example() {
+        let input = "FBFBBFFRLR\nBFFFBBFRRR\nFFFBBBFRRR\nBBFFBBFRLL\n";
+        assert_eq!(Day5.run(1, input).unwrap().to_string(), "820");
+    }
+
+    #[test]
+    fn part_2_finds_the_gap() {
+        // seats 357 (FBFBBFFRLR) and 359 (FBFBBFFRRR) are taken, but not 358
+        let input = "FBFBBFFRLR\nFBFBBFFRRR\n";
+        assert_eq!(Day5.run(2, input).unwrap().to_string(), "358");
+    }
+
+    #[test]
+    fn rejects_unknown_letters() {
+        assert!(matches!(
+            Day5.parse("FBFBBFFRLR\nFBFBXFFRLR\n"),
+            Err(Error::Parse {
+                line: 2,
+                column: 5,
+                ..
+            })
+        ));
+    }
 }
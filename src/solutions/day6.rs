@@ -1,11 +1,12 @@
-use itertools::Itertools;
+use super::{Answer, Solution};
+use crate::error::{parse_groups, parse_lines, Error, Result};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::prelude::*;
+
+pub struct Day6;
 
 type PersonAnswers = HashMap<char, bool>;
 
-struct GroupAnswers {
+pub struct GroupAnswers {
     all: Vec<PersonAnswers>,
 }
 
@@ -39,49 +40,83 @@ impl GroupAnswers {
     }
 }
 
-fn read_file() -> Vec<GroupAnswers> {
-    let mut file = File::open("./input/input6.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    contents
-        .lines()
-        .group_by(|s| s.is_empty())
-        .into_iter()
-        .filter(|(k, _g)| !k)
-        .map(|(_k, g)| parse_group(g.collect()))
-        .collect()
+fn parse_file(contents: &str) -> Result<Vec<GroupAnswers>> {
+    parse_groups(contents, parse_group)
 }
 
-fn parse_group(group: Vec<&str>) -> GroupAnswers {
-    GroupAnswers {
-        all: group.into_iter().map(parse_answers).collect(),
-    }
+fn parse_group(group: &str) -> Result<GroupAnswers> {
+    Ok(GroupAnswers {
+        all: parse_lines(group, parse_answers)?,
+    })
 }
 
-fn parse_answers(s: &str) -> PersonAnswers {
+fn parse_answers(s: &str) -> Result<PersonAnswers> {
     let mut answers = HashMap::new();
-    for c in s.chars() {
+    for (i, c) in s.char_indices() {
+        if !c.is_ascii_lowercase() {
+            return Err(Error::parse(
+                s,
+                &s[i..i + c.len_utf8()],
+                "expected a question from a to z",
+            ));
+        }
         answers.insert(c, true);
     }
-    answers
+    Ok(answers)
 }
 
-fn solve_part_1(info: Vec<GroupAnswers>) -> usize {
+fn solve_part_1(info: &[GroupAnswers]) -> usize {
     info.iter()
         .map(|answers| answers.total_at_least_one())
         .sum()
 }
 
-pub fn part_1() -> usize {
-    let info = read_file();
-    solve_part_1(info)
+fn solve_part_2(info: &[GroupAnswers]) -> usize {
+    info.iter().map(|answers| answers.total_all()).sum()
 }
 
-fn solve_part_2(info: Vec<GroupAnswers>) -> usize {
-    info.iter().map(|answers| answers.total_all()).sum()
+impl Solution for Day6 {
+    type Input = Vec<GroupAnswers>;
+
+    fn parse(&self, input: &str) -> Result<Vec<GroupAnswers>> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, info: &Vec<GroupAnswers>) -> Result<Answer> {
+        Ok(solve_part_1(info).into())
+    }
+
+    fn part_2(&self, info: &Vec<GroupAnswers>) -> Result<Answer> {
+        Ok(solve_part_2(info).into())
+    }
 }
 
-pub fn part_2() -> usize {
-    let info = read_file();
-    solve_part_2(info)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    const EXAMPLE: &str = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb\n";
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day6.run(1, EXAMPLE).unwrap().to_string(), "11");
+    }
+
+    #[test]
+    fn part_2_example() {
+        assert_eq!(Day6.run(2, EXAMPLE).unwrap().to_string(), "6");
+    }
+
+    #[test]
+    fn rejects_unknown_questions() {
+        assert!(matches!(
+            Day6.parse("abc\n\na\nB\n"),
+            Err(Error::Parse {
+                line: 4,
+                column: 1,
+                ..
+            })
+        ));
+    }
 }
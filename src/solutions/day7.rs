@@ -1,47 +1,53 @@
+use super::{Answer, Solution};
+use crate::error::{parse_lines, parse_number, Error, Result};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::prelude::*;
+
+pub struct Day7;
 
 type BagDescription = String;
 
 type BagRules = HashMap<BagDescription, Vec<(usize, BagDescription)>>;
 
-fn read_file() -> BagRules {
-    let mut file = File::open("./input/input7.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    parse_rules(contents)
-}
-
-fn parse_rules(s: String) -> BagRules {
-    let mut rules = HashMap::new();
-    for l in s.lines() {
-        let (container, contained) = parse_line(l.to_string());
-        rules.insert(container, contained);
-    }
-    rules
+fn parse_rules(s: &str) -> Result<BagRules> {
+    Ok(parse_lines(s, parse_line)?.into_iter().collect())
 }
 
-fn parse_line(s: String) -> (BagDescription, Vec<(usize, BagDescription)>) {
+fn parse_line(s: &str) -> Result<(BagDescription, Vec<(usize, BagDescription)>)> {
     let parts: Vec<&str> = s.split(" bags contain ").collect();
+    if parts.len() != 2 {
+        return Err(Error::parse(
+            s,
+            s,
+            "expected a rule like 'X bags contain ...'",
+        ));
+    }
     let container = parts[0].to_string();
-    let contained = parts[1].to_string();
-    if contained == String::from("no other bags.") {
-        return (container, Vec::new());
+    let contained = parts[1];
+    if contained == "no other bags." {
+        return Ok((container, Vec::new()));
     }
     let all_parts = contained.split(", ");
-    (
+    Ok((
         container,
-        all_parts.map(|s| parse_part(s.to_string())).collect(),
-    )
+        all_parts
+            .map(|part| parse_part(s, part))
+            .collect::<Result<_>>()?,
+    ))
 }
 
-fn parse_part(s: String) -> (usize, BagDescription) {
-    let words: Vec<&str> = s.split(" ").collect();
+fn parse_part(line: &str, s: &str) -> Result<(usize, BagDescription)> {
+    let words: Vec<&str> = s.split(' ').collect();
     let num_words = words.len();
-    let number = words[0].parse().unwrap();
+    if num_words < 3 {
+        return Err(Error::parse(
+            line,
+            s,
+            "expected a bag like '2 shiny gold bags'",
+        ));
+    }
+    let number = parse_number(line, words[0])?;
     let bag_description = words[1..num_words - 1].join(" ");
-    (number, bag_description)
+    Ok((number, bag_description))
 }
 
 fn is_valid(target: &BagDescription, container: BagDescription, rules: &BagRules) -> bool {
@@ -65,41 +71,93 @@ fn is_valid(target: &BagDescription, container: BagDescription, rules: &BagRules
     }
 }
 
-fn solve_part_1(rules: BagRules) -> usize {
+fn solve_part_1(rules: &BagRules) -> usize {
     let want = String::from("shiny gold");
     let mut number = 0;
     for k in rules.keys() {
-        if is_valid(&want, k.to_string(), &rules) {
+        if is_valid(&want, k.to_string(), rules) {
             number += 1;
         }
     }
     number
 }
 
-pub fn part_1() -> usize {
-    let rules = read_file();
-    solve_part_1(rules)
-}
-
 fn get_bag_total(bag: BagDescription, rules: &BagRules) -> usize {
     let mut total = 0;
     let contained_in = rules.get(&bag);
-    match contained_in {
-        Some(bags) => {
-            for (num, inner_bag) in bags {
-                total += num * (get_bag_total(inner_bag.to_string(), rules) + 1);
-            }
+    if let Some(bags) = contained_in {
+        for (num, inner_bag) in bags {
+            total += num * (get_bag_total(inner_bag.to_string(), rules) + 1);
         }
-        None => {}
     }
     total
 }
 
-fn solve_part_2(rules: BagRules) -> usize {
-    get_bag_total(String::from("shiny gold"), &rules)
+fn solve_part_2(rules: &BagRules) -> usize {
+    get_bag_total(String::from("shiny gold"), rules)
 }
 
-pub fn part_2() -> usize {
-    let rules = read_file();
-    solve_part_2(rules)
+impl Solution for Day7 {
+    type Input = BagRules;
+
+    fn parse(&self, input: &str) -> Result<BagRules> {
+        parse_rules(input)
+    }
+
+    fn part_1(&self, rules: &BagRules) -> Result<Answer> {
+        Ok(solve_part_1(rules).into())
+    }
+
+    fn part_2(&self, rules: &BagRules) -> Result<Answer> {
+        Ok(solve_part_2(rules).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
+
+    const EXAMPLE: &str = "\
+light red bags contain 1 bright white bag, 2 muted yellow bags.
+dark orange bags contain 3 bright white bags, 4 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.
+dark olive bags contain 3 faded blue bags, 4 dotted black bags.
+vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
+faded blue bags contain no other bags.
+dotted black bags contain no other bags.
+";
+
+    const DEEP_EXAMPLE: &str = "\
+shiny gold bags contain 2 dark red bags.
+dark red bags contain 2 dark orange bags.
+dark orange bags contain 2 dark yellow bags.
+dark yellow bags contain 2 dark green bags.
+dark green bags contain 2 dark blue bags.
+dark blue bags contain 2 dark violet bags.
+dark violet bags contain no other bags.
+";
+
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day7.run(1, EXAMPLE).unwrap().to_string(), "4");
+    }
+
+    #[test]
+    fn part_2_examples() {
+        assert_eq!(Day7.run(2, EXAMPLE).unwrap().to_string(), "32");
+        assert_eq!(Day7.run(2, DEEP_EXAMPLE).unwrap().to_string(), "126");
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        assert!(matches!(
+            Day7.parse(
+                "faded blue bags contain no other bags.\ndark red bags hold 2 dark orange bags.\n"
+            ),
+            Err(Error::Parse { line: 2, .. })
+        ));
+    }
 }
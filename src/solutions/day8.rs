@@ -1,113 +1,83 @@
-use std::fs::File;
-use std::io::prelude::*;
+use super::{Answer, Solution};
+use crate::error::{Error, Result};
+use crate::vm::{self, Instruction, Machine, Status};
 
-#[derive(Clone)]
-enum Instruction {
-    Acc(isize),
-    Jmp(isize),
-    Nop(isize),
-}
+pub struct Day8;
 
-impl Instruction {
-    fn swap(&self) -> Self {
-        match self {
-            Instruction::Acc(num) => Instruction::Acc(*num),
-            Instruction::Jmp(num) => Instruction::Nop(*num),
-            Instruction::Nop(num) => Instruction::Jmp(*num),
-        }
+fn solve_part_1(program: &[Instruction]) -> Result<isize> {
+    let mut machine = Machine::new(program.to_vec());
+    match machine.run()? {
+        Status::Looped(_) => Ok(machine.acc()),
+        Status::JumpedOut => Err(Error::Unsupported(String::from(
+            "program jumped outside its instructions",
+        ))),
+        _ => Err(Error::NoSolution(String::from(
+            "program terminated without repeating",
+        ))),
     }
 }
 
-struct ProgramState {
-    program: Vec<Instruction>,
-    position: usize,
-    acc: isize,
+fn solve_part_2(program: &[Instruction]) -> Result<isize> {
+    match vm::repair(program)? {
+        Some(repair) => Ok(repair.acc),
+        None => Err(Error::NoSolution(String::from(
+            "no single jmp/nop swap makes the program terminate",
+        ))),
+    }
 }
 
-impl ProgramState {
-    fn new(program: Vec<Instruction>) -> ProgramState {
-        ProgramState {
-            program,
-            position: 0,
-            acc: 0,
-        }
+impl Solution for Day8 {
+    type Input = Vec<Instruction>;
+
+    fn parse(&self, input: &str) -> Result<Vec<Instruction>> {
+        vm::assemble(input)
     }
 
-    fn run_instruction(&mut self) -> () {
-        let instruction = &self.program[self.position];
-        match instruction {
-            Instruction::Acc(num) => {
-                self.acc += num;
-                self.position += 1;
-            }
-            Instruction::Jmp(num) => self.position = (self.position as isize + num) as usize,
-            Instruction::Nop(_) => {
-                self.position += 1;
-            }
-        }
+    fn part_1(&self, program: &Vec<Instruction>) -> Result<Answer> {
+        solve_part_1(program).map(Answer::from)
     }
 
-    fn run_program(&mut self) -> Result<isize, isize> {
-        let mut used: Vec<usize> = Vec::new();
-        while !used.contains(&self.position) {
-            if self.position == self.program.len() {
-                return Ok(self.acc);
-            }
-            used.push(self.position);
-            self.run_instruction();
-        }
-        Err(self.acc)
+    fn part_2(&self, program: &Vec<Instruction>) -> Result<Answer> {
+        solve_part_2(program).map(Answer::from)
     }
 }
 
-fn read_file() -> Vec<Instruction> {
-    let mut file = File::open("./input/input8.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    contents.lines().map(parse_line).collect()
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::Runner;
 
-fn parse_line(s: &str) -> Instruction {
-    let instr_name = &s[0..3];
-    let number: &isize = &s[4..].parse().unwrap();
-    match instr_name {
-        "acc" => Instruction::Acc(*number),
-        "jmp" => Instruction::Jmp(*number),
-        "nop" => Instruction::Nop(*number),
-        s => panic!("unexpected instruction: {}", s),
-    }
-}
+    const EXAMPLE: &str = "\
+nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6
+";
 
-fn solve_part_1(program: Vec<Instruction>) -> isize {
-    let mut state = ProgramState::new(program);
-    match state.run_program() {
-        Ok(_) => panic!("program terminated without repeating!"),
-        Err(acc) => acc,
+    #[test]
+    fn part_1_example() {
+        assert_eq!(Day8.run(1, EXAMPLE).unwrap().to_string(), "5");
     }
-}
-
-pub fn part_1() -> isize {
-    let program = read_file();
-    solve_part_1(program)
-}
 
-fn solve_part_2(program: Vec<Instruction>) -> isize {
-    for i in 0..program.len() {
-        let mut new_program = program.clone();
-        match &program[i] {
-            Instruction::Acc(_) => continue,
-            instr => new_program[i] = instr.swap(),
-        }
-        let mut state = ProgramState::new(new_program.to_vec());
-        match state.run_program() {
-            Ok(acc) => return acc,
-            Err(_) => (),
-        }
+    #[test]
+    fn part_2_example() {
+        assert_eq!(Day8.run(2, EXAMPLE).unwrap().to_string(), "8");
     }
-    panic!("no swap worked!");
-}
 
-pub fn part_2() -> isize {
-    let program = read_file();
-    solve_part_2(program)
+    #[test]
+    fn rejects_unknown_operations() {
+        assert!(matches!(
+            Day8.parse("nop +0\nmul +3\n"),
+            Err(Error::Parse {
+                line: 2,
+                column: 1,
+                ..
+            })
+        ));
+    }
 }
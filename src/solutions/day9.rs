@@ -1,11 +1,10 @@
-use std::fs::File;
-use std::io::prelude::*;
-
-fn read_file() -> Vec<usize> {
-    let mut file = File::open("./input/input9.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    contents.lines().map(|n| n.parse().unwrap()).collect()
+use super::{Answer, Solution};
+use crate::error::{parse_lines, parse_number, Error, Result};
+
+pub struct Day9;
+
+fn parse_file(contents: &str) -> Result<Vec<usize>> {
+    parse_lines(contents, |n| parse_number(n, n))
 }
 
 fn pair_sums_to(v: &[usize], n: usize) -> bool {
@@ -20,44 +19,87 @@ fn pair_sums_to(v: &[usize], n: usize) -> bool {
     false
 }
 
-fn solve_part_1(nums: &Vec<usize>) -> usize {
-    for i in 25..nums.len() {
+// how many numbers come before the first that's checked. The examples use a shorter one
+const PREAMBLE: usize = 25;
+
+fn solve_part_1(nums: &[usize], preamble: usize) -> Result<usize> {
+    for i in preamble..nums.len() {
         let n = nums[i];
-        let previous_twenty_five = &nums[i - 25..i];
-        if !pair_sums_to(previous_twenty_five, n) {
-            return n;
+        let previous = &nums[i - preamble..i];
+        if !pair_sums_to(previous, n) {
+            return Ok(n);
         }
     }
-    panic!("couldn't find a solution!");
-}
-
-pub fn part_1() -> usize {
-    let nums = read_file();
-    solve_part_1(&nums)
+    Err(Error::NoSolution(format!(
+        "every number is the sum of two of the {} before it",
+        preamble
+    )))
 }
 
-fn subset_sum(nums: &[usize], target: usize) -> &[usize] {
-    for i in 0..(nums.len() - 1) {
+// finds a run of at least two consecutive numbers adding up to the target
+fn subset_sum(nums: &[usize], target: usize) -> Result<&[usize]> {
+    for i in 0..nums.len() {
         let mut total = nums[i];
         let mut next_index = i;
-        while total < target {
+        while total < target && next_index + 1 < nums.len() {
             next_index += 1;
             total += nums[next_index];
         }
-        if total == target {
-            return &nums[i..(next_index + 1)];
+        if total == target && next_index > i {
+            return Ok(&nums[i..(next_index + 1)]);
         }
     }
-    panic!("couldn't find a subset with the right total!");
+    Err(Error::NoSolution(format!(
+        "no run of numbers adds up to {}",
+        target
+    )))
 }
 
-fn solve_part_2(nums: Vec<usize>) -> usize {
-    let target = solve_part_1(&nums);
-    let subset = subset_sum(&nums, target);
-    subset.iter().max().unwrap() + subset.iter().min().unwrap()
+fn solve_part_2(nums: &[usize], preamble: usize) -> Result<usize> {
+    let target = solve_part_1(nums, preamble)?;
+    let subset = subset_sum(nums, target)?;
+    // the subset has at least two numbers, so has a maximum and minimum
+    Ok(subset.iter().max().unwrap() + subset.iter().min().unwrap())
+}
+
+impl Solution for Day9 {
+    type Input = Vec<usize>;
+
+    fn parse(&self, input: &str) -> Result<Vec<usize>> {
+        parse_file(input)
+    }
+
+    fn part_1(&self, nums: &Vec<usize>) -> Result<Answer> {
+        solve_part_1(nums, PREAMBLE).map(Answer::from)
+    }
+
+    fn part_2(&self, nums: &Vec<usize>) -> Result<Answer> {
+        solve_part_2(nums, PREAMBLE).map(Answer::from)
+    }
 }
 
-pub fn part_2() -> usize {
-    let nums = read_file();
-    solve_part_2(nums)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+35\n20\n15\n25\n47\n40\n62\n55\n65\n95\n102\n117\n150\n182\n127\n219\n299\n277\n309\n576\n";
+
+    #[test]
+    fn part_1_example() {
+        let nums = Day9.parse(EXAMPLE).unwrap();
+        assert_eq!(solve_part_1(&nums, 5).unwrap(), 127);
+    }
+
+    #[test]
+    fn part_2_example() {
+        let nums = Day9.parse(EXAMPLE).unwrap();
+        assert_eq!(solve_part_2(&nums, 5).unwrap(), 62);
+    }
+
+    #[test]
+    fn short_input_has_no_answer() {
+        let nums = Day9.parse("1\n2\n3\n").unwrap();
+        assert!(matches!(solve_part_1(&nums, 5), Err(Error::NoSolution(_))));
+    }
 }
@@ -23,3 +23,275 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
+
+use crate::deduction::Explanation;
+use crate::error::{Error, Result};
+use crate::input::InputSource;
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// The answer to one part of a puzzle. Almost all are numbers, but a couple of days have
+/// strings for answers, and numbers too big for an i64 are kept as their digits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl From<i32> for Answer {
+    fn from(n: i32) -> Answer {
+        Answer::Number(n.into())
+    }
+}
+
+impl From<u16> for Answer {
+    fn from(n: u16) -> Answer {
+        Answer::Number(n.into())
+    }
+}
+
+// anything which doesn't fit in an i64 is kept as text, so no answer is ever wrapped round
+impl From<isize> for Answer {
+    fn from(n: isize) -> Answer {
+        match i64::try_from(n) {
+            Ok(n) => Answer::Number(n),
+            Err(_) => Answer::Text(n.to_string()),
+        }
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(n: i64) -> Answer {
+        Answer::Number(n)
+    }
+}
+
+impl From<u64> for Answer {
+    fn from(n: u64) -> Answer {
+        match i64::try_from(n) {
+            Ok(n) => Answer::Number(n),
+            Err(_) => Answer::Text(n.to_string()),
+        }
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Answer {
+        match i64::try_from(n) {
+            Ok(n) => Answer::Number(n),
+            Err(_) => Answer::Text(n.to_string()),
+        }
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Answer {
+        Answer::Text(s)
+    }
+}
+
+/// The interface every day's solution provides: turn the puzzle input into whatever form
+/// is most convenient, then answer each part from that.
+pub trait Solution {
+    type Input;
+
+    fn parse(&self, input: &str) -> Result<Self::Input>;
+
+    fn part_1(&self, input: &Self::Input) -> Result<Answer>;
+
+    // not every day has a second part which can be run - the registry below only
+    // asks for it when it exists
+    fn part_2(&self, _input: &Self::Input) -> Result<Answer> {
+        Err(Error::Unsupported(String::from("this day has no part 2")))
+    }
+
+    // days whose answer is worked out by deduction can show each step of it
+    fn explain(&self, _input: &Self::Input) -> Result<Option<Explanation>> {
+        Ok(None)
+    }
+}
+
+/// An object-safe view of a `Solution`, so that days with different input types can be
+/// kept together in the registry.
+pub trait Runner {
+    fn run(&self, part: u8, input: &str) -> Result<Answer> {
+        self.run_timed(part, input).map(|timed| timed.answer)
+    }
+
+    fn run_timed(&self, part: u8, input: &str) -> Result<Timed>;
+
+    fn explain(&self, input: &str) -> Result<Option<Explanation>>;
+}
+
+impl<S: Solution> Runner for S {
+    fn run_timed(&self, part: u8, input: &str) -> Result<Timed> {
+        let start = Instant::now();
+        let input = self.parse(input)?;
+        let parse = start.elapsed();
+
+        let start = Instant::now();
+        let answer = match part {
+            1 => self.part_1(&input),
+            _ => self.part_2(&input),
+        }?;
+        let solve = start.elapsed();
+
+        Ok(Timed {
+            answer,
+            parse,
+            solve,
+        })
+    }
+
+    fn explain(&self, input: &str) -> Result<Option<Explanation>> {
+        Solution::explain(self, &self.parse(input)?)
+    }
+}
+
+/// An answer, along with how long it took to parse the input and then to solve the part.
+pub struct Timed {
+    pub answer: Answer,
+    pub parse: Duration,
+    pub solve: Duration,
+}
+
+/// A single puzzle part which can be run, along with the day and part it answers.
+pub struct Entry {
+    pub day: u8,
+    pub part: u8,
+    pub solution: &'static dyn Runner,
+}
+
+impl Entry {
+    pub fn run(&self, source: &InputSource) -> Result<Answer> {
+        self.solution.run(self.part, &source.read(self.day)?)
+    }
+
+    /// The steps taken to work out the day's answer, if it's one which can show them.
+    pub fn explain(&self, source: &InputSource) -> Result<Option<Explanation>> {
+        self.solution.explain(&source.read(self.day)?)
+    }
+}
+
+/// All registered solutions, which can be looked up by day and part.
+pub struct Registry {
+    entries: Vec<Entry>,
+}
+
+impl Registry {
+    fn both_parts(&mut self, day: u8, solution: &'static dyn Runner) {
+        self.part_1_only(day, solution);
+        self.entries.push(Entry {
+            day,
+            part: 2,
+            solution,
+        });
+    }
+
+    fn part_1_only(&mut self, day: u8, solution: &'static dyn Runner) {
+        self.entries.push(Entry {
+            day,
+            part: 1,
+            solution,
+        });
+    }
+
+    pub fn get(&self, day: u8, part: u8) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.day == day && entry.part == part)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter()
+    }
+}
+
+/// Every solved puzzle part, in order. Day 25 has no second part at all.
+pub fn registry() -> Registry {
+    let mut registry = Registry {
+        entries: Vec::new(),
+    };
+    registry.both_parts(1, &day1::Day1);
+    registry.both_parts(2, &day2::Day2);
+    registry.both_parts(3, &day3::Day3);
+    registry.both_parts(4, &day4::Day4);
+    registry.both_parts(5, &day5::Day5);
+    registry.both_parts(6, &day6::Day6);
+    registry.both_parts(7, &day7::Day7);
+    registry.both_parts(8, &day8::Day8);
+    registry.both_parts(9, &day9::Day9);
+    registry.both_parts(10, &day10::Day10);
+    registry.both_parts(11, &day11::Day11);
+    registry.both_parts(12, &day12::Day12);
+    registry.both_parts(13, &day13::Day13);
+    registry.both_parts(14, &day14::Day14);
+    registry.both_parts(15, &day15::Day15);
+    registry.both_parts(16, &day16::Day16);
+    registry.both_parts(17, &day17::Day17);
+    registry.both_parts(18, &day18::Day18);
+    registry.both_parts(19, &day19::Day19);
+    registry.both_parts(20, &day20::Day20);
+    registry.both_parts(21, &day21::Day21);
+    registry.both_parts(22, &day22::Day22);
+    registry.both_parts(23, &day23::Day23);
+    registry.both_parts(24, &day24::Day24);
+    registry.part_1_only(25, &day25::Day25);
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answers::{self, Answers, Check};
+
+    #[test]
+    fn big_answers_are_not_wrapped() {
+        assert_eq!(Answer::from(u64::MAX).to_string(), "18446744073709551615");
+        assert_eq!(Answer::from(usize::MAX).to_string(), usize::MAX.to_string());
+        assert_eq!(Answer::from(1u64 << 40), Answer::Number(1 << 40));
+        assert_eq!(Answer::from(-3isize), Answer::Number(-3));
+    }
+
+    #[test]
+    fn every_day_is_registered() {
+        let registry = registry();
+        for day in 1..=25 {
+            assert!(registry.get(day, 1).is_some(), "day {} is missing", day);
+        }
+    }
+
+    // runs every registered part against the real inputs, which takes a while - so only
+    // when asked for with `cargo test --release -- --ignored`
+    #[test]
+    #[ignore]
+    fn real_inputs_give_recorded_answers() {
+        let answers = Answers::load(&answers::default_path()).unwrap();
+        let mut failures = Vec::new();
+        for entry in registry().entries() {
+            let failure = match answers.check(entry, &InputSource::Default) {
+                Check::Pass => continue,
+                Check::Fail { expected, actual } => {
+                    format!("expected {}, got {}", expected, actual)
+                }
+                Check::Missing(actual) => format!("no recorded answer, got {}", actual),
+                Check::Error(e) => e.to_string(),
+            };
+            failures.push(format!(
+                "day {}, part {}: {}",
+                entry.day, entry.part, failure
+            ));
+        }
+        assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+    }
+}
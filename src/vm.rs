@@ -0,0 +1,465 @@
+use crate::error::{parse_lines, parse_number, Error, Result};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// One instruction of the handheld console's boot code.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instruction {
+    // adds to the accumulator
+    Acc(isize),
+    // jumps relative to this instruction
+    Jmp(isize),
+    // does nothing
+    Nop(isize),
+}
+
+impl Instruction {
+    /// Swaps a jmp for a nop and back again, keeping the argument - acc is left alone.
+    pub fn swap(&self) -> Self {
+        match self {
+            Instruction::Acc(num) => Instruction::Acc(*num),
+            Instruction::Jmp(num) => Instruction::Nop(*num),
+            Instruction::Nop(num) => Instruction::Jmp(*num),
+        }
+    }
+}
+
+// written just as in the boot code, with the sign always shown
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Acc(num) => write!(f, "acc {:+}", num),
+            Instruction::Jmp(num) => write!(f, "jmp {:+}", num),
+            Instruction::Nop(num) => write!(f, "nop {:+}", num),
+        }
+    }
+}
+
+/// Reads boot code, with an instruction like `jmp -3` on each line.
+pub fn assemble(source: &str) -> Result<Vec<Instruction>> {
+    parse_lines(source, parse_instruction)
+}
+
+fn parse_instruction(s: &str) -> Result<Instruction> {
+    let (instr_name, number) = match s.split_once(' ') {
+        Some(parts) => parts,
+        None => return Err(Error::parse(s, s, "expected an instruction and a number")),
+    };
+    let number = parse_number(s, number)?;
+    match instr_name {
+        "acc" => Ok(Instruction::Acc(number)),
+        "jmp" => Ok(Instruction::Jmp(number)),
+        "nop" => Ok(Instruction::Nop(number)),
+        _ => Err(Error::parse(s, instr_name, "unexpected instruction")),
+    }
+}
+
+/// Writes a program back out as boot code, which `assemble` reads back in unchanged.
+pub fn disassemble(program: &[Instruction]) -> String {
+    program.iter().map(|instr| format!("{}\n", instr)).collect()
+}
+
+/// A record of one instruction having been run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceEntry {
+    // how many instructions had been run before this one
+    pub step: usize,
+    pub position: usize,
+    pub instruction: Instruction,
+    // the accumulator once the instruction had run
+    pub acc: isize,
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:>6}  {:>5}  {:<9} acc = {}",
+            self.step,
+            self.position,
+            self.instruction.to_string(),
+            self.acc
+        )
+    }
+}
+
+/// The instructions a program goes round and round, once it's in an infinite loop.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cycle {
+    // the positions of the instructions, in the order they're run, starting with the one
+    // which was about to be run a second time
+    pub positions: Vec<usize>,
+    // the step at which the first of them was first run
+    pub entered_at: usize,
+}
+
+/// Where a program has got to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Status {
+    // it can carry on running
+    Ready,
+    // it's stopped before the instruction at this position, as asked
+    Breakpoint(usize),
+    // it ran off the end of its instructions, which is how it's meant to finish
+    Terminated,
+    // it jumped somewhere which isn't the end or an instruction at all
+    JumpedOut,
+    // it's about to run an instruction for the second time, so would go on forever
+    Looped(Cycle),
+}
+
+/// The handheld console, running a program one instruction at a time. Every instruction
+/// run is traced, and the console stops as soon as it would run any instruction twice.
+pub struct Machine {
+    program: Vec<Instruction>,
+    position: usize,
+    acc: isize,
+    // the step each instruction was first run at, if it has been
+    first_run: Vec<Option<usize>>,
+    trace: Vec<TraceEntry>,
+    breakpoints: BTreeSet<usize>,
+}
+
+impl Machine {
+    pub fn new(program: Vec<Instruction>) -> Machine {
+        let first_run = vec![None; program.len()];
+        Machine {
+            program,
+            position: 0,
+            acc: 0,
+            first_run,
+            trace: Vec::new(),
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    pub fn program(&self) -> &[Instruction] {
+        &self.program
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn acc(&self) -> isize {
+        self.acc
+    }
+
+    pub fn trace(&self) -> &[TraceEntry] {
+        &self.trace
+    }
+
+    /// Starts the program again from the beginning, keeping its breakpoints.
+    pub fn reset(&mut self) {
+        let breakpoints = std::mem::take(&mut self.breakpoints);
+        *self = Machine::new(std::mem::take(&mut self.program));
+        self.breakpoints = breakpoints;
+    }
+
+    /// Sets a breakpoint at the given position, or clears it if there's one there already.
+    /// Gives whether there's now a breakpoint there.
+    pub fn toggle_breakpoint(&mut self, position: usize) -> bool {
+        if self.breakpoints.remove(&position) {
+            false
+        } else {
+            self.breakpoints.insert(position);
+            true
+        }
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = usize> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    /// Whether the program can go on from where it is, and if not why not.
+    pub fn status(&self) -> Status {
+        if self.position == self.program.len() {
+            return Status::Terminated;
+        }
+        if self.position > self.program.len() {
+            return Status::JumpedOut;
+        }
+        match self.first_run[self.position] {
+            Some(entered_at) => Status::Looped(Cycle {
+                positions: self.trace[entered_at..]
+                    .iter()
+                    .map(|entry| entry.position)
+                    .collect(),
+                entered_at,
+            }),
+            None => Status::Ready,
+        }
+    }
+
+    /// Runs a single instruction, unless the program has already stopped. An acc which
+    /// would take the accumulator out of range is an error, and leaves the console as it was.
+    pub fn step(&mut self) -> Result<Status> {
+        let status = self.status();
+        if status != Status::Ready {
+            return Ok(status);
+        }
+        let instruction = self.program[self.position];
+        let position = self.position;
+        match instruction {
+            Instruction::Acc(num) => {
+                self.acc = self.acc.checked_add(num).ok_or_else(|| {
+                    Error::Unsupported(format!(
+                        "acc {:+} at instruction {} takes the accumulator past {}",
+                        num, position, self.acc
+                    ))
+                })?;
+                self.position += 1;
+            }
+            // a jump before the start, or too far to work out, is left at the furthest
+            // position there is, which is caught as being out of range before anything else
+            // is run
+            Instruction::Jmp(num) => {
+                self.position = self.position.checked_add_signed(num).unwrap_or(usize::MAX)
+            }
+            Instruction::Nop(_) => {
+                self.position += 1;
+            }
+        }
+        self.first_run[position] = Some(self.trace.len());
+        self.trace.push(TraceEntry {
+            step: self.trace.len(),
+            position,
+            instruction,
+            acc: self.acc,
+        });
+        Ok(self.status())
+    }
+
+    /// Runs until the program stops, or reaches a breakpoint. The instruction it starts on
+    /// is always run, so that running again carries on past a breakpoint.
+    pub fn run(&mut self) -> Result<Status> {
+        loop {
+            match self.step()? {
+                Status::Ready if self.breakpoints.contains(&self.position) => {
+                    return Ok(Status::Breakpoint(self.position))
+                }
+                Status::Ready => (),
+                other => return Ok(other),
+            }
+        }
+    }
+}
+
+// where running the instruction at `position` goes next - the end of the program counts
+// as a place to go, but anywhere outside it doesn't
+fn successor(program: &[Instruction], position: usize, instruction: Instruction) -> Option<usize> {
+    let next = match instruction {
+        Instruction::Jmp(num) => position.checked_add_signed(num)?,
+        Instruction::Acc(_) | Instruction::Nop(_) => position + 1,
+    };
+    Some(next).filter(|&next| next <= program.len())
+}
+
+/// The control-flow graph of a program, where each instruction leads to exactly one other
+/// (or to the end). Position `program.len()` stands for the end itself.
+pub struct ControlFlow {
+    // which instructions can get to the end from where they are
+    reaches_end: Vec<bool>,
+}
+
+impl ControlFlow {
+    /*
+    Following the edges backwards from the end finds everything which can get there. Each
+    edge is looked at once, so this takes time in proportion to the length of the program.
+    */
+    pub fn new(program: &[Instruction]) -> ControlFlow {
+        let mut predecessors = vec![Vec::new(); program.len() + 1];
+        for (position, &instruction) in program.iter().enumerate() {
+            if let Some(next) = successor(program, position, instruction) {
+                predecessors[next].push(position);
+            }
+        }
+        let mut reaches_end = vec![false; program.len() + 1];
+        reaches_end[program.len()] = true;
+        let mut to_visit = vec![program.len()];
+        while let Some(position) = to_visit.pop() {
+            for &previous in &predecessors[position] {
+                if !reaches_end[previous] {
+                    reaches_end[previous] = true;
+                    to_visit.push(previous);
+                }
+            }
+        }
+        ControlFlow { reaches_end }
+    }
+
+    pub fn reaches_end(&self, position: usize) -> bool {
+        self.reaches_end.get(position).copied().unwrap_or(false)
+    }
+}
+
+/// The single swap of a jmp for a nop, or back again, which lets a looping program run to
+/// the end.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Repair {
+    pub position: usize,
+    pub original: Instruction,
+    pub replacement: Instruction,
+    // the accumulator at the end of the repaired program
+    pub acc: isize,
+    // every instruction the repaired program runs, in order
+    pub path: Vec<usize>,
+}
+
+/*
+The corrupted instruction must be one the program actually runs, or changing it would make
+no difference. So the program is followed from the start, and at each jmp or nop the
+question is whether the swapped instruction would lead somewhere that can reach the end.
+The first which does is the fix - the rest of the path from there can't pass back through
+it, since then the unrepaired program would have reached the end too.
+
+Finding what reaches the end, and following the program, each take linear time, as does
+running the repaired program to get the accumulator.
+*/
+pub fn repair(program: &[Instruction]) -> Result<Option<Repair>> {
+    let flow = ControlFlow::new(program);
+    if flow.reaches_end(0) {
+        // there's nothing to repair
+        return Ok(None);
+    }
+    let mut machine = Machine::new(program.to_vec());
+    loop {
+        let position = machine.position();
+        let original = match machine.status() {
+            Status::Ready => program[position],
+            // it's looped, or jumped out, without finding anything to swap
+            _ => return Ok(None),
+        };
+        let replacement = original.swap();
+        let fixes = replacement != original
+            && successor(program, position, replacement).is_some_and(|next| flow.reaches_end(next));
+        if fixes {
+            let mut repaired = program.to_vec();
+            repaired[position] = replacement;
+            let mut machine = Machine::new(repaired);
+            machine.run()?;
+            return Ok(Some(Repair {
+                position,
+                original,
+                replacement,
+                acc: machine.acc(),
+                path: machine.trace().iter().map(|entry| entry.position).collect(),
+            }));
+        }
+        machine.step()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6
+";
+
+    #[test]
+    fn assembling_round_trips() {
+        let program = assemble(EXAMPLE).unwrap();
+        assert_eq!(program[4], Instruction::Jmp(-3));
+        assert_eq!(disassemble(&program), EXAMPLE);
+    }
+
+    #[test]
+    fn finds_the_exact_loop() {
+        let mut machine = Machine::new(assemble(EXAMPLE).unwrap());
+        let cycle = Cycle {
+            positions: vec![1, 2, 6, 7, 3, 4],
+            entered_at: 1,
+        };
+        assert_eq!(machine.run().unwrap(), Status::Looped(cycle));
+        assert_eq!(machine.acc(), 5);
+        assert_eq!(machine.trace().len(), 7);
+    }
+
+    #[test]
+    fn stepping_and_breakpoints() {
+        let mut machine = Machine::new(assemble(EXAMPLE).unwrap());
+        assert_eq!(machine.step().unwrap(), Status::Ready);
+        assert_eq!(machine.position(), 1);
+        assert!(machine.toggle_breakpoint(7));
+        assert_eq!(machine.run().unwrap(), Status::Breakpoint(7));
+        assert_eq!(machine.acc(), 2);
+        // carrying on runs the instruction at the breakpoint
+        assert!(matches!(machine.run().unwrap(), Status::Looped(_)));
+
+        machine.reset();
+        assert_eq!((machine.position(), machine.acc()), (0, 0));
+        assert_eq!(machine.breakpoints().collect::<Vec<_>>(), vec![7]);
+        assert!(!machine.toggle_breakpoint(7));
+    }
+
+    #[test]
+    fn what_reaches_the_end() {
+        let flow = ControlFlow::new(&assemble(EXAMPLE).unwrap());
+        let reaching: Vec<usize> = (0..=9).filter(|&p| flow.reaches_end(p)).collect();
+        assert_eq!(reaching, vec![8, 9]);
+    }
+
+    #[test]
+    fn repairs_the_corrupted_instruction() {
+        let fix = repair(&assemble(EXAMPLE).unwrap()).unwrap().unwrap();
+        assert_eq!(fix.position, 7);
+        assert_eq!(fix.original, Instruction::Jmp(-4));
+        assert_eq!(fix.replacement, Instruction::Nop(-4));
+        assert_eq!(fix.acc, 8);
+        assert_eq!(fix.path, vec![0, 1, 2, 6, 7, 8]);
+    }
+
+    #[test]
+    fn nothing_to_repair() {
+        // this already terminates
+        assert_eq!(
+            repair(&assemble("nop +0\nacc +1\n").unwrap()).unwrap(),
+            None
+        );
+        // and no swap along the way gets this out of its loop
+        assert_eq!(
+            repair(&assemble("acc +1\njmp -1\njmp -1\n").unwrap()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn stops_at_the_end() {
+        let mut machine = Machine::new(assemble("acc +2\njmp +2\nacc +9\nnop -1\n").unwrap());
+        assert_eq!(machine.run().unwrap(), Status::Terminated);
+        assert_eq!(machine.acc(), 2);
+        // nothing more happens once it's stopped
+        assert_eq!(machine.step().unwrap(), Status::Terminated);
+
+        let mut machine = Machine::new(assemble("nop +0\njmp -5\n").unwrap());
+        assert_eq!(machine.run().unwrap(), Status::JumpedOut);
+    }
+
+    #[test]
+    fn jumps_too_far_to_work_out() {
+        let program = format!("nop +0\njmp +{}\n", isize::MAX);
+        let mut machine = Machine::new(assemble(&program).unwrap());
+        assert_eq!(machine.run().unwrap(), Status::JumpedOut);
+        assert_eq!(machine.step().unwrap(), Status::JumpedOut);
+    }
+
+    #[test]
+    fn accumulator_overflow() {
+        let program = format!("acc +{}\nacc +1\n", isize::MAX);
+        let mut machine = Machine::new(assemble(&program).unwrap());
+        assert!(matches!(machine.run(), Err(Error::Unsupported(_))));
+        // the instruction which would have overflowed wasn't run
+        assert_eq!((machine.position(), machine.acc()), (1, isize::MAX));
+        assert_eq!(machine.trace().len(), 1);
+    }
+}